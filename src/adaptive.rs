@@ -0,0 +1,47 @@
+/// Sizes each break from the focus time actually worked with
+/// `--adaptive-break`, so a focus session cut short earns a shorter break
+/// and a long one a longer break.
+#[derive(Debug, Clone, Copy)]
+pub struct AdaptiveBreak {
+    /// The break is the focus time worked divided by this
+    pub divisor: f64,
+    /// Bounds on the break, in seconds
+    pub min: u64,
+    pub max: u64,
+}
+
+impl AdaptiveBreak {
+    /// The break after `worked` seconds of focus, to the nearest second.
+    pub fn length(&self, worked: u64) -> u64 {
+        ((worked as f64 / self.divisor).round() as u64).clamp(self.min, self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIFTH: AdaptiveBreak = AdaptiveBreak {
+        divisor: 5.0,
+        min: 2 * 60,
+        max: 15 * 60,
+    };
+
+    #[test]
+    fn the_break_is_the_work_divided() {
+        assert_eq!(FIFTH.length(25 * 60), 5 * 60);
+        assert_eq!(FIFTH.length(52 * 60), 624);
+    }
+
+    #[test]
+    fn a_short_session_gets_the_minimum() {
+        assert_eq!(FIFTH.length(5 * 60), 2 * 60);
+        assert_eq!(FIFTH.length(0), 2 * 60);
+    }
+
+    #[test]
+    fn a_long_session_gets_the_maximum() {
+        assert_eq!(FIFTH.length(100 * 60), 15 * 60);
+        assert_eq!(FIFTH.length(u64::MAX), 15 * 60);
+    }
+}
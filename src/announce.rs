@@ -0,0 +1,61 @@
+use crate::{Phase, PomodoroTimer, TimerState};
+use std::time::{Duration, Instant};
+
+/// Plain-text lines about the timer for a screen reader watching the
+/// terminal, with `--headless --accessible`: one on every change of state,
+/// and a reminder every so often while a phase runs.
+pub struct Announcer {
+    /// Between reminders; `None` announces changes only
+    every: Option<Duration>,
+    last: Option<(TimerState, usize)>,
+    last_at: Instant,
+}
+
+impl Announcer {
+    pub fn new(every: Option<Duration>, now: Instant) -> Self {
+        Self {
+            every,
+            last: None,
+            last_at: now,
+        }
+    }
+
+    /// The line to print now, if there is one.
+    pub fn due(&mut self, timer: &PomodoroTimer, now: Instant) -> Option<String> {
+        let state = (timer.state, timer.position);
+        let reminder = timer.state != TimerState::Paused
+            && self.every.is_some_and(|every| now.duration_since(self.last_at) >= every);
+        if self.last == Some(state) && !reminder {
+            return None;
+        }
+        self.last = Some(state);
+        self.last_at = now;
+        Some(describe(timer))
+    }
+}
+
+/// e.g. `Focus: 15 minutes remaining`, or `Paused. Break: 30 seconds remaining`
+fn describe(timer: &PomodoroTimer) -> String {
+    let (phase, remaining) = match timer.current_phase() {
+        Phase::Focus => ("Focus", timer.focus_remaining),
+        Phase::Break if timer.mandatory_rest => ("Mandatory rest", timer.break_remaining),
+        Phase::Break => ("Break", timer.break_remaining),
+    };
+    let left = if remaining < 60 {
+        count(remaining, "second")
+    } else {
+        count(remaining.div_ceil(60), "minute")
+    };
+    match timer.state {
+        TimerState::Paused => format!("Paused. {}: {} remaining", phase, left),
+        _ => format!("{}: {} remaining", phase, left),
+    }
+}
+
+fn count(n: u64, unit: &str) -> String {
+    if n == 1 {
+        format!("1 {}", unit)
+    } else {
+        format!("{} {}s", n, unit)
+    }
+}
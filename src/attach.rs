@@ -0,0 +1,104 @@
+use crate::{
+    icons::Icons,
+    input::Keys,
+    status::{self, Request, Status},
+    Phase, PomodoroTimer,
+};
+use crossterm::{
+    event::KeyCode,
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Alignment, Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame, Terminal,
+};
+use std::{io, path::Path, time::Duration};
+
+const REFRESH: Duration = Duration::from_millis(200);
+
+/// Why the client stopped showing the timer.
+pub enum Detach {
+    /// The timer keeps running in the background
+    Left,
+    /// The timer ended, stopped by the client or on its own
+    Stopped,
+}
+
+/// Show the background timer answering at `path` until `q` or `d` detaches,
+/// passing the control keys on to it. Only the timer's own process keeps
+/// state; this screen is redrawn from its status.
+pub fn run(path: &Path, icons: &Icons) -> io::Result<Detach> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let result = run_loop(&mut terminal, path, icons);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+fn run_loop(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, path: &Path, icons: &Icons) -> io::Result<Detach> {
+    let mut keys = Keys::new();
+    let mut request = Request::Status;
+    loop {
+        let Some(status) = status::send(path, request)? else {
+            return Ok(Detach::Stopped);
+        };
+        if request == Request::Stop {
+            return Ok(Detach::Stopped);
+        }
+        terminal.draw(|f| draw(f, &status, icons))?;
+
+        request = match keys.next(REFRESH)? {
+            Some(KeyCode::Char('q' | 'd')) => return Ok(Detach::Left),
+            Some(KeyCode::Char('x')) => Request::Stop,
+            Some(KeyCode::Char(' ')) => Request::TogglePause,
+            Some(KeyCode::Char('s')) => Request::Skip,
+            Some(KeyCode::Char('r')) => Request::Reset,
+            _ => Request::Status,
+        };
+    }
+}
+
+fn draw(f: &mut Frame, status: &Status, icons: &Icons) {
+    let [digits_area, controls_area] = Layout::vertical([Constraint::Min(7), Constraint::Length(3)])
+        .margin(1)
+        .areas(f.area());
+
+    let (color, icon) = match status.phase {
+        Phase::Focus => (Color::Green, icons.focus),
+        Phase::Break => (Color::Yellow, icons.break_),
+    };
+    let style = if status.paused {
+        Style::default().fg(Color::DarkGray)
+    } else {
+        Style::default().fg(color).add_modifier(Modifier::BOLD)
+    };
+    let lines: Vec<Line> = PomodoroTimer::get_ascii_digits(&PomodoroTimer::format_time(status.remaining_secs))
+        .into_iter()
+        .map(|line| Line::from(Span::styled(line, style)))
+        .collect();
+    let mut title = format!("{} {} · cycle {}", status.state(), icon, status.cycle);
+    if let Some(task) = &status.task {
+        title.push_str(&format!(" · {}", task));
+    }
+    let digits = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .block(Block::default().title(title).borders(Borders::ALL).style(style));
+    f.render_widget(digits, digits_area);
+
+    let controls = Paragraph::new("SPACE: Pause/Resume | S: Skip | R: Reset | Q/D: Detach | X: Stop the timer")
+        .style(Style::default().fg(Color::Cyan))
+        .alignment(Alignment::Center)
+        .block(Block::default().title("Background timer").borders(Borders::ALL));
+    f.render_widget(controls, controls_area);
+}
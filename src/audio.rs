@@ -0,0 +1,553 @@
+use crate::{
+    noise::{Noise, NoiseKind},
+    sound::{self, Sound},
+    Transition,
+};
+use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
+use serde::Deserialize;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
+
+/// Length of the fade in and out applied to every notification.
+const ENVELOPE: Duration = Duration::from_millis(150);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+type Completion = oneshot::Sender<Result<(), String>>;
+
+enum Command {
+    Play {
+        source: Box<dyn Source<Item = f32> + Send>,
+        volume: f32,
+        done: Option<Completion>,
+        /// Notifications play one at a time, with the other sounds mixed in
+        serial: Option<Overlap>,
+    },
+}
+
+/// What happens to a notification that arrives while another still plays,
+/// `overlap` under `[sound]`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Overlap {
+    /// It waits for the one playing to finish
+    #[default]
+    Queue,
+    /// The one playing stops and it starts right away
+    Cancel,
+}
+
+/// What a sound is for, each with its own volume.
+#[derive(Debug, Clone, Copy)]
+pub enum Channel {
+    /// The sound at the end of a phase
+    Notification,
+    /// The get-ready countdown
+    Tick,
+    /// Background noise
+    Ambient,
+    /// The halfway tone and the soft alarm of `--manual`
+    Warning,
+}
+
+/// How loud each channel plays, from 0 to 1, set under `[sound.volume]`.
+/// What reaches the speakers is the channel's volume times `master`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Volume {
+    pub master: f32,
+    pub notification: f32,
+    pub tick: f32,
+    pub ambient: f32,
+    pub warning: f32,
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            notification: 1.0,
+            tick: 1.0,
+            ambient: 0.1,
+            warning: 1.0,
+        }
+    }
+}
+
+impl Volume {
+    pub fn of(&self, channel: Channel) -> f32 {
+        let volume = match channel {
+            Channel::Notification => self.notification,
+            Channel::Tick => self.tick,
+            Channel::Ambient => self.ambient,
+            Channel::Warning => self.warning,
+        };
+        self.master.clamp(0.0, 1.0) * volume.clamp(0.0, 1.0)
+    }
+
+    /// Every setting by its key, for checking the config.
+    pub fn settings(&self) -> [(&'static str, f32); 5] {
+        [
+            ("master", self.master),
+            ("notification", self.notification),
+            ("tick", self.tick),
+            ("ambient", self.ambient),
+            ("warning", self.warning),
+        ]
+    }
+}
+
+/// Handle to the audio thread, which owns the one output stream for the whole
+/// run. Notifications get a fade envelope and duck the background noise
+/// while they play.
+pub struct Audio {
+    commands: mpsc::Sender<Command>,
+    thread: JoinHandle<()>,
+    ambient_gain: Arc<AtomicU32>,
+    /// Sounds sent to the thread and not finished yet, for the debug pane
+    pending: Arc<AtomicUsize>,
+    volume: Volume,
+    /// Nothing plays, whatever the channel; see `set_muted`
+    muted: bool,
+    pub ambient_enabled: bool,
+}
+
+impl Audio {
+    /// Start the audio thread, with a background noise channel if requested.
+    /// Without an audio device everything still works, silently; playback
+    /// requests then complete with an error.
+    pub fn start(noise: Option<NoiseKind>, volume: Volume) -> Self {
+        let (commands, receiver) = mpsc::channel();
+        let ambient_gain = Arc::new(AtomicU32::new(0.0f32.to_bits()));
+        let noise_source = noise.map(|kind| (kind, Arc::clone(&ambient_gain)));
+        let pending = Arc::new(AtomicUsize::new(0));
+        let finished = Arc::clone(&pending);
+        let thread = thread::spawn(move || run(receiver, noise_source, &finished));
+
+        Self {
+            commands,
+            thread,
+            ambient_gain,
+            pending,
+            volume,
+            muted: false,
+            ambient_enabled: noise.is_some(),
+        }
+    }
+
+    /// Applies to sounds started from now on, and to the background noise
+    /// straight away with the next `set_ambient_playing`.
+    pub fn set_volume(&mut self, volume: Volume) {
+        self.volume = volume;
+    }
+
+    /// Silence every channel, the background noise with the next
+    /// `set_ambient_playing`. Sounds asked for while muted are skipped, not
+    /// held back until unmuting.
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    /// Raise or lower the master volume by `step`, returning the new one.
+    pub fn adjust_master(&mut self, step: f32) -> f32 {
+        // Rounded so ten steps down from full really reach silence
+        self.volume.master = ((self.volume.master + step).clamp(0.0, 1.0) * 100.0).round() / 100.0;
+        self.volume.master
+    }
+
+    /// Sounds queued or still playing, for the debug pane.
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::Relaxed)
+    }
+
+    /// Queue the notification for a transition. The receiver resolves once it
+    /// has finished playing; it can simply be dropped to fire and forget.
+    pub fn play_notification(&self, sound: &Sound, transition: Transition) -> oneshot::Receiver<Result<(), String>> {
+        self.play(sound::notification_source(sound, transition), Channel::Notification, Some(sound.overlap))
+    }
+
+    pub fn play_fanfare(&self, sound: &Sound) {
+        self.play(sound::fanfare(), Channel::Notification, Some(sound.overlap));
+    }
+
+    pub fn play_soft_tone(&self) {
+        self.play(sound::soft_tone(), Channel::Warning, None);
+    }
+
+    pub fn play_countdown_tone(&self, go: bool) {
+        self.play(sound::countdown_tone(go), Channel::Tick, None);
+    }
+
+    /// Fade the background noise towards its volume or silence.
+    pub fn set_ambient_playing(&self, playing: bool) {
+        let target = if playing && self.ambient_enabled && !self.muted {
+            self.volume.of(Channel::Ambient)
+        } else {
+            0.0
+        };
+        self.ambient_gain.store(target.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Stop the background noise and let notifications still playing finish,
+    /// waiting at most `limit` in case the audio device is stuck.
+    pub fn finish(self, limit: Duration) {
+        let Self { commands, thread, .. } = self;
+        drop(commands);
+        let deadline = Instant::now() + limit;
+        while !thread.is_finished() && Instant::now() < deadline {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn play(
+        &self,
+        source: Box<dyn Source<Item = f32> + Send>,
+        channel: Channel,
+        serial: Option<Overlap>,
+    ) -> oneshot::Receiver<Result<(), String>> {
+        let (done, receiver) = oneshot::channel();
+        if self.muted {
+            let _ = done.send(Ok(()));
+            return receiver;
+        }
+        self.pending.fetch_add(1, Ordering::Relaxed);
+        let sent = self.commands.send(Command::Play {
+            source,
+            volume: self.volume.of(channel),
+            done: Some(done),
+            serial,
+        });
+        if sent.is_err() {
+            self.pending.fetch_sub(1, Ordering::Relaxed);
+        }
+        receiver
+    }
+}
+
+struct Playing {
+    sink: Sink,
+    done: Option<Completion>,
+}
+
+/// A notification waiting for its turn.
+struct Queued {
+    source: Box<dyn Source<Item = f32> + Send>,
+    volume: f32,
+    done: Option<Completion>,
+}
+
+/// Notifications one at a time, so two transitions close together play one
+/// after the other (or the newer one alone) instead of over each other.
+struct Notifications<'a> {
+    playing: Option<Playing>,
+    waiting: VecDeque<Queued>,
+    /// The audio handle's count of sounds not finished yet
+    pending: &'a AtomicUsize,
+}
+
+impl<'a> Notifications<'a> {
+    fn new(pending: &'a AtomicUsize) -> Self {
+        Self {
+            playing: None,
+            waiting: VecDeque::new(),
+            pending,
+        }
+    }
+
+    fn request<F>(&mut self, queued: Queued, overlap: Overlap, start: F)
+    where
+        F: Fn(Box<dyn Source<Item = f32> + Send>, f32) -> Result<Sink, String>,
+    {
+        if overlap == Overlap::Cancel {
+            // Cut short rather than failed, so it completes without an error
+            if let Some(playing) = self.playing.take() {
+                playing.sink.stop();
+                self.finish(playing.done, Ok(()));
+            }
+            while let Some(queued) = self.waiting.pop_front() {
+                self.finish(queued.done, Ok(()));
+            }
+        }
+        self.waiting.push_back(queued);
+        self.poll(start);
+    }
+
+    /// Start the next notification once the one playing has finished.
+    fn poll<F>(&mut self, start: F)
+    where
+        F: Fn(Box<dyn Source<Item = f32> + Send>, f32) -> Result<Sink, String>,
+    {
+        if self.playing.as_ref().is_some_and(|playing| !playing.sink.empty()) {
+            return;
+        }
+        if let Some(playing) = self.playing.take() {
+            self.finish(playing.done, Ok(()));
+        }
+        while let Some(queued) = self.waiting.pop_front() {
+            match start(queued.source, queued.volume) {
+                Ok(sink) => {
+                    self.playing = Some(Playing { sink, done: queued.done });
+                    return;
+                }
+                Err(e) => self.finish(queued.done, Err(e)),
+            }
+        }
+    }
+
+    fn is_busy(&self) -> bool {
+        self.playing.is_some() || !self.waiting.is_empty()
+    }
+
+    fn finish(&self, done: Option<Completion>, result: Result<(), String>) {
+        self.pending.fetch_sub(1, Ordering::Relaxed);
+        if let Some(done) = done {
+            let _ = done.send(result);
+        }
+    }
+}
+
+fn run(commands: mpsc::Receiver<Command>, noise: Option<(NoiseKind, Arc<AtomicU32>)>, pending: &AtomicUsize) {
+    let device = OutputStream::try_default()
+        .map_err(|e| format!("no audio output device available: {}", e));
+    let ducked = Arc::new(AtomicBool::new(false));
+
+    let mut ambient = match (&device, noise) {
+        (Ok((_, handle)), Some((kind, gain))) => Sink::try_new(handle).ok().inspect(|sink| {
+            sink.append(Noise::new(kind, gain, Arc::clone(&ducked)));
+        }),
+        _ => None,
+    };
+
+    let start = |source, volume| start(&device, source, volume);
+    let mut notifications = Notifications::new(pending);
+    let mut playing: Vec<Playing> = Vec::new();
+    let mut closing = false;
+    while !(closing && playing.is_empty() && !notifications.is_busy()) {
+        match commands.recv_timeout(POLL_INTERVAL) {
+            Ok(Command::Play { source, volume, done, serial: Some(overlap) }) => {
+                notifications.request(Queued { source, volume, done }, overlap, start);
+            }
+            Ok(Command::Play { source, volume, mut done, serial: None }) => match start(source, volume) {
+                Ok(sink) => playing.push(Playing { sink, done }),
+                Err(e) => {
+                    pending.fetch_sub(1, Ordering::Relaxed);
+                    if let Some(done) = done.take() {
+                        let _ = done.send(Err(e));
+                    }
+                }
+            },
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                // The timer is gone: drain what is playing, then stop
+                drop(ambient.take());
+                closing = true;
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+
+        playing.retain_mut(|notification| {
+            if !notification.sink.empty() {
+                return true;
+            }
+            if let Some(done) = notification.done.take() {
+                let _ = done.send(Ok(()));
+            }
+            pending.fetch_sub(1, Ordering::Relaxed);
+            false
+        });
+        notifications.poll(start);
+        ducked.store(!playing.is_empty() || notifications.is_busy(), Ordering::Relaxed);
+    }
+}
+
+fn start(
+    device: &Result<(OutputStream, OutputStreamHandle), String>,
+    source: Box<dyn Source<Item = f32> + Send>,
+    volume: f32,
+) -> Result<Sink, String> {
+    let (_, handle) = device.as_ref().map_err(Clone::clone)?;
+    let sink = Sink::try_new(handle).map_err(|e| format!("cannot play audio: {}", e))?;
+    sink.set_volume(volume);
+    sink.append(Envelope::new(source, ENVELOPE));
+    Ok(sink)
+}
+
+/// Fades a source in over its first `fade` and, when its length is known,
+/// out over its last `fade`, so notifications never start or stop with a click.
+struct Envelope<S> {
+    source: S,
+    fade_samples: u64,
+    total_samples: Option<u64>,
+    position: u64,
+}
+
+impl<S: Source<Item = f32>> Envelope<S> {
+    fn new(source: S, fade: Duration) -> Self {
+        let samples_per_sec = source.sample_rate() as u64 * source.channels() as u64;
+        let to_samples = |duration: Duration| duration.as_millis() as u64 * samples_per_sec / 1000;
+        let total_samples = source.total_duration().map(to_samples);
+        // Short sounds get a proportionally shorter fade so they stay audible
+        let fade_samples = to_samples(fade).min(total_samples.map_or(u64::MAX, |total| total / 4));
+        Self {
+            source,
+            fade_samples: fade_samples.max(1),
+            total_samples,
+            position: 0,
+        }
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Envelope<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.source.next()?;
+        let fade_in = (self.position as f32 / self.fade_samples as f32).min(1.0);
+        let fade_out = self.total_samples.map_or(1.0, |total| {
+            (total.saturating_sub(self.position) as f32 / self.fade_samples as f32).min(1.0)
+        });
+        self.position += 1;
+        Some(sample * fade_in.min(fade_out))
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Envelope<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::{buffer::SamplesBuffer, queue::SourcesQueueOutput};
+    use std::cell::RefCell;
+
+    /// A short notification and the receiver that hears when it is done.
+    fn chime() -> (Queued, oneshot::Receiver<Result<(), String>>) {
+        let (done, receiver) = oneshot::channel();
+        let queued = Queued {
+            source: Box::new(SamplesBuffer::new(1, 8000, vec![0.5; 80])),
+            volume: 1.0,
+            done: Some(done),
+        };
+        (queued, receiver)
+    }
+
+    /// Stands in for the output device: each sound started gets a sink
+    /// whose samples the test pulls, as the device would.
+    #[derive(Default)]
+    struct Device {
+        outputs: RefCell<Vec<SourcesQueueOutput<f32>>>,
+    }
+
+    impl Device {
+        fn start(&self, source: Box<dyn Source<Item = f32> + Send>, volume: f32) -> Result<Sink, String> {
+            let (sink, output) = Sink::new_idle();
+            sink.set_volume(volume);
+            sink.append(source);
+            self.outputs.borrow_mut().push(output);
+            Ok(sink)
+        }
+
+        fn started(&self) -> usize {
+            self.outputs.borrow().len()
+        }
+
+        /// Play the `index`th sound started to its end.
+        fn play_out(&self, index: usize) {
+            self.outputs.borrow_mut()[index].by_ref().take(1000).for_each(drop);
+        }
+    }
+
+    #[test]
+    fn a_queued_notification_waits_for_the_one_playing() {
+        let pending = AtomicUsize::new(2);
+        let device = Device::default();
+        let start = |source, volume| device.start(source, volume);
+        let mut notifications = Notifications::new(&pending);
+        let (first, mut first_done) = chime();
+        let (second, mut second_done) = chime();
+
+        notifications.request(first, Overlap::Queue, start);
+        notifications.request(second, Overlap::Queue, start);
+        notifications.poll(start);
+        assert_eq!(device.started(), 1);
+        assert!(first_done.try_recv().is_err());
+
+        device.play_out(0);
+        notifications.poll(start);
+        assert_eq!(device.started(), 2);
+        assert_eq!(first_done.try_recv(), Ok(Ok(())));
+        assert!(second_done.try_recv().is_err());
+
+        device.play_out(1);
+        notifications.poll(start);
+        assert_eq!(second_done.try_recv(), Ok(Ok(())));
+        assert!(!notifications.is_busy());
+        assert_eq!(pending.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn a_cancelling_notification_stops_the_one_playing_first() {
+        let pending = AtomicUsize::new(3);
+        let device = Device::default();
+        let start = |source, volume| device.start(source, volume);
+        let mut notifications = Notifications::new(&pending);
+        let (first, mut first_done) = chime();
+        let (second, mut second_done) = chime();
+        let (third, mut third_done) = chime();
+
+        notifications.request(first, Overlap::Queue, start);
+        notifications.request(second, Overlap::Queue, start);
+        notifications.request(third, Overlap::Cancel, start);
+        // The one playing and the one waiting both complete before the newest starts
+        assert_eq!(first_done.try_recv(), Ok(Ok(())));
+        assert_eq!(second_done.try_recv(), Ok(Ok(())));
+        assert_eq!(device.started(), 2);
+        assert!(third_done.try_recv().is_err());
+        assert_eq!(pending.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn a_notification_that_fails_to_start_lets_the_next_one_play() {
+        let pending = AtomicUsize::new(2);
+        let device = Device::default();
+        let fails = AtomicBool::new(true);
+        let start = |source, volume| {
+            if fails.swap(false, Ordering::Relaxed) {
+                return Err("no audio output device available".to_string());
+            }
+            device.start(source, volume)
+        };
+        let mut notifications = Notifications::new(&pending);
+        let (first, mut first_done) = chime();
+        let (second, mut second_done) = chime();
+
+        notifications.waiting.extend([first, second]);
+        notifications.poll(start);
+        assert!(matches!(first_done.try_recv(), Ok(Err(_))));
+        assert_eq!(device.started(), 1);
+        assert!(second_done.try_recv().is_err());
+    }
+}
@@ -0,0 +1,71 @@
+use ratatui::{style::Color, Frame};
+use std::time::{Duration, Instant};
+
+/// Lifetime pomodoro counts worth celebrating.
+pub const MILESTONES: [u64; 5] = [10, 50, 100, 500, 1000];
+/// How long the confetti stays on screen.
+const LENGTH: Duration = Duration::from_secs(2);
+/// How often the confetti moves down a row.
+pub const FRAME: Duration = Duration::from_millis(100);
+
+const PIECES: [char; 6] = ['*', '+', 'o', '~', 'x', '.'];
+const COLORS: [Color; 6] = [Color::Red, Color::Yellow, Color::Green, Color::Cyan, Color::Magenta, Color::Blue];
+
+/// The milestone that going from `before` to `after` pomodoros reaches, if
+/// any. One already celebrated never counts again, even if the count drops
+/// and climbs back past it, e.g. after old log entries were pruned.
+pub fn reached(before: u64, after: u64, celebrated: u64) -> Option<u64> {
+    MILESTONES
+        .iter()
+        .rev()
+        .copied()
+        .find(|&milestone| before < milestone && milestone <= after && milestone > celebrated)
+}
+
+/// Colored characters falling over the whole window for a couple of seconds.
+pub struct Confetti {
+    start: Instant,
+    /// Where the pieces start; the same for every frame so they fall rather
+    /// than flicker
+    seed: u64,
+}
+
+impl Confetti {
+    pub fn new(now: Instant, seed: u64) -> Self {
+        Self {
+            start: now,
+            // Xorshift never leaves zero
+            seed: seed.max(1),
+        }
+    }
+
+    pub fn is_over(&self, now: Instant) -> bool {
+        now.duration_since(self.start) >= LENGTH
+    }
+
+    /// Drawn last, over everything else, leaving the cells between pieces alone.
+    pub fn render(&self, f: &mut Frame, now: Instant) {
+        let area = f.area();
+        if area.is_empty() {
+            return;
+        }
+        let fallen = (now.duration_since(self.start).as_millis() / FRAME.as_millis()) as u64;
+        let mut state = self.seed;
+        let mut random = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let buffer = f.buffer_mut();
+        for _ in 0..(area.area() / 12).max(1) {
+            let x = area.x + (random() % u64::from(area.width)) as u16;
+            let y = area.y + ((random() % u64::from(area.height) + fallen) % u64::from(area.height)) as u16;
+            let piece = PIECES[(random() % PIECES.len() as u64) as usize];
+            let color = COLORS[(random() % COLORS.len() as u64) as usize];
+            if let Some(cell) = buffer.cell_mut((x, y)) {
+                cell.set_char(piece).set_fg(color);
+            }
+        }
+    }
+}
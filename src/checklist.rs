@@ -0,0 +1,146 @@
+use crate::session_log::ChecklistTally;
+use crossterm::event::KeyCode;
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+/// The most items that number keys can reach.
+pub const MAX_KEYED: usize = 9;
+
+/// Things to do during each break, like "stand" or "water", from
+/// `checklist` in the config. Ticked off with the number keys, or with
+/// `j`/`k` and space.
+pub struct Checklist {
+    items: Vec<String>,
+    done: Vec<bool>,
+    /// Picked with `j`/`k`; until then space pauses as usual
+    cursor: Option<usize>,
+    /// Left unticked at the end of the last break, to remind about
+    left_over: Vec<usize>,
+}
+
+impl Checklist {
+    /// `None` for an empty list, which turns the checklist off.
+    pub fn new(items: Vec<String>) -> Option<Self> {
+        if items.is_empty() {
+            return None;
+        }
+        Some(Self {
+            done: vec![false; items.len()],
+            items,
+            cursor: None,
+            left_over: Vec::new(),
+        })
+    }
+
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
+    /// Start a break with nothing ticked. Returns a reminder of what the
+    /// last break left undone, if anything.
+    pub fn begin(&mut self) -> Option<String> {
+        self.done.fill(false);
+        self.cursor = None;
+        let left_over: Vec<&str> = self.left_over.drain(..).map(|i| self.items[i].as_str()).collect();
+        (!left_over.is_empty()).then(|| format!("Still to do: {}", left_over.join(", ")))
+    }
+
+    /// End the break; what is left unticked comes back at the next one.
+    pub fn finish(&mut self) -> ChecklistTally {
+        self.left_over = (0..self.items.len()).filter(|&i| !self.done[i]).collect();
+        self.cursor = None;
+        ChecklistTally {
+            done: self.done.iter().filter(|&&done| done).count() as u32,
+            of: self.items.len() as u32,
+        }
+    }
+
+    /// Tick items with the number keys, or pick one with `j`/`k` (or the
+    /// arrow keys) and tick it with space. Returns whether the key was used.
+    pub fn handle(&mut self, key: KeyCode) -> bool {
+        match key {
+            KeyCode::Char(digit @ '1'..='9') => self.toggle(digit as usize - '1' as usize),
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.step(true);
+                true
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.step(false);
+                true
+            }
+            KeyCode::Char(' ') => self.toggle_cursor(),
+            _ => false,
+        }
+    }
+
+    /// Tick or untick an item by its position, from 0. Returns whether there is one.
+    fn toggle(&mut self, index: usize) -> bool {
+        let Some(done) = self.done.get_mut(index) else {
+            return false;
+        };
+        *done = !*done;
+        true
+    }
+
+    /// Move the cursor down (`j`) or up (`k`), starting at the first item.
+    fn step(&mut self, down: bool) {
+        let last = self.items.len() - 1;
+        self.cursor = Some(match self.cursor {
+            None => 0,
+            Some(i) if down => (i + 1).min(last),
+            Some(i) => i.saturating_sub(1),
+        });
+    }
+
+    /// Tick or untick the item under the cursor. Returns `false` when
+    /// nothing is picked, so space can pause instead.
+    fn toggle_cursor(&mut self) -> bool {
+        self.cursor.is_some_and(|i| self.toggle(i))
+    }
+
+    /// The items as `[x] 1 stand`, packed into lines no wider than `width`
+    /// without splitting any; one too wide gets a line to itself.
+    pub fn lines(&self, width: u16, ascii: bool) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        let mut line: Vec<Span> = Vec::new();
+        let mut used = 0;
+        for (i, item) in self.items.iter().enumerate() {
+            let mark = match (self.done[i], ascii) {
+                (true, true) => "[x]",
+                (true, false) => "☑",
+                (false, true) => "[ ]",
+                (false, false) => "☐",
+            };
+            let text = if i < MAX_KEYED {
+                format!("{} {} {}", mark, i + 1, item)
+            } else {
+                format!("{} {}", mark, item)
+            };
+            let mut style = if self.done[i] {
+                Style::default().fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT)
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            if self.cursor == Some(i) {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            let text_width = text.chars().count();
+            let gap = if line.is_empty() { 0 } else { 2 };
+            if !line.is_empty() && used + gap + text_width > width as usize {
+                lines.push(Line::from(std::mem::take(&mut line)));
+                used = 0;
+            } else if gap > 0 {
+                line.push(Span::raw("  "));
+                used += gap;
+            }
+            line.push(Span::styled(text, style));
+            used += text_width;
+        }
+        if !line.is_empty() {
+            lines.push(Line::from(line));
+        }
+        lines
+    }
+}
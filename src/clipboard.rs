@@ -0,0 +1,67 @@
+use std::{
+    env,
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+/// Clipboard tools tried in order when the terminal cannot take OSC 52.
+const TOOLS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip.exe", &[]),
+];
+
+/// Copy `text` to the system clipboard. The OSC 52 escape goes through the
+/// terminal itself, so it needs no native library and works over SSH and
+/// inside tmux; it is written to `terminal`. Terminals known to ignore it
+/// fall back to a clipboard tool.
+pub fn copy(terminal: &mut impl Write, text: &str) -> Result<(), String> {
+    if osc52_supported() {
+        return write!(terminal, "\x1b]52;c;{}\x07", base64(text.as_bytes()))
+            .and_then(|()| terminal.flush())
+            .map_err(|e| format!("cannot copy: {}", e));
+    }
+    TOOLS
+        .iter()
+        .find_map(|(program, args)| run_tool(program, args, text).ok())
+        .ok_or_else(|| "cannot copy: the terminal lacks OSC 52 and no clipboard tool was found".to_string())
+}
+
+/// The Linux console and dumb terminals print the escape as garbage or
+/// drop it; nearly everything else in use handles it.
+fn osc52_supported() -> bool {
+    !matches!(env::var("TERM").as_deref(), Ok("linux" | "dumb") | Err(_))
+}
+
+fn run_tool(program: &str, args: &[&str], text: &str) -> io::Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    child.stdin.take().expect("stdin is piped").write_all(text.as_bytes())?;
+    if child.wait()?.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("{} failed", program)))
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| group | (byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
@@ -0,0 +1,683 @@
+use crate::{
+    gradient::Gradient,
+    icons::IconSet,
+    journal::JournalConfig,
+    session_log::Rounding,
+    audio::{Overlap, Volume},
+    sound::{self, SoundTheme},
+    suggestions::{self, Order},
+    template::Template,
+    Phase,
+};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fmt,
+    fs,
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Written when the config is opened for editing and does not exist yet.
+const TEMPLATE: &str = r##"# pomo configuration; every section is optional.
+
+[timer]
+# focus = 25
+# break = 5
+# Switched between with `p` while the timer runs
+# presets = [
+#     { name = "sprint", focus = 15, break = 3 },
+#     { name = "marathon", focus = 50, break = 10 },
+# ]
+# A repeating sequence of named phases, used instead of focus and break
+# schedule = [
+#     { name = "deep work", mins = 50, kind = "focus" },
+#     { name = "stretch", mins = 5, kind = "break" },
+# ]
+# Picked with --category or cycled with `c`; sessions are uncategorized otherwise
+# categories = ["work", "study", "personal"]
+# daily_goal = 8  # focus sessions a day, shown as "Today: 5/8" in the header
+# microbreak_interval = 20  # minutes of focus between 20-second reminders to look away
+
+[sound]
+# enabled = true
+# muted = false  # silence every sound but keep the flash and desktop notifications; m toggles it
+# escalate = true  # with --manual, repeat the alarm until a key is pressed
+# nudge = false  # with --manual, replay the sound with each nudge after a break
+# theme = "marimba"
+# melody = [[660, 150], [880, 150], [1320, 300]]  # [Hz, ms] notes played instead of the beep theme's triple beep
+# overlap = "queue"  # a notification while another plays waits its turn; "cancel" stops the older one
+# file = "/path/to/sound.wav"
+# focus_end = "/path/to/gong.wav"  # per transition, instead of file
+# break_end = "/path/to/chime.wav"
+
+# From 0 to 1; each plays at its volume times master, which `v`/`V` change
+[sound.volume]
+# master = 1.0
+# notification = 1.0  # the end of a phase
+# tick = 1.0  # the get-ready countdown of --start-mode countdown
+# ambient = 0.1  # --noise, unless --noise-volume is given
+# warning = 1.0  # the halfway tone and the soft alarm of --manual
+
+[ui]
+# layout = "header,focus,break,controls"
+# icons = "emoji"  # or "nerdfont", "ascii"
+# focus_gradient = ["#9cff57", "#1b5e20"]  # top and bottom digit colors
+# break_gradient = ["#ffd54f", "#e65100"]
+# animations = true
+# clock = "24h"  # or "12h", "off"
+# note_key = "i"  # jot down what interrupted you, saved to the event log
+# nudge_every = 60  # with --manual, flash this often (seconds) once a break is over; 0 turns it off
+# announce_every = 5  # with --headless --accessible, print the time left this often (minutes); 0 for changes only
+# suggestions = ["Stand up and stretch", "Refill your water"]  # one per break; [] turns them off
+# suggestions_file = "/path/to/suggestions.txt"  # one per line, instead of the list
+# suggestions_order = "shuffle"  # or "in-order"
+# checklist = ["stand", "water", "stretch"]  # ticked off with 1-9 during breaks
+# celebrations = true  # confetti and a fanfare at 10, 50, 100, 500 and 1000 pomodoros
+
+[format]
+# Placeholders: {state} {phase} {remaining} {focus_remaining} {break_remaining}
+# {remaining_secs} {cycle} {cycles} {task} {ends_at} {today_minutes} {focus_minutes} {now} {muted},
+# with an optional width like {task:<20}
+# status = "{state} {remaining} until {ends_at}"  # pomo status
+# summary = "{focus_minutes} min on {task} by {now}"  # copied with y
+
+[log]
+# keep_days = 365  # drop older entries at startup; keeps everything when unset
+# sync_command = "git -C ~/pomo-log add -A && git -C ~/pomo-log commit -m pomo && git -C ~/pomo-log push"
+# rounding = "nearest-minute"  # or "up", "down"; logged lengths only, the timer keeps exact time
+
+# [webhook]
+# url = "https://ntfy.sh/my-pomo"
+
+# [slack]
+# token = "xoxp-..."
+
+[journal]
+# enabled = false  # ask "How did today go?" once a day
+# after = "18:00"  # the first quit from this time on asks, as does meeting the daily goal
+# file = "/path/to/journal.md"  # journal.md next to the event log by default
+"##;
+
+/// Every key pomo understands, per table (`""` is the top level). Tables
+/// not listed here, like `webhook.headers`, take arbitrary keys.
+const KNOWN_KEYS: &[(&str, &[&str])] = &[
+    ("", &["timer", "webhook", "slack", "sound", "ui", "format", "log", "journal"]),
+    ("journal", &["enabled", "after", "file"]),
+    (
+        "timer",
+        &["focus", "break", "presets", "schedule", "categories", "daily_goal", "microbreak_interval"],
+    ),
+    ("webhook", &["url", "headers"]),
+    ("slack", &["token", "enabled"]),
+    (
+        "sound",
+        &[
+            "enabled",
+            "muted",
+            "escalate",
+            "nudge",
+            "theme",
+            "melody",
+            "volume",
+            "overlap",
+            "file",
+            "focus_end",
+            "break_end",
+        ],
+    ),
+    ("sound.volume", &["master", "notification", "tick", "ambient", "warning"]),
+    ("format", &["status", "summary"]),
+    ("log", &["keep_days", "sync_command", "rounding"]),
+    (
+        "ui",
+        &[
+            "layout",
+            "icons",
+            "focus_gradient",
+            "break_gradient",
+            "animations",
+            "clock",
+            "note_key",
+            "nudge_every",
+            "announce_every",
+            "suggestions",
+            "suggestions_file",
+            "suggestions_order",
+            "checklist",
+            "celebrations",
+        ],
+    ),
+];
+
+#[derive(clap::Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Validate the config file and list any problems
+    Check,
+    /// Print which config file is used
+    Path,
+}
+
+/// Something wrong with the config file. Fatal problems mean the file
+/// cannot be used at all; the rest are keys that are silently ignored.
+#[derive(Debug)]
+pub struct Problem {
+    pub line: Option<usize>,
+    pub message: String,
+    pub fatal: bool,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Settings read from `config.toml`. Every section is optional so a missing
+/// or partial file behaves like the defaults.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub timer: TimerConfig,
+    pub webhook: Option<WebhookConfig>,
+    pub slack: Option<SlackConfig>,
+    pub sound: SoundConfig,
+    pub ui: UiConfig,
+    pub format: FormatConfig,
+    pub log: LogConfig,
+    pub journal: JournalConfig,
+}
+
+/// Custom text for outputs that print the timer's state; each has a
+/// built-in format used when its template is not set.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FormatConfig {
+    /// `pomo status`
+    pub status: Option<Template>,
+    /// What `y` copies to the clipboard
+    pub summary: Option<Template>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// Days of history to keep in the event log
+    pub keep_days: Option<u32>,
+    /// Shell command run after sessions are logged, and once more on exit
+    pub sync_command: Option<String>,
+    /// Same as `--log-rounding`, which takes precedence
+    pub rounding: Option<Rounding>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    /// Same format as `--layout`
+    pub layout: Option<String>,
+    /// Detected from the terminal when not set
+    pub icons: Option<IconSet>,
+    /// Digit colors on true-color terminals
+    pub focus_gradient: Option<Gradient>,
+    pub break_gradient: Option<Gradient>,
+    /// Fade the timer blocks when the phase changes
+    pub animations: bool,
+    /// The wall clock in the header
+    pub clock: ClockFormat,
+    /// Opens the interruption note input
+    pub note_key: char,
+    /// Seconds between reminders once a break is over with `--manual`
+    pub nudge_every: u64,
+    /// Minutes between spoken-style updates with `--headless --accessible`
+    pub announce_every: u64,
+    /// Shown one at a time during breaks; the built-in ones when not set,
+    /// none when empty
+    pub suggestions: Option<Vec<String>>,
+    /// One suggestion per line, instead of `suggestions`
+    pub suggestions_file: Option<PathBuf>,
+    pub suggestions_order: Order,
+    /// Ticked off during each break; none when empty
+    pub checklist: Vec<String>,
+    /// Confetti, a fanfare and a notification at lifetime pomodoro milestones
+    pub celebrations: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+pub enum ClockFormat {
+    #[default]
+    #[serde(rename = "24h")]
+    TwentyFourHour,
+    #[serde(rename = "12h")]
+    TwelveHour,
+    #[serde(rename = "off")]
+    Off,
+}
+
+impl UiConfig {
+    /// `announce_every` as a duration; `None` when it is turned off.
+    pub fn announce_every(&self) -> Option<Duration> {
+        (self.announce_every > 0).then(|| Duration::from_secs(self.announce_every * 60))
+    }
+
+    /// The break suggestions from `suggestions_file`, `suggestions` or the
+    /// built-in list, in that order; blank lines in the file are skipped.
+    pub fn suggestion_list(&self) -> Result<Vec<String>, String> {
+        if let Some(path) = &self.suggestions_file {
+            let contents = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            return Ok(contents.lines().map(str::trim).filter(|line| !line.is_empty()).map(String::from).collect());
+        }
+        Ok(match &self.suggestions {
+            Some(list) => list.clone(),
+            None => suggestions::DEFAULTS.map(String::from).to_vec(),
+        })
+    }
+}
+
+impl ClockFormat {
+    /// The chrono format string, or `None` when the clock is hidden.
+    pub fn pattern(self) -> Option<&'static str> {
+        match self {
+            ClockFormat::TwentyFourHour => Some("%H:%M"),
+            ClockFormat::TwelveHour => Some("%-I:%M %p"),
+            ClockFormat::Off => None,
+        }
+    }
+}
+
+/// Phase lengths in minutes, used when not given on the command line.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TimerConfig {
+    pub focus: Option<u64>,
+    #[serde(rename = "break")]
+    pub break_time: Option<u64>,
+    pub presets: Vec<Preset>,
+    pub schedule: Vec<ScheduleEntry>,
+    /// What focus sessions can be tagged with
+    pub categories: Vec<String>,
+    /// Focus sessions to aim for each day
+    pub daily_goal: Option<u32>,
+    /// Minutes of uninterrupted focus between micro-breaks; 0 turns them off
+    pub microbreak_interval: Option<u64>,
+}
+
+impl TimerConfig {
+    pub fn microbreak_interval(&self) -> Option<Duration> {
+        self.microbreak_interval.filter(|&minutes| minutes > 0).map(|minutes| Duration::from_secs(minutes * 60))
+    }
+}
+
+/// One phase of a custom schedule.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScheduleEntry {
+    pub name: String,
+    pub mins: u64,
+    pub kind: Phase,
+}
+
+/// Named phase lengths in minutes, cycled through with `p` or picked with
+/// `--preset`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub focus: u64,
+    #[serde(rename = "break")]
+    pub break_time: u64,
+    /// Replaces every fourth break; only the built-in presets set it
+    #[serde(skip)]
+    pub long_break: Option<u64>,
+}
+
+impl Preset {
+    /// Well-known rhythms available to `--preset` without any config.
+    pub fn built_in() -> Vec<Preset> {
+        let preset = |name: &str, focus, break_time, long_break| Preset {
+            name: name.to_string(),
+            focus,
+            break_time,
+            long_break,
+        };
+        vec![
+            preset("pomodoro", 25, 5, Some(15)),
+            preset("52-17", 52, 17, None),
+            preset("ultradian", 90, 20, None),
+        ]
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct SoundConfig {
+    pub enabled: bool,
+    /// Start muted; `m` overrides it from then on, until it changes here
+    pub muted: bool,
+    pub escalate: bool,
+    pub nudge: bool,
+    pub theme: Option<SoundTheme>,
+    /// `[frequency_hz, duration_ms]` pairs, checked by `sound::parse_melody`
+    pub melody: Vec<(f64, f64)>,
+    pub volume: Volume,
+    /// When a notification arrives while another still plays
+    pub overlap: Overlap,
+    pub file: Option<PathBuf>,
+    pub focus_end: Option<PathBuf>,
+    pub break_end: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SlackConfig {
+    pub token: String,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            layout: None,
+            icons: None,
+            focus_gradient: None,
+            break_gradient: None,
+            animations: true,
+            clock: ClockFormat::default(),
+            note_key: 'i',
+            nudge_every: 60,
+            announce_every: 5,
+            suggestions: None,
+            suggestions_file: None,
+            suggestions_order: Order::default(),
+            checklist: Vec::new(),
+            celebrations: true,
+        }
+    }
+}
+
+impl Default for SoundConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            muted: false,
+            escalate: true,
+            nudge: false,
+            theme: None,
+            melody: Vec::new(),
+            volume: Volume::default(),
+            overlap: Overlap::default(),
+            file: None,
+            focus_end: None,
+            break_end: None,
+        }
+    }
+}
+
+impl Config {
+    /// Default location, e.g. `~/.config/pomo/config.toml` on Linux.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("pomo").join("config.toml"))
+    }
+
+    /// The config file, created from a commented template if it is missing,
+    /// so there is something to open in an editor.
+    pub fn ensure_exists(path: Option<&Path>) -> io::Result<PathBuf> {
+        let (path, _) = Self::resolve(path)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "cannot locate the config directory"))?;
+        if !path.exists() {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, TEMPLATE)?;
+        }
+        Ok(path)
+    }
+
+    /// The file `load` reads, whether or not it exists.
+    pub fn path(path: Option<&Path>) -> Option<PathBuf> {
+        Self::resolve(path).map(|(path, _)| path)
+    }
+
+    /// The file to read and whether it was asked for explicitly.
+    fn resolve(path: Option<&Path>) -> Option<(PathBuf, bool)> {
+        match path {
+            Some(path) => Some((path.to_path_buf(), true)),
+            None => Self::default_path().map(|path| (path, false)),
+        }
+    }
+
+    /// Load the config from `path`, or from the default location when no path
+    /// is given. A missing default file is not an error.
+    pub fn load(path: Option<&Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let Some((path, explicit)) = Self::resolve(path) else {
+            return Ok(Self::default());
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents)
+                .map_err(|e| format!("invalid config {}: {}", path.display(), e).into()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !explicit => Ok(Self::default()),
+            Err(e) => Err(format!("cannot read config {}: {}", path.display(), e).into()),
+        }
+    }
+}
+
+/// Notices edits to the config file by polling its modification time, so a
+/// running timer can reload it.
+pub struct ConfigWatcher {
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    last_check: Instant,
+    forced: bool,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: Option<&Path>) -> Self {
+        let path = Config::resolve(path).map(|(path, _)| path);
+        let modified = path.as_deref().and_then(modified_time);
+        Self {
+            path,
+            modified,
+            last_check: Instant::now(),
+            forced: false,
+        }
+    }
+
+    /// Report a change on the next check, whether or not the file changed.
+    pub fn force(&mut self) {
+        self.forced = true;
+    }
+
+    /// Whether the file was changed, created or removed since the last check.
+    /// Only looks at the file every couple of seconds.
+    pub fn changed(&mut self, now: Instant) -> bool {
+        if now.duration_since(self.last_check) < WATCH_INTERVAL && !self.forced {
+            return false;
+        }
+        self.last_check = now;
+        let modified = self.path.as_deref().and_then(modified_time);
+        let changed = modified != std::mem::replace(&mut self.modified, modified);
+        changed || std::mem::take(&mut self.forced)
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Run every check on the config file that `load` would read.
+pub fn check(path: Option<&Path>) -> Vec<Problem> {
+    let Some((path, explicit)) = Config::resolve(path) else {
+        return Vec::new();
+    };
+    match fs::read_to_string(&path) {
+        Ok(contents) => validate(&contents),
+        Err(e) if e.kind() == io::ErrorKind::NotFound && !explicit => Vec::new(),
+        Err(e) => vec![Problem {
+            line: None,
+            message: format!("cannot read {}: {}", path.display(), e),
+            fatal: true,
+        }],
+    }
+}
+
+/// Syntax and type errors are fatal; unknown keys are reported with the
+/// closest valid key as a suggestion.
+pub fn validate(contents: &str) -> Vec<Problem> {
+    let fatal = |e: toml::de::Error| Problem {
+        line: e.span().map(|span| line_of(contents, span.start)),
+        message: e.message().trim().replace('\n', ", "),
+        fatal: true,
+    };
+    let table: toml::Table = match toml::from_str(contents) {
+        Ok(table) => table,
+        Err(e) => return vec![fatal(e)],
+    };
+
+    let mut problems = Vec::new();
+    for &(section, known) in KNOWN_KEYS {
+        let keys = section
+            .split('.')
+            .filter(|part| !part.is_empty())
+            .try_fold(&table, |table, part| table.get(part)?.as_table());
+        for key in keys.into_iter().flat_map(|keys| keys.keys()) {
+            if known.contains(&key.as_str()) {
+                continue;
+            }
+            let full_key = if section.is_empty() { key.clone() } else { format!("{}.{}", section, key) };
+            let mut message = format!("unknown key `{}`", full_key);
+            if let Some(suggestion) = closest(key, known) {
+                message.push_str(&format!(", did you mean `{}`?", suggestion));
+            }
+            problems.push(Problem {
+                line: find_key_line(contents, section, key),
+                message,
+                fatal: false,
+            });
+        }
+    }
+
+    match toml::from_str::<Config>(contents) {
+        Ok(config) => {
+            // A melody that cannot be played falls back to the beeps
+            if let Err(e) = sound::parse_melody(&config.sound.melody) {
+                problems.push(Problem {
+                    line: find_key_line(contents, "sound", "melody"),
+                    message: format!("`sound.melody` ignored: {}", e),
+                    fatal: false,
+                });
+            }
+            if let Err(e) = config.ui.suggestion_list() {
+                problems.push(Problem {
+                    line: find_key_line(contents, "ui", "suggestions_file"),
+                    message: format!("no break suggestions, cannot read `ui.suggestions_file`: {}", e),
+                    fatal: false,
+                });
+            }
+            for (key, volume) in config.sound.volume.settings() {
+                if !(0.0..=1.0).contains(&volume) {
+                    problems.push(Problem {
+                        line: find_key_line(contents, "sound.volume", key),
+                        message: format!("`sound.volume.{}` should be between 0 and 1, got {}", key, volume),
+                        fatal: false,
+                    });
+                }
+            }
+        }
+        Err(e) => problems.push(fatal(e)),
+    }
+    problems.sort_by_key(|problem| problem.line);
+    problems
+}
+
+fn line_of(contents: &str, offset: usize) -> usize {
+    contents[..offset.min(contents.len())].lines().count().max(1)
+}
+
+/// The line where `key` is set inside `[section]`, found by scanning the text
+/// since the parsed table does not keep positions.
+fn find_key_line(contents: &str, section: &str, key: &str) -> Option<usize> {
+    let mut current = "";
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            current = header.trim();
+            // A top-level key can also be a table of its own
+            if section.is_empty() && current.split('.').next() == Some(key) {
+                return Some(index + 1);
+            }
+        } else if current == section
+            && let Some(rest) = line.strip_prefix(key)
+            && rest.trim_start().starts_with('=')
+        {
+            return Some(index + 1);
+        }
+    }
+    None
+}
+
+/// The known key within edit distance 2 of `key`, if any.
+fn closest<'a>(key: &str, known: &[&'a str]) -> Option<&'a str> {
+    known
+        .iter()
+        .map(|candidate| (edit_distance(key, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= 2)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// `pomo config check` and `pomo config path`. Returns whether the config is usable.
+pub fn run(command: &ConfigCommand, path: Option<&Path>) -> bool {
+    match command {
+        ConfigCommand::Path => match Config::resolve(path) {
+            Some((path, _)) if path.exists() => println!("{}", path.display()),
+            Some((path, _)) => println!("{} (not found, using defaults)", path.display()),
+            None => println!("no config directory, using defaults"),
+        },
+        ConfigCommand::Check => {
+            let problems = check(path);
+            if problems.is_empty() {
+                println!("ok");
+            }
+            let file = Config::resolve(path).map_or("config".into(), |(path, _)| path.display().to_string());
+            for problem in &problems {
+                let location = match problem.line {
+                    Some(line) => format!("{}:{}", file, line),
+                    None => file.clone(),
+                };
+                let severity = if problem.fatal { "error" } else { "warning" };
+                println!("{}: {}: {}", location, severity, problem.message);
+            }
+            return !problems.iter().any(|problem| problem.fatal);
+        }
+    }
+    true
+}
@@ -0,0 +1,362 @@
+use crate::{
+    announce::Announcer,
+    audio::Audio,
+    dnd::Dnd,
+    lifetime::Lifetime,
+    noise::NoiseKind,
+    session_log::{self, LogEntry, Rounding, SessionLog},
+    shutdown::Shutdown,
+    sound::Sound,
+    slack::Slack,
+    status::{Request, Status, StatusServer},
+    sync_hook::SyncHook,
+    webhook::Webhook,
+    ExitReason, PomodoroTimer, TimerState, Transition, SOUND_EXIT_LIMIT, SYNC_EXIT_LIMIT,
+};
+use chrono::{Local, NaiveTime};
+use std::{
+    env, io,
+    path::PathBuf,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Set on the background copy of `pomo start`, so it runs the timer instead
+/// of starting yet another copy.
+const CHILD_ENV: &str = "POMO_DAEMON";
+const TICK: Duration = Duration::from_millis(100);
+
+/// Whether this process is the background timer started by `pomo start`.
+pub fn is_child() -> bool {
+    env::var_os(CHILD_ENV).is_some()
+}
+
+/// Start this binary again with the same arguments, detached from the
+/// terminal: a session of its own, so closing the terminal does not hang
+/// it up, and no standard streams. Returns its PID.
+pub fn spawn() -> io::Result<u32> {
+    let mut command = Command::new(env::current_exe()?);
+    command
+        .args(env::args_os().skip(1))
+        .env(CHILD_ENV, "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // SAFETY: setsid is async-signal-safe, and nothing else runs between fork and exec
+    #[cfg(unix)]
+    unsafe {
+        std::os::unix::process::CommandExt::pre_exec(&mut command, || {
+            if libc::setsid() == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    Ok(command.spawn()?.id())
+}
+
+/// What the background timer records, as the TUI would.
+pub struct Recorder {
+    pub sound: Sound,
+    pub session_log: Option<SessionLog>,
+    pub lifetime_path: Option<PathBuf>,
+    pub task: Option<String>,
+    pub tags: Vec<String>,
+    pub day_start: NaiveTime,
+    pub rounding: Rounding,
+    pub sync: Option<SyncHook>,
+    pub webhook: Option<Webhook>,
+    pub slack: Option<Slack>,
+    /// Background noise, playing while focus runs
+    pub noise: Option<NoiseKind>,
+    pub dnd: Option<Dnd>,
+    /// Set by a signal to stop the timer like `pomo attach` would
+    pub shutdown: Shutdown,
+    /// Prints the state to stdout, for `--headless --accessible`
+    pub announcer: Option<Announcer>,
+    /// No sound at all, as set when the timer started
+    pub muted: bool,
+    /// Each phase waits, paused, until a client starts it
+    pub manual: bool,
+    /// Clients cannot pause, skip or reset, only stop the timer
+    pub strict: bool,
+}
+
+/// Run `timer` without a screen until it completes or `pomo attach` stops
+/// it. Clients see and control it through `server`.
+pub fn run(mut timer: PomodoroTimer, server: StatusServer, mut recorder: Recorder) -> io::Result<ExitReason> {
+    let mut audio = Audio::start(recorder.noise, recorder.sound.volume);
+    audio.set_muted(recorder.muted);
+    let mut lifetime = match &recorder.lifetime_path {
+        Some(path) => Some(Lifetime::load(path)?),
+        None => None,
+    };
+    let mut focus_secs_done = 0;
+    let mut last_tick = Instant::now();
+    let mut stop = false;
+    // Set while a phase waits to be started: the first with --start-mode
+    // prompt, which leaves the timer paused, and each finished one with --manual
+    let mut awaiting = timer.state == TimerState::Paused;
+
+    while !stop && !timer.completed && !timer.finished && !recorder.shutdown.requested() {
+        let now = Instant::now();
+        // Before this round's updates, so a finished run says nothing more
+        if let Some(announcer) = &mut recorder.announcer
+            && let Some(line) = announcer.due(&timer, now)
+        {
+            println!("{}", line);
+        }
+        // Before anything this round completes a cycle, so one just after
+        // midnight counts toward the new day
+        timer.roll_over_day(session_log::day_of(Local::now(), recorder.day_start));
+        let mut transitions = Vec::new();
+        // Ticks a second apart from the last second counted, as `update`
+        // keeps the fraction of one that has passed since
+        if timer.state != TimerState::Paused && now.duration_since(last_tick) >= Duration::from_secs(1) {
+            transitions.extend(timer.update(now));
+            timer.midpoint_reached = false;
+            last_tick = timer.last_update;
+        }
+
+        server.serve(|request| {
+            match request {
+                Request::Status => {}
+                // Like any key in the TUI, any request but stop starts the waiting phase
+                Request::TogglePause | Request::Skip | Request::Reset if awaiting => {
+                    awaiting = false;
+                    timer.toggle_pause(now);
+                }
+                Request::TogglePause | Request::Skip | Request::Reset if recorder.strict => {}
+                Request::TogglePause => timer.toggle_pause(now),
+                Request::Skip => transitions.extend(timer.skip(now)),
+                Request::Reset => timer.reset(now),
+                Request::Stop => stop = true,
+            }
+            Status {
+                daemon: true,
+                muted: recorder.muted,
+                ..Status::of(&timer, recorder.task.as_deref(), focus_secs_done / 60)
+            }
+        });
+
+        for transition in transitions {
+            if recorder.sound.enabled {
+                audio.play_notification(&recorder.sound, transition);
+            }
+            if recorder.manual && !timer.completed && !awaiting {
+                timer.toggle_pause(now);
+                awaiting = true;
+            }
+            if let Some(webhook) = &recorder.webhook {
+                webhook.notify(transition, timer.total_cycles, recorder.task.as_deref());
+            }
+            let day = session_log::day_of(Local::now(), recorder.day_start);
+            if transition == Transition::FocusEnd {
+                focus_secs_done += timer.ended().seconds;
+                // With nowhere to show errors, a failed write only loses that record
+                if let (Some(lifetime), Some(path)) = (&mut lifetime, &recorder.lifetime_path) {
+                    lifetime.record_pomodoro(day, timer.ended().seconds / 60);
+                    let _ = lifetime.save(path);
+                }
+            }
+            if let Some(log) = &recorder.session_log {
+                let appended = log.append(&LogEntry {
+                    at: Local::now(),
+                    event: transition,
+                    duration_secs: recorder.rounding.apply(timer.ended().seconds),
+                    cycles: timer.total_cycles,
+                    task: recorder.task.clone(),
+                    tags: recorder.tags.clone(),
+                    estimate: timer.estimate,
+                    preset: timer.preset.clone(),
+                    category: timer.category.clone().filter(|_| transition == Transition::FocusEnd),
+                    source: None,
+                    rating: None,
+                    microbreaks: 0,
+                    checklist: None,
+                });
+                if appended.is_ok()
+                    && let Some(sync) = &mut recorder.sync
+                {
+                    sync.logged(now);
+                }
+            }
+        }
+
+        audio.set_ambient_playing(timer.state == TimerState::Focus);
+        if let Some(dnd) = &mut recorder.dnd {
+            dnd.sync(timer.state == TimerState::Focus);
+        }
+        if let Some(slack) = &mut recorder.slack {
+            let focus_until = (timer.state == TimerState::Focus)
+                .then(|| Local::now() + chrono::Duration::seconds(timer.focus_remaining as i64));
+            slack.sync(focus_until);
+        }
+        if let Some(sync) = &mut recorder.sync {
+            sync.tick(now);
+        }
+        timer.check_max_runtime(now);
+        thread::sleep(TICK);
+    }
+    // Do-not-disturb goes off before anything else, as in the TUI
+    drop(recorder.dnd.take());
+    // Let the last notification finish before the process exits
+    audio.finish(SOUND_EXIT_LIMIT);
+    if let Some(sync) = recorder.sync {
+        sync.finish(SYNC_EXIT_LIMIT);
+    }
+    Ok(if timer.completed {
+        ExitReason::Completed
+    } else if timer.finished {
+        ExitReason::MaxRuntime
+    } else {
+        ExitReason::Quit
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{status, Phase, Stage};
+    use std::path::Path;
+
+    fn quiet(manual: bool, strict: bool) -> Recorder {
+        Recorder {
+            sound: Sound {
+                enabled: false,
+                ..Sound::default()
+            },
+            session_log: None,
+            lifetime_path: None,
+            task: None,
+            tags: Vec::new(),
+            day_start: NaiveTime::MIN,
+            rounding: Rounding::None,
+            sync: None,
+            webhook: None,
+            slack: None,
+            noise: None,
+            dnd: None,
+            shutdown: Shutdown::default(),
+            announcer: None,
+            muted: true,
+            manual,
+            strict,
+        }
+    }
+
+    fn serve_at(name: &str) -> (StatusServer, PathBuf) {
+        let path = env::temp_dir().join(format!("pomo-daemon-{}-{}.sock", std::process::id(), name));
+        let server = StatusServer::bind(path.clone()).unwrap().expect("nothing else listens there");
+        (server, path)
+    }
+
+    /// Ask for the status until `done` holds, then send `request`.
+    fn once(path: &Path, done: impl Fn(&Status) -> bool, request: Request) -> Status {
+        loop {
+            if let Some(status) = status::query(path).unwrap()
+                && done(&status)
+            {
+                return status::send(path, request).unwrap().unwrap();
+            }
+            thread::sleep(TICK);
+        }
+    }
+
+    #[test]
+    fn reaching_the_goal_exits_as_completed() {
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(1, 60), Instant::now());
+        timer.once = true;
+        let (server, _) = serve_at("completed");
+        assert_eq!(run(timer, server, quiet(false, false)).unwrap(), ExitReason::Completed);
+    }
+
+    #[test]
+    fn the_max_runtime_cap_exits_as_such() {
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(60, 60), Instant::now());
+        timer.max_runtime = Some(Duration::ZERO);
+        let (server, _) = serve_at("max-runtime");
+        assert_eq!(run(timer, server, quiet(false, false)).unwrap(), ExitReason::MaxRuntime);
+    }
+
+    #[test]
+    fn a_stop_request_exits_as_quit() {
+        let timer = PomodoroTimer::new(Stage::default_schedule(60, 60), Instant::now());
+        let (server, path) = serve_at("quit");
+        let client = thread::spawn(move || status::send(&path, Request::Stop).unwrap());
+        assert_eq!(run(timer, server, quiet(false, false)).unwrap(), ExitReason::Quit);
+        assert!(client.join().unwrap().is_some());
+    }
+
+    #[test]
+    fn strict_ignores_skip_and_pause() {
+        let timer = PomodoroTimer::new(Stage::default_schedule(60, 60), Instant::now());
+        let (server, path) = serve_at("strict");
+        let client = thread::spawn(move || {
+            let skipped = status::send(&path, Request::Skip).unwrap().unwrap();
+            let toggled = status::send(&path, Request::TogglePause).unwrap().unwrap();
+            status::send(&path, Request::Stop).unwrap();
+            (skipped, toggled)
+        });
+        run(timer, server, quiet(false, true)).unwrap();
+        let (skipped, toggled) = client.join().unwrap();
+        assert_eq!((skipped.phase, skipped.cycle), (Phase::Focus, 1));
+        assert!(!toggled.paused);
+    }
+
+    #[test]
+    fn manual_waits_after_each_phase_even_when_strict() {
+        let timer = PomodoroTimer::new(Stage::default_schedule(1, 60), Instant::now());
+        let (server, path) = serve_at("manual");
+        let client = thread::spawn(move || {
+            let started = once(&path, |status| status.paused, Request::TogglePause);
+            status::send(&path, Request::Stop).unwrap();
+            started
+        });
+        run(timer, server, quiet(true, true)).unwrap();
+        let started = client.join().unwrap();
+        assert_eq!((started.phase, started.paused), (Phase::Break, false));
+    }
+
+    #[test]
+    fn a_timer_started_paused_starts_on_request_even_when_strict() {
+        let now = Instant::now();
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(60, 60), now);
+        timer.toggle_pause(now);
+        let (server, path) = serve_at("prompt");
+        let client = thread::spawn(move || {
+            let started = status::send(&path, Request::Skip).unwrap().unwrap();
+            status::send(&path, Request::Stop).unwrap();
+            started
+        });
+        run(timer, server, quiet(false, true)).unwrap();
+        let started = client.join().unwrap();
+        assert_eq!((started.phase, started.paused), (Phase::Focus, false));
+    }
+
+    #[test]
+    fn a_signal_stops_the_timer_and_turns_do_not_disturb_off() {
+        let dir = env::temp_dir().join(format!("pomo-daemon-dnd-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (on, off) = (dir.join("on"), dir.join("off"));
+        let timer = PomodoroTimer::new(Stage::default_schedule(60, 60), Instant::now());
+        let (server, _) = serve_at("signal");
+        let mut recorder = quiet(false, false);
+        recorder.dnd = Dnd::new(
+            Some(format!("touch {}", on.display())),
+            Some(format!("touch {}", off.display())),
+        );
+        let shutdown = recorder.shutdown.clone();
+        let signal = thread::spawn(move || {
+            while !on.exists() {
+                thread::sleep(TICK);
+            }
+            shutdown.request();
+        });
+        assert_eq!(run(timer, server, recorder).unwrap(), ExitReason::Quit);
+        signal.join().unwrap();
+        assert!(off.exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
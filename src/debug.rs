@@ -0,0 +1,140 @@
+use crate::{Phase, PomodoroTimer, TimerState};
+use chrono::{DateTime, Local};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Internal events kept for the pane, newest last.
+const EVENTS: usize = 10;
+/// The wall clock moving this much more or less than the monotonic clock
+/// between two ticks counts as a jump, e.g. a suspend or an NTP correction.
+const JUMP: Duration = Duration::from_secs(1);
+const WIDTH: u16 = 52;
+
+/// What the timer, the tick loop and the audio thread are doing, for
+/// chasing timing bugs; `F12` or `--debug`. It only exists while shown, so
+/// a hidden pane records nothing and costs nothing.
+pub struct DebugPane {
+    /// Monotonic and wall time at the last tick
+    last_tick: Option<(Instant, SystemTime)>,
+    /// Time since the timer's previous update, and the whole seconds it counted
+    last_elapsed: Option<(Duration, u64)>,
+    /// How far the wall clock moved apart from the monotonic one, and when
+    last_jump: Option<(f64, DateTime<Local>)>,
+    events: VecDeque<(DateTime<Local>, String)>,
+    /// What `observe` saw last frame
+    seen: Option<(TimerState, usize, u64, u64)>,
+}
+
+impl DebugPane {
+    pub fn new() -> Self {
+        Self {
+            last_tick: None,
+            last_elapsed: None,
+            last_jump: None,
+            events: VecDeque::with_capacity(EVENTS),
+            seen: None,
+        }
+    }
+
+    /// Note something that happened, with the time it did.
+    pub fn event(&mut self, text: impl Into<String>) {
+        if self.events.len() == EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back((Local::now(), text.into()));
+    }
+
+    /// Call when the loop ticks the timer, with the time since its previous
+    /// update; the timer counts the whole seconds of it.
+    pub fn tick(&mut self, now: Instant, elapsed: Duration) {
+        let wall = SystemTime::now();
+        if let Some((instant, time)) = self.last_tick {
+            let monotonic = now.duration_since(instant).as_secs_f64();
+            let walled = wall.duration_since(time).map_or_else(|e| -e.duration().as_secs_f64(), |d| d.as_secs_f64());
+            let jump = walled - monotonic;
+            if jump.abs() >= JUMP.as_secs_f64() {
+                self.last_jump = Some((jump, Local::now()));
+                self.event(format!("wall clock jumped {:+.1}s", jump));
+            }
+        }
+        self.last_tick = Some((now, wall));
+        self.last_elapsed = Some((elapsed, elapsed.as_secs()));
+    }
+
+    /// Call every frame; records changes of state, phase and lengths as
+    /// events, however they came about.
+    pub fn observe(&mut self, timer: &PomodoroTimer) {
+        let now = (timer.state, timer.position, timer.focus_duration, timer.break_duration);
+        if let Some((state, position, focus, break_)) = self.seen {
+            if state != now.0 {
+                self.event(format!("state {:?} -> {:?}", state, now.0));
+            }
+            if position != now.1 {
+                self.event(format!("schedule position {} -> {}", position, now.1));
+            }
+            if focus != now.2 {
+                self.event(format!("focus length {}s -> {}s", focus, now.2));
+            }
+            if break_ != now.3 {
+                self.event(format!("break length {}s -> {}s", break_, now.3));
+            }
+        }
+        self.seen = Some(now);
+    }
+
+    /// Docked at the right edge, over whatever is there.
+    pub fn render(&self, f: &mut Frame, timer: &PomodoroTimer, pending_sounds: usize, now: Instant) {
+        let remaining = match timer.current_phase() {
+            Phase::Focus => timer.focus_remaining,
+            Phase::Break => timer.break_remaining,
+        };
+        // Where the running phase ends: the last update plus what it left
+        let deadline = timer.last_update + Duration::from_secs(remaining);
+        let deadline = match timer.state {
+            TimerState::Paused => "paused".to_string(),
+            _ => format!("in {:.1}s", deadline.saturating_duration_since(now).as_secs_f64()),
+        };
+        let mut lines = vec![
+            format!("state      {:?} (phase {:?})", timer.state, timer.current_phase()),
+            format!("position   {} of {}", timer.position + 1, timer.schedule.len()),
+            format!("focus      {}s of {}s", timer.focus_remaining, timer.focus_duration),
+            format!("break      {}s of {}s", timer.break_remaining, timer.break_duration),
+            format!("deadline   {}", deadline),
+            format!("since tick {:.3}s", now.duration_since(timer.last_update).as_secs_f64()),
+            match self.last_elapsed {
+                Some((elapsed, counted)) => format!("last tick  {:.3}s, counted {}s", elapsed.as_secs_f64(), counted),
+                None => "last tick  -".to_string(),
+            },
+            match self.last_jump {
+                Some((jump, at)) => format!("clock jump {:+.1}s at {}", jump, at.format("%H:%M:%S")),
+                None => "clock jump none".to_string(),
+            },
+            format!("countdown  {:?}", timer.countdown),
+            format!("sounds     {} pending", pending_sounds),
+            String::new(),
+        ];
+        lines.extend(self.events.iter().map(|(at, text)| format!("{} {}", at.format("%H:%M:%S%.3f"), text)));
+        let lines: Vec<Line> = lines.into_iter().map(Line::from).collect();
+
+        let height = lines.len() as u16 + 2;
+        let [column] = Layout::horizontal([Constraint::Length(WIDTH)]).flex(Flex::End).areas(f.area());
+        let [area] = Layout::vertical([Constraint::Length(height)]).areas(column);
+        let paragraph = Paragraph::new(lines).style(Style::default().fg(Color::Gray)).block(
+            Block::default()
+                .title(" debug (F12) ")
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::Yellow)),
+        );
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+    }
+}
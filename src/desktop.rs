@@ -0,0 +1,31 @@
+use std::{
+    process::{Command, Stdio},
+    thread,
+};
+
+/// Show a desktop notification, if the platform has a way to. Best effort:
+/// the terminal shows the same message, so a missing tool is not an error.
+pub fn notify(summary: &str, body: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {} with title {}",
+            apple_script_string(body),
+            apple_script_string(summary)
+        ));
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.args(["--app-name", "pomo", summary, body]);
+        command
+    };
+    let spawned = command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn();
+    if let Ok(mut child) = spawned {
+        // Reap it without holding up the timer
+        thread::spawn(move || child.wait());
+    }
+}
+
+fn apple_script_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
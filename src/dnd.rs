@@ -0,0 +1,78 @@
+use crate::sync_hook;
+use std::{
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Sender},
+    },
+    thread::{self, JoinHandle},
+};
+
+/// Turns the system's do-not-disturb, or a window manager's focus mode, on
+/// while focusing, with the commands from `--enable-dnd-cmd` and
+/// `--disable-dnd-cmd`.
+pub struct Dnd {
+    enable: Option<String>,
+    disable: Option<String>,
+    /// Whether the last command sent was the enable one
+    on: bool,
+    /// Runs the commands one at a time and in order, so a quick pause
+    /// and resume cannot end with the disable command running last
+    commands: Option<Sender<String>>,
+    worker: Option<JoinHandle<()>>,
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+impl Dnd {
+    /// Returns `None` when neither command is set.
+    pub fn new(enable: Option<String>, disable: Option<String>) -> Option<Self> {
+        if enable.is_none() && disable.is_none() {
+            return None;
+        }
+        let (commands, receiver) = mpsc::channel::<String>();
+        let last_error = Arc::new(Mutex::new(None));
+        let errors = Arc::clone(&last_error);
+        let worker = thread::spawn(move || {
+            for command in receiver {
+                *errors.lock().unwrap() = sync_hook::run(&command).err().map(|e| format!("dnd: {}", e));
+            }
+        });
+        Some(Self {
+            enable,
+            disable,
+            on: false,
+            commands: Some(commands),
+            worker: Some(worker),
+            last_error,
+        })
+    }
+
+    /// Call every frame with whether focus is running; the commands only
+    /// run when that changes.
+    pub fn sync(&mut self, focusing: bool) {
+        if focusing == self.on {
+            return;
+        }
+        self.on = focusing;
+        let command = if focusing { &self.enable } else { &self.disable };
+        if let (Some(command), Some(commands)) = (command, &self.commands) {
+            let _ = commands.send(command.clone());
+        }
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().unwrap().clone()
+    }
+}
+
+impl Drop for Dnd {
+    // Runs on quit, on early returns and while unwinding from a panic, so
+    // quitting mid-focus never leaves do-not-disturb on.
+    fn drop(&mut self) {
+        self.sync(false);
+        // Let the worker finish what was sent, the disable command last
+        drop(self.commands.take());
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
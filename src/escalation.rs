@@ -0,0 +1,72 @@
+use std::time::{Duration, Instant};
+
+/// How insistent the reminder is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Alarm {
+    /// The quiet mid-phase tone
+    Soft,
+    /// The full notification sound
+    Full,
+}
+
+/// From how long after the phase ended, how often to remind, and with what.
+/// The last step is the cap.
+const STEPS: [(Duration, Duration, Alarm); 3] = [
+    (Duration::from_secs(30), Duration::from_secs(30), Alarm::Soft),
+    (Duration::from_secs(120), Duration::from_secs(20), Alarm::Full),
+    (Duration::from_secs(300), Duration::from_secs(10), Alarm::Full),
+];
+
+/// Reminders while a finished phase waits for a key with `--manual`: a soft
+/// tone at first, then the full sound more and more often the longer it is
+/// ignored.
+pub struct Escalation {
+    since: Instant,
+    last: Instant,
+}
+
+impl Escalation {
+    /// Start counting from the notification that ended the phase.
+    pub fn new(now: Instant) -> Self {
+        Self { since: now, last: now }
+    }
+
+    /// The reminder to play now, if one is due.
+    pub fn due(&mut self, now: Instant) -> Option<Alarm> {
+        let waited = now.duration_since(self.since);
+        let &(_, every, alarm) = STEPS.iter().rev().find(|&&(after, _, _)| waited >= after)?;
+        if now.duration_since(self.last) < every {
+            return None;
+        }
+        self.last = now;
+        Some(alarm)
+    }
+
+    /// How long the finished phase has been waiting.
+    pub fn waited(&self, now: Instant) -> Duration {
+        now.duration_since(self.since)
+    }
+}
+
+/// The prompt to get back to work while a finished break waits for a key
+/// with `--manual`: the transition flashes again every `every`, whatever the
+/// alarm is doing.
+pub struct Nudge {
+    every: Duration,
+    last: Instant,
+}
+
+impl Nudge {
+    pub fn new(every: Duration, now: Instant) -> Self {
+        Self { every, last: now }
+    }
+
+    /// Whether the next nudge is due now.
+    pub fn due(&mut self, now: Instant) -> bool {
+        if now.duration_since(self.last) < self.every {
+            return false;
+        }
+        self.last = now;
+        true
+    }
+}
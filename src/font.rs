@@ -0,0 +1,169 @@
+use std::{collections::HashMap, fs, path::Path};
+
+/// The characters a font needs to show the time.
+const REQUIRED: &str = "0123456789:";
+
+/// Characters every figlet font defines, in file order: printable ASCII,
+/// then seven German letters.
+const STANDARD: &str = " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~ÄÖÜäöüß";
+
+/// A figlet font (`.flf`) for the big time display, loaded with `--font-file`.
+#[derive(Debug, Clone)]
+pub struct Font {
+    height: usize,
+    glyphs: HashMap<char, Vec<String>>,
+}
+
+impl Font {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Parse the header, skip the comments, then read one glyph per
+    /// character. Glyph lines end in an end mark (usually `@`, doubled on a
+    /// glyph's last line), and the header's hardblank stands for a space.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        let header = lines.next().ok_or("empty file")?;
+        let signature = header.strip_prefix("flf2a").ok_or("not a figlet font (no flf2a header)")?;
+        let hardblank = signature.chars().next().ok_or("header has no hardblank")?;
+        // Signed, as the layout is -1 in fonts drawn at full width
+        let fields: Vec<i32> = signature
+            .split_whitespace()
+            .skip(1)
+            .map(|field| field.parse().map_err(|_| format!("bad header field `{}`", field)))
+            .take(5)
+            .collect::<Result<_, String>>()?;
+        let &[height, _baseline, _max_length, _old_layout, comment_lines] = fields.as_slice() else {
+            return Err("header is missing fields".to_string());
+        };
+        let height = usize::try_from(height).ok().filter(|&height| height > 0).ok_or("height is not positive")?;
+        let comment_lines = usize::try_from(comment_lines).map_err(|_| "negative comment line count")?;
+
+        let mut lines = lines.skip(comment_lines);
+
+        let mut glyphs = HashMap::new();
+        for c in STANDARD.chars() {
+            match read_glyph(&mut lines, height, hardblank) {
+                Some(rows) => glyphs.insert(c, rows),
+                None => break,
+            };
+        }
+        // Code-tagged glyphs follow, each after a line such as `196  LATIN CAPITAL A`
+        while let Some(tag) = lines.next() {
+            let code = tag.split_whitespace().next().and_then(parse_code);
+            let Some(rows) = read_glyph(&mut lines, height, hardblank) else {
+                break;
+            };
+            if let Some(c) = code.and_then(char::from_u32) {
+                glyphs.insert(c, rows);
+            }
+        }
+
+        if let Some(missing) = REQUIRED.chars().find(|c| !glyphs.contains_key(c)) {
+            return Err(format!("no glyph for `{}`", missing));
+        }
+        Ok(Self { height, glyphs })
+    }
+
+    /// `text` in this font, glyphs side by side at their full width; `None`
+    /// if the font lacks one of its characters.
+    pub fn render(&self, text: &str) -> Option<Vec<String>> {
+        let mut rows = vec![String::new(); self.height];
+        for c in text.chars() {
+            for (row, line) in rows.iter_mut().zip(self.glyphs.get(&c)?) {
+                row.push_str(line);
+            }
+        }
+        Some(rows)
+    }
+
+    /// Room the widest possible time takes, as (width, height).
+    pub fn time_size(&self) -> (u16, u16) {
+        let width = |c: char| self.glyphs[&c].first().map_or(0, |row| row.chars().count());
+        let digit = ('0'..='9').map(width).max().unwrap_or(0);
+        ((4 * digit + width(':')) as u16, self.height as u16)
+    }
+}
+
+/// The next `height` lines as one glyph, or `None` at the end of the file.
+fn read_glyph<'a>(lines: &mut impl Iterator<Item = &'a str>, height: usize, hardblank: char) -> Option<Vec<String>> {
+    let rows: Vec<String> = lines
+        .take(height)
+        .map(|line| strip_end_mark(line).replace(hardblank, " "))
+        .collect();
+    (rows.len() == height).then(|| pad(rows))
+}
+
+/// Remove the end mark, which is whatever the line ends with, repeated.
+fn strip_end_mark(line: &str) -> &str {
+    let line = line.trim_end();
+    match line.chars().last() {
+        Some(mark) => line.trim_end_matches(mark),
+        None => line,
+    }
+}
+
+/// Pad a glyph's rows to the same width so columns line up.
+fn pad(rows: Vec<String>) -> Vec<String> {
+    let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+    rows.into_iter()
+        .map(|row| format!("{:<width$}", row, width = width))
+        .collect()
+}
+
+/// Code tags are decimal, `0x` hex or `0` octal; negative ones are unused here.
+fn parse_code(tag: &str) -> Option<u32> {
+    if let Some(hex) = tag.strip_prefix("0x").or_else(|| tag.strip_prefix("0X")) {
+        u32::from_str_radix(hex, 16).ok()
+    } else if tag.len() > 1
+        && let Some(octal) = tag.strip_prefix('0')
+    {
+        u32::from_str_radix(octal, 8).ok()
+    } else {
+        tag.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEGMENTS: &str = include_str!("../tests/fixtures/segments.flf");
+
+    #[test]
+    fn the_bundled_font_renders_the_time_at_its_full_width() {
+        let font = Font::parse(SEGMENTS).unwrap();
+        assert_eq!(font.time_size(), (13, 3));
+        let rows = font.render("12:34").unwrap();
+        assert_eq!(rows, ["    _   _    ", "  | _|. _||_|", "  ||_ . _|  |"]);
+        assert!(rows.iter().all(|row| row.chars().count() == 13));
+    }
+
+    #[test]
+    fn hardblanks_become_spaces_and_end_marks_go() {
+        let font = Font::parse(SEGMENTS).unwrap();
+        assert_eq!(font.render("1").unwrap(), ["   ", "  |", "  |"]);
+        // Letters come after the digits, which this font stops at
+        assert_eq!(font.render("A"), None);
+    }
+
+    #[test]
+    fn a_full_width_layout_is_accepted() {
+        let full_width = SEGMENTS.replacen("flf2a$ 3 2 5 0 2", "flf2a$ 3 2 5 -1 2", 1);
+        assert_eq!(Font::parse(&full_width).unwrap().time_size(), (13, 3));
+    }
+
+    #[test]
+    fn malformed_fonts_are_rejected() {
+        assert!(Font::parse("").is_err());
+        assert!(Font::parse("not a font").is_err());
+        assert!(Font::parse("flf2a$ 0 0 5 0 0").is_err());
+        assert!(Font::parse("flf2a$ 3 2").is_err());
+        assert!(Font::parse("flf2a$ -3 2 5 0 0").is_err());
+        // Cut off before the colon
+        let truncated: String = SEGMENTS.lines().take(3 + 26 * 3).map(|line| format!("{}\n", line)).collect();
+        assert_eq!(Font::parse(&truncated).unwrap_err(), "no glyph for `:`");
+    }
+}
@@ -0,0 +1,89 @@
+use ratatui::style::Color;
+use serde::Deserialize;
+use std::env;
+
+/// A vertical color fade for the active timer's digits, written in the
+/// config as `["#rrggbb", "#rrggbb"]` (top, bottom).
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "[String; 2]")]
+pub struct Gradient {
+    top: (u8, u8, u8),
+    bottom: (u8, u8, u8),
+}
+
+pub const FOCUS: Gradient = Gradient {
+    top: (0x9c, 0xff, 0x57),
+    bottom: (0x1b, 0x5e, 0x20),
+};
+
+pub const BREAK: Gradient = Gradient {
+    top: (0xff, 0xd5, 0x4f),
+    bottom: (0xe6, 0x51, 0x00),
+};
+
+impl TryFrom<[String; 2]> for Gradient {
+    type Error = String;
+
+    fn try_from([top, bottom]: [String; 2]) -> Result<Self, String> {
+        Ok(Self {
+            top: parse_hex(&top)?,
+            bottom: parse_hex(&bottom)?,
+        })
+    }
+}
+
+impl Gradient {
+    /// The color of line `index` out of `count`, from top to bottom.
+    pub fn at(self, index: usize, count: usize) -> Color {
+        self.at_fraction(if count > 1 { index as f32 / (count - 1) as f32 } else { 0.0 })
+    }
+
+    fn at_fraction(self, t: f32) -> Color {
+        let mix = |top: u8, bottom: u8| (top as f32 + (bottom as f32 - top as f32) * t).round() as u8;
+        Color::Rgb(
+            mix(self.top.0, self.bottom.0),
+            mix(self.top.1, self.bottom.1),
+            mix(self.top.2, self.bottom.2),
+        )
+    }
+}
+
+/// Approximate RGB values of the named colors the timer blocks use, so they
+/// can be blended during the phase change animation.
+pub const GREEN: (u8, u8, u8) = (0x00, 0xcd, 0x00);
+pub const YELLOW: (u8, u8, u8) = (0xcd, 0xcd, 0x00);
+pub const DARK_GRAY: (u8, u8, u8) = (0x7f, 0x7f, 0x7f);
+
+pub const ORANGE: (u8, u8, u8) = (0xff, 0x8c, 0x00);
+pub const RED: (u8, u8, u8) = (0xe0, 0x20, 0x20);
+
+/// The color for a phase `urgency` of the way (0 to 1) into its last stretch:
+/// from the phase color through orange to red. Without true color it snaps
+/// to the nearest of the basic colors instead.
+pub fn urgency(base: (u8, u8, u8), base_named: Color, urgency: f32, truecolor: bool) -> Color {
+    match (truecolor, urgency) {
+        (true, u) if u < 0.5 => blend(base, ORANGE, u * 2.0),
+        (true, u) => blend(ORANGE, RED, (u - 0.5) * 2.0),
+        (false, u) if u < 1.0 / 3.0 => base_named,
+        (false, u) if u < 2.0 / 3.0 => Color::Yellow,
+        (false, _) => Color::Red,
+    }
+}
+
+/// `t` of the way from `from` to `to`.
+pub fn blend(from: (u8, u8, u8), to: (u8, u8, u8), t: f32) -> Color {
+    Gradient { top: from, bottom: to }.at_fraction(t)
+}
+
+/// Whether the terminal advertises 24-bit color. Without it, RGB colors get
+/// rounded to the nearest palette entry, which turns a gradient into bands.
+pub fn supported() -> bool {
+    env::var("COLORTERM").is_ok_and(|value| value == "truecolor" || value == "24bit")
+}
+
+fn parse_hex(value: &str) -> Result<(u8, u8, u8), String> {
+    let invalid = || format!("expected a color like \"#1b5e20\", got \"{}\"", value);
+    let hex = value.strip_prefix('#').filter(|hex| hex.len() == 6).ok_or_else(invalid)?;
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2).ok_or_else(invalid)?, 16).map_err(|_| invalid());
+    Ok((channel(0)?, channel(2)?, channel(4)?))
+}
@@ -0,0 +1,182 @@
+use crate::Transition;
+use std::env;
+
+/// Display strings for one UI language. Emoji stay in `draw_ui` so they are
+/// shared by every language.
+#[derive(Debug)]
+pub struct Strings {
+    pub title: &'static str,
+    pub focus_ended: &'static str,
+    pub break_ended: &'static str,
+    /// Footer after a transition; `{event}` and `{minutes}` are filled in
+    pub last_event: &'static str,
+    pub focus_time: &'static str,
+    pub break_time: &'static str,
+    pub focus: &'static str,
+    pub break_: &'static str,
+    pub minutes: &'static str,
+    /// Marks a break length derived from `--break-ratio`
+    pub auto: &'static str,
+    pub cycles: &'static str,
+    pub all_time: &'static str,
+    /// Header count towards the daily goal
+    pub today: &'static str,
+    pub pause: &'static str,
+    pub resume: &'static str,
+    /// Header banner while the timer is paused
+    pub paused: &'static str,
+    /// Header banner during the count before the first phase
+    pub get_ready: &'static str,
+    /// Header banner during the long break forced by `--max-consecutive`
+    pub mandatory_rest: &'static str,
+    /// Micro-break countdown, e.g. "Look away — 20s"
+    pub look_away: &'static str,
+    /// Label of the `--cycles` progress bar
+    pub session_goal: &'static str,
+    /// Break block title during a break taken with `z`
+    pub quick_break: &'static str,
+    /// Header projection of the run's end; `{at}` and `{left}` are filled in
+    pub run_ends: &'static str,
+    pub reset: &'static str,
+    pub quit: &'static str,
+}
+
+const ENGLISH: Strings = Strings {
+    title: "POMODORO TIMER",
+    focus_ended: "focus ended",
+    break_ended: "break ended",
+    last_event: "last: {event} {minutes}m ago",
+    focus_time: "FOCUS TIME",
+    break_time: "BREAK TIME",
+    focus: "Focus",
+    break_: "Break",
+    minutes: "min",
+    auto: "auto",
+    cycles: "Cycles",
+    all_time: "All-time",
+    today: "Today",
+    pause: "Pause",
+    resume: "Resume",
+    paused: "PAUSED",
+    get_ready: "GET READY",
+    mandatory_rest: "MANDATORY REST",
+    look_away: "Look away",
+    session_goal: "Session goal",
+    quick_break: "QUICK BREAK",
+    run_ends: "done {at}, {left} left",
+    reset: "Reset",
+    quit: "Quit",
+};
+
+// Translations only list what differs; anything left out falls back to English.
+const GERMAN: Strings = Strings {
+    title: "POMODORO-TIMER",
+    focus_ended: "Fokus beendet",
+    break_ended: "Pause beendet",
+    last_event: "zuletzt: {event} vor {minutes} Min.",
+    focus_time: "FOKUSZEIT",
+    break_time: "PAUSENZEIT",
+    focus: "Fokus",
+    break_: "Pause",
+    cycles: "Zyklen",
+    all_time: "Gesamt",
+    today: "Heute",
+    pause: "Pausieren",
+    resume: "Fortsetzen",
+    paused: "PAUSIERT",
+    get_ready: "BEREIT MACHEN",
+    mandatory_rest: "PFLICHTPAUSE",
+    look_away: "Wegschauen",
+    session_goal: "Sitzungsziel",
+    quick_break: "KURZE PAUSE",
+    run_ends: "fertig {at}, noch {left}",
+    reset: "Zurücksetzen",
+    quit: "Beenden",
+    ..ENGLISH
+};
+
+const FRENCH: Strings = Strings {
+    title: "MINUTEUR POMODORO",
+    focus_ended: "concentration terminée",
+    break_ended: "pause terminée",
+    last_event: "dernier : {event} il y a {minutes} min",
+    focus_time: "CONCENTRATION",
+    break_time: "PAUSE",
+    focus: "Concentration",
+    break_: "Pause",
+    all_time: "Total",
+    today: "Aujourd'hui",
+    resume: "Reprendre",
+    paused: "EN PAUSE",
+    get_ready: "PRÉPAREZ-VOUS",
+    mandatory_rest: "REPOS OBLIGATOIRE",
+    look_away: "Regardez au loin",
+    session_goal: "Objectif de la session",
+    quick_break: "PETITE PAUSE",
+    run_ends: "fin {at}, encore {left}",
+    reset: "Réinitialiser",
+    quit: "Quitter",
+    ..ENGLISH
+};
+
+const SPANISH: Strings = Strings {
+    title: "TEMPORIZADOR POMODORO",
+    focus_ended: "enfoque terminado",
+    break_ended: "descanso terminado",
+    last_event: "último: {event} hace {minutes} min",
+    focus_time: "CONCENTRACIÓN",
+    break_time: "DESCANSO",
+    focus: "Enfoque",
+    break_: "Descanso",
+    cycles: "Ciclos",
+    all_time: "Total",
+    today: "Hoy",
+    pause: "Pausar",
+    resume: "Reanudar",
+    paused: "EN PAUSA",
+    get_ready: "PREPÁRATE",
+    mandatory_rest: "DESCANSO OBLIGATORIO",
+    look_away: "Mira a lo lejos",
+    session_goal: "Meta de la sesión",
+    quick_break: "PAUSA RÁPIDA",
+    run_ends: "fin {at}, faltan {left}",
+    reset: "Reiniciar",
+    quit: "Salir",
+    ..ENGLISH
+};
+
+impl Strings {
+    pub fn transition(&self, transition: Transition) -> &'static str {
+        match transition {
+            Transition::FocusEnd => self.focus_ended,
+            Transition::BreakEnd => self.break_ended,
+        }
+    }
+
+    /// Strings for a language code such as `de` or `de_DE.UTF-8`. Unknown
+    /// languages get English.
+    pub fn for_lang(lang: &str) -> &'static Strings {
+        let code = lang
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match code.as_str() {
+            "de" => &GERMAN,
+            "fr" => &FRENCH,
+            "es" => &SPANISH,
+            _ => &ENGLISH,
+        }
+    }
+
+    /// Use the `--lang` flag if given, otherwise the usual locale variables.
+    pub fn detect(lang: Option<&str>) -> &'static Strings {
+        let lang = lang.map(str::to_string).or_else(|| {
+            ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .filter_map(|var| env::var(var).ok())
+                .find(|value| !value.is_empty())
+        });
+        lang.map_or(&ENGLISH, |lang| Self::for_lang(&lang))
+    }
+}
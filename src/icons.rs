@@ -0,0 +1,81 @@
+use serde::Deserialize;
+use std::env;
+
+/// Which glyphs the UI uses. Every icon goes through `Icons`, so switching
+/// sets never changes how titles line up.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IconSet {
+    Emoji,
+    /// Single-width private-use glyphs from a Nerd Font patched font
+    Nerdfont,
+    Ascii,
+}
+
+#[derive(Debug)]
+pub struct Icons {
+    pub tomato: &'static str,
+    pub focus: &'static str,
+    pub break_: &'static str,
+    pub bell: &'static str,
+    pub warning: &'static str,
+    pub presence: &'static str,
+    pub pause: &'static str,
+    pub muted: &'static str,
+}
+
+const EMOJI: Icons = Icons {
+    tomato: "🍅",
+    focus: "⚡",
+    break_: "☕",
+    bell: "🔔",
+    warning: "⚠",
+    presence: "👀",
+    pause: "⏸",
+    muted: "🔇",
+};
+
+const NERD_FONT: Icons = Icons {
+    tomato: "\u{f017}",
+    focus: "\u{f0e7}",
+    break_: "\u{f0f4}",
+    bell: "\u{f0f3}",
+    warning: "\u{f071}",
+    presence: "\u{f06e}",
+    pause: "\u{f04c}",
+    muted: "\u{f026}",
+};
+
+const ASCII: Icons = Icons {
+    tomato: "*",
+    focus: ">",
+    break_: "~",
+    bell: "!",
+    warning: "!",
+    presence: "?",
+    pause: "||",
+    muted: "MUTED",
+};
+
+impl IconSet {
+    /// Emoji, except where they are known to render as boxes or at the wrong
+    /// width: the Linux console and the classic Windows console.
+    pub fn detect() -> Self {
+        let linux_console = env::var("TERM").is_ok_and(|term| term == "linux");
+        // Windows Terminal sets WT_SESSION; conhost does not
+        let windows_console = cfg!(windows) && env::var_os("WT_SESSION").is_none();
+        if linux_console || windows_console {
+            IconSet::Ascii
+        } else {
+            IconSet::Emoji
+        }
+    }
+
+    pub fn icons(self) -> &'static Icons {
+        match self {
+            IconSet::Emoji => &EMOJI,
+            IconSet::Nerdfont => &NERD_FONT,
+            IconSet::Ascii => &ASCII,
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use crate::{
+    session_log::{LogEntry, SessionLog},
+    Transition,
+};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use clap::ValueEnum;
+use std::{collections::HashSet, fs, path::PathBuf};
+
+/// Date and time layouts tried in order for the start and end columns,
+/// after RFC 3339. Times without a zone are local.
+const DATE_TIME_FORMATS: [&str; 8] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%Y/%m/%d %H:%M",
+    "%d.%m.%Y %H:%M:%S",
+    "%d.%m.%Y %H:%M",
+];
+
+#[derive(clap::Args, Debug)]
+pub struct ImportArgs {
+    /// The exported sessions
+    file: PathBuf,
+
+    /// Layout of the file
+    #[arg(long, value_enum, default_value_t = ImportFormat::Csv)]
+    format: ImportFormat,
+
+    /// Columns to read each field from, e.g. "start=Started,end=Finished,task=Label";
+    /// the fields are start, end, duration (in minutes) and task
+    #[arg(long, value_parser = Mapping::parse)]
+    map: Option<Mapping>,
+
+    /// Report what would be imported without writing to the log
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ImportFormat {
+    /// Any CSV file with a header row; needs --map
+    Csv,
+    /// The CSV export of Focus To-Do
+    FocusTodo,
+}
+
+impl ImportFormat {
+    /// Recorded as the `source` of every imported entry.
+    fn name(self) -> &'static str {
+        match self {
+            ImportFormat::Csv => "csv",
+            ImportFormat::FocusTodo => "focus-todo",
+        }
+    }
+
+    /// The columns of the format's own export; `--map` overrides single fields.
+    fn mapping(self) -> Mapping {
+        match self {
+            ImportFormat::Csv => Mapping::default(),
+            ImportFormat::FocusTodo => Mapping {
+                start: Some("Start Time".to_string()),
+                end: Some("End Time".to_string()),
+                duration: None,
+                task: Some("Task".to_string()),
+            },
+        }
+    }
+}
+
+/// Which column holds each field, by header name.
+#[derive(Debug, Clone, Default)]
+pub struct Mapping {
+    start: Option<String>,
+    end: Option<String>,
+    /// Minutes, for exports without an end time
+    duration: Option<String>,
+    task: Option<String>,
+}
+
+impl Mapping {
+    /// Parse `--map`: comma-separated `field=Column` pairs.
+    fn parse(value: &str) -> Result<Mapping, String> {
+        let mut mapping = Mapping::default();
+        for pair in value.split(',').filter(|pair| !pair.trim().is_empty()) {
+            let Some((field, column)) = pair.split_once('=') else {
+                return Err(format!("expected field=Column, got '{}'", pair.trim()));
+            };
+            let column = Some(column.trim().to_string());
+            match field.trim() {
+                "start" => mapping.start = column,
+                "end" => mapping.end = column,
+                "duration" => mapping.duration = column,
+                "task" => mapping.task = column,
+                other => {
+                    return Err(format!("unknown field '{}', expected start, end, duration or task", other));
+                }
+            }
+        }
+        Ok(mapping)
+    }
+
+    fn or(self, defaults: Mapping) -> Mapping {
+        Mapping {
+            start: self.start.or(defaults.start),
+            end: self.end.or(defaults.end),
+            duration: self.duration.or(defaults.duration),
+            task: self.task.or(defaults.task),
+        }
+    }
+}
+
+/// Column positions for a mapping, found in the header row.
+struct Columns {
+    start: usize,
+    end: Option<usize>,
+    duration: Option<usize>,
+    task: Option<usize>,
+}
+
+impl Columns {
+    fn find(mapping: &Mapping, header: &[String]) -> Result<Columns, String> {
+        let position = |column: &Option<String>| -> Result<Option<usize>, String> {
+            let Some(column) = column else {
+                return Ok(None);
+            };
+            header
+                .iter()
+                .position(|name| name.trim().eq_ignore_ascii_case(column))
+                .map(Some)
+                .ok_or_else(|| format!("no column '{}' in the header ({})", column, header.join(", ")))
+        };
+        let Some(start) = position(&mapping.start)? else {
+            return Err("no column for start; pass --map start=<column>,...".to_string());
+        };
+        let end = position(&mapping.end)?;
+        let duration = position(&mapping.duration)?;
+        if end.is_none() && duration.is_none() {
+            return Err("no column for end or duration; add one to --map".to_string());
+        }
+        Ok(Columns {
+            start,
+            end,
+            duration,
+            task: position(&mapping.task)?,
+        })
+    }
+
+    /// The row as a completed focus session, or why it cannot be one.
+    fn entry(&self, row: &[String], source: &str) -> Result<LogEntry, String> {
+        let field = |index: usize| row.get(index).map_or("", |value| value.trim());
+        let start = parse_date_time(field(self.start))?;
+        let at = match self.end.map(field).filter(|value| !value.is_empty()) {
+            Some(end) => parse_date_time(end)?,
+            None => {
+                let minutes = self.duration.map(field).unwrap_or_default();
+                let unreadable = || format!("cannot read duration '{}'", minutes);
+                let seconds: f64 = minutes.parse::<f64>().map_err(|_| unreadable())? * 60.0;
+                // Far past what a date can hold, e.g. from a corrupt export
+                chrono::Duration::try_seconds(seconds.round() as i64)
+                    .and_then(|duration| start.checked_add_signed(duration))
+                    .ok_or_else(unreadable)?
+            }
+        };
+        if at <= start {
+            return Err("ends before it starts".to_string());
+        }
+        let task = self.task.map(field).filter(|task| !task.is_empty());
+        Ok(LogEntry {
+            at,
+            event: Transition::FocusEnd,
+            duration_secs: (at - start).num_seconds() as u64,
+            cycles: 0,
+            task: task.map(str::to_string),
+            preset: None,
+            category: None,
+            tags: Vec::new(),
+            estimate: None,
+            source: Some(source.to_string()),
+            rating: None,
+            microbreaks: 0,
+            checklist: None,
+        })
+    }
+}
+
+fn parse_date_time(value: &str) -> Result<DateTime<Local>, String> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(value) {
+        return Ok(at.with_timezone(&Local));
+    }
+    DATE_TIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+        .ok_or_else(|| format!("cannot read date '{}'", value))
+}
+
+/// Records of a CSV file with the line each starts on. Quoted fields may
+/// hold commas, doubled quotes and line breaks; blank lines are skipped.
+fn parse_csv(text: &str) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|field| !field.trim().is_empty()) {
+                    records.push((record_line, std::mem::take(&mut record)));
+                }
+                record.clear();
+                line += 1;
+                record_line = line;
+            }
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    record.push(field);
+    if record.iter().any(|field| !field.trim().is_empty()) {
+        records.push((record_line, record));
+    }
+    records
+}
+
+/// Convert the sessions in `args.file` and add them to the log in time
+/// order, leaving out those whose start is already there; `pomo import`.
+pub fn run(log: &SessionLog, args: &ImportArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let text = fs::read_to_string(&args.file).map_err(|e| format!("{}: {}", args.file.display(), e))?;
+    let mut rows = parse_csv(&text).into_iter();
+    let Some((_, header)) = rows.next() else {
+        return Err(format!("{} is empty", args.file.display()).into());
+    };
+    let mapping = args.map.clone().unwrap_or_default().or(args.format.mapping());
+    let columns = Columns::find(&mapping, &header)?;
+
+    // Sessions are matched on their start, to the second
+    let mut starts: HashSet<i64> = log
+        .entries()?
+        .iter()
+        .map(|entry| entry.at.timestamp() - entry.duration_secs as i64)
+        .collect();
+    let mut imported = Vec::new();
+    let (mut skipped, mut invalid) = (0, 0);
+    for (line, row) in rows {
+        match columns.entry(&row, args.format.name()) {
+            Ok(entry) if !starts.insert(entry.at.timestamp() - entry.duration_secs as i64) => skipped += 1,
+            Ok(entry) => imported.push(entry),
+            Err(e) => {
+                eprintln!("line {}: {}", line, e);
+                invalid += 1;
+            }
+        }
+    }
+
+    let count = imported.len();
+    if !args.dry_run && count > 0 {
+        log.insert(imported)?;
+    }
+    println!(
+        "{} {} sessions, skipped {} already in the log, {} invalid rows",
+        if args.dry_run { "Would import" } else { "Imported" },
+        count,
+        skipped,
+        invalid
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> Columns {
+        Columns {
+            start: 0,
+            end: None,
+            duration: Some(1),
+            task: Some(2),
+        }
+    }
+
+    /// Each row of `csv` after the header, read as a session.
+    fn read(csv: &str) -> Vec<Result<LogEntry, String>> {
+        let columns = columns();
+        parse_csv(csv).into_iter().skip(1).map(|(_, row)| columns.entry(&row, "csv")).collect()
+    }
+
+    #[test]
+    fn quoted_fields_keep_commas_quotes_and_line_breaks() {
+        let records = parse_csv("Start,Minutes,Task\r\n\n2024-06-01 09:00,25,\"write, \"\"edit\"\"\nand send\"\n");
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].0, 3);
+        assert_eq!(records[1].1[2], "write, \"edit\"\nand send");
+    }
+
+    #[test]
+    fn a_good_row_becomes_a_focus_session() {
+        let rows = read("Start,Minutes,Task\n2024-06-01 09:00,25,report\n");
+        let entry = rows[0].as_ref().unwrap();
+        assert_eq!((entry.duration_secs, entry.task.as_deref()), (25 * 60, Some("report")));
+    }
+
+    #[test]
+    fn an_unreadable_date_is_an_invalid_row() {
+        let rows = read("Start,Minutes,Task\nyesterday,25,report\n");
+        assert_eq!(rows[0].as_ref().unwrap_err(), "cannot read date 'yesterday'");
+    }
+
+    #[test]
+    fn an_unreadable_or_absurd_duration_is_an_invalid_row() {
+        // Too long for a duration, and an end past the last date there is
+        let rows = read(concat!(
+            "Start,Minutes,Task\n",
+            "2024-06-01 09:00,soon,a\n",
+            "2024-06-01 09:00,1e300,b\n",
+            "2024-06-01 09:00,2e11,c\n",
+        ));
+        assert_eq!(rows[0].as_ref().unwrap_err(), "cannot read duration 'soon'");
+        assert_eq!(rows[1].as_ref().unwrap_err(), "cannot read duration '1e300'");
+        assert_eq!(rows[2].as_ref().unwrap_err(), "cannot read duration '2e11'");
+    }
+
+    #[test]
+    fn a_session_that_ends_before_it_starts_is_an_invalid_row() {
+        let rows = read("Start,Minutes,Task\n2024-06-01 09:00,-5,a\n2024-06-01 09:00,NaN,b\n");
+        assert!(rows.iter().all(|row| row.as_ref().unwrap_err() == "ends before it starts"));
+    }
+}
@@ -0,0 +1,184 @@
+use chrono::{DateTime, Local};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// Where key presses come from: the keyboard, optionally recorded to a file,
+/// or a recording played back with its original timing. Replaying a
+/// recording reproduces a whole session, which makes bugs in the event loop
+/// easy to report and check. The loop takes the time from here too, so a
+/// script can run it on a virtual clock.
+pub struct Keys {
+    start: Instant,
+    record: Option<File>,
+    replay: VecDeque<(Duration, KeyCode)>,
+    /// Set for a script: waiting moves this on instead of sleeping
+    virtual_now: Option<Instant>,
+    /// The wall-clock time at `start` on a script's clock
+    local_start: Option<DateTime<Local>>,
+}
+
+impl Keys {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            record: None,
+            replay: VecDeque::new(),
+            virtual_now: None,
+            local_start: None,
+        }
+    }
+
+    /// Key presses at set times after `start`, on a virtual clock that only
+    /// moves as the loop waits for them. The keyboard is never read; once
+    /// the script runs out, waiting for a key is an error.
+    #[cfg(test)]
+    pub fn scripted(start: Instant, script: impl IntoIterator<Item = (Duration, KeyCode)>) -> Self {
+        Self {
+            start,
+            record: None,
+            replay: script.into_iter().collect(),
+            virtual_now: Some(start),
+            local_start: Some(Local::now()),
+        }
+    }
+
+    /// Run the script's clock from this wall-clock time, e.g. just before midnight.
+    #[cfg(test)]
+    pub fn starting_at(mut self, local: DateTime<Local>) -> Self {
+        self.local_start = Some(local);
+        self
+    }
+
+    /// The current time: the clock's, or the script's.
+    pub fn now(&self) -> Instant {
+        self.virtual_now.unwrap_or_else(Instant::now)
+    }
+
+    /// The wall-clock time, for dates: the system's, or the script's.
+    pub fn local_now(&self) -> DateTime<Local> {
+        match (self.local_start, self.virtual_now) {
+            (Some(local), Some(now)) => local + (now - self.start),
+            _ => Local::now(),
+        }
+    }
+
+    /// Append every key press to `path`, one `<milliseconds> <key>` line each.
+    pub fn record(mut self, path: &Path) -> io::Result<Self> {
+        self.record = Some(File::create(path)?);
+        Ok(self)
+    }
+
+    /// Play back a recording before handing over to the keyboard.
+    pub fn replay(mut self, path: &Path) -> io::Result<Self> {
+        for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}:{}: expected `<milliseconds> <key>`", path.display(), number + 1),
+                )
+            };
+            let (at, key) = line.split_once(' ').ok_or_else(invalid)?;
+            let at = at.parse().map_err(|_| invalid())?;
+            let key = parse_key(key).ok_or_else(invalid)?;
+            self.replay.push_back((Duration::from_millis(at), key));
+        }
+        Ok(self)
+    }
+
+    /// The next key press, waiting at most `timeout`.
+    pub fn next(&mut self, timeout: Duration) -> io::Result<Option<KeyCode>> {
+        Ok(self.next_with_modifiers(timeout)?.map(|(code, _)| code))
+    }
+
+    /// The next key press with Shift, Ctrl and so on. Recordings keep only
+    /// the key, so replayed presses have no modifiers.
+    pub fn next_with_modifiers(&mut self, timeout: Duration) -> io::Result<Option<(KeyCode, KeyModifiers)>> {
+        if let Some(now) = &mut self.virtual_now {
+            let &(at, key) = self
+                .replay
+                .front()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "the key script ran out"))?;
+            let due = self.start + at;
+            if due > *now + timeout {
+                *now += timeout;
+                return Ok(None);
+            }
+            *now = due.max(*now);
+            self.replay.pop_front();
+            return Ok(Some((key, KeyModifiers::NONE)));
+        }
+
+        if let Some(&(at, key)) = self.replay.front() {
+            let due = self.start + at;
+            let now = Instant::now();
+            if due > now + timeout {
+                std::thread::sleep(timeout);
+                return Ok(None);
+            }
+            std::thread::sleep(due.saturating_duration_since(now));
+            self.replay.pop_front();
+            return Ok(Some((key, KeyModifiers::NONE)));
+        }
+
+        if !event::poll(timeout)? {
+            return Ok(None);
+        }
+        let key = match event::read()? {
+            Event::Key(key) => key,
+            // Anything else, a resize included, returns straight away, so the
+            // caller draws the next frame at the new size with the layout
+            // variant that fits
+            _ => return Ok(None),
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(None);
+        }
+        if let Some(file) = &mut self.record
+            && let Some(name) = key_name(key.code)
+        {
+            writeln!(file, "{} {}", self.start.elapsed().as_millis(), name)?;
+        }
+        Ok(Some((key.code, key.modifiers)))
+    }
+}
+
+fn key_name(code: KeyCode) -> Option<String> {
+    Some(match code {
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Enter => "Enter".to_string(),
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::Up => "Up".to_string(),
+        KeyCode::Down => "Down".to_string(),
+        KeyCode::Left => "Left".to_string(),
+        KeyCode::Right => "Right".to_string(),
+        _ => return None,
+    })
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    Some(match name {
+        "Enter" => KeyCode::Enter,
+        "Esc" => KeyCode::Esc,
+        "Backspace" => KeyCode::Backspace,
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        // The space key is recorded as a literal space after the separator
+        _ => {
+            let mut chars = name.chars();
+            let c = chars.next()?;
+            if chars.next().is_some() {
+                return None;
+            }
+            KeyCode::Char(c)
+        }
+    })
+}
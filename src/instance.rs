@@ -0,0 +1,51 @@
+use std::{
+    fs::{self, File, OpenOptions, TryLockError},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+/// Held by the running timer so a second one started by accident cannot
+/// double-count sessions in the log and stats. The operating system drops
+/// the lock when the process ends, however it ends, so a crashed instance
+/// never leaves a stale lock behind; the PID in the file is only for the
+/// message.
+pub struct InstanceLock {
+    file: File,
+}
+
+impl InstanceLock {
+    /// Default location, e.g. `~/.local/share/pomo/pomo.lock` next to the log.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("pomo").join("pomo.lock"))
+    }
+
+    /// Take the lock, or return the PID of the instance holding it (`None`
+    /// if it did not record one).
+    pub fn acquire(path: &Path) -> io::Result<Result<Self, Option<u32>>> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut pid = String::new();
+                file.read_to_string(&mut pid)?;
+                return Ok(Err(pid.trim().parse().ok()));
+            }
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+        file.set_len(0)?;
+        write!(file, "{}", std::process::id())?;
+        file.flush()?;
+        Ok(Ok(Self { file }))
+    }
+}
+
+impl Drop for InstanceLock {
+    /// Clear the PID; the file itself stays, since removing it could let a
+    /// starting instance lock a file nobody else can see any more.
+    fn drop(&mut self) {
+        let _ = self.file.set_len(0);
+    }
+}
@@ -0,0 +1,74 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Varies break lengths with `--break-jitter`, so the end of a break is
+/// harder to watch for. A xorshift generator like the one in the noise
+/// source: nothing here needs more than an even spread, and a fixed seed
+/// gives the same lengths every time.
+pub struct Jitter {
+    /// Largest change either way, in seconds
+    range: u64,
+    state: u64,
+}
+
+impl Jitter {
+    pub fn new(range: u64, seed: u64) -> Self {
+        // Xorshift never leaves zero
+        Self { range, state: seed.max(1) }
+    }
+
+    /// Seeded from the clock, for a different sequence every run.
+    pub fn from_clock(range: u64) -> Self {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_nanos());
+        Self::new(range, nanos as u64)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// `seconds` moved by up to `range` either way, and at least one second.
+    pub fn apply(&mut self, seconds: u64) -> u64 {
+        let offset = (self.next() % (2 * self.range + 1)) as i64 - self.range as i64;
+        seconds.saturating_add_signed(offset).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lengths_stay_within_the_range_and_cover_it() {
+        let mut jitter = Jitter::new(60, 42);
+        let lengths: Vec<u64> = (0..2000).map(|_| jitter.apply(300)).collect();
+        assert!(lengths.iter().all(|length| (240..=360).contains(length)));
+        assert_eq!(lengths.iter().min(), Some(&240));
+        assert_eq!(lengths.iter().max(), Some(&360));
+    }
+
+    #[test]
+    fn a_short_break_never_drops_below_one_second() {
+        let mut jitter = Jitter::new(600, 7);
+        assert!((0..1000).all(|_| (1..=610).contains(&jitter.apply(10))));
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_lengths() {
+        let (mut a, mut b) = (Jitter::new(30, 1234), Jitter::new(30, 1234));
+        for _ in 0..100 {
+            assert_eq!(a.apply(300), b.apply(300));
+        }
+        // A zero seed would stall xorshift at zero forever
+        let mut zero = Jitter::new(30, 0);
+        assert!((0..100).any(|_| zero.apply(300) != 270));
+    }
+
+    #[test]
+    fn no_range_changes_nothing() {
+        let mut jitter = Jitter::new(0, 99);
+        assert!((0..100).all(|_| jitter.apply(300) == 300));
+    }
+}
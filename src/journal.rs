@@ -0,0 +1,100 @@
+use crate::session_log;
+use chrono::{DateTime, Local, NaiveDate, NaiveTime};
+use serde::Deserialize;
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Asked in the prompt and written above each answer.
+pub const QUESTION: &str = "How did today go?";
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct JournalConfig {
+    pub enabled: bool,
+    /// Quitting from this time of day on asks first
+    pub after: NaiveTime,
+    /// `journal.md` next to the event log when not set
+    pub file: Option<PathBuf>,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            after: NaiveTime::from_hms_opt(18, 0, 0).expect("valid time"),
+            file: None,
+        }
+    }
+}
+
+/// The end-of-day prompt: asked once the daily goal is met, or on the
+/// first quit after `after`, and at most once a day. Answers go into a
+/// markdown file, one section per day with the day's totals.
+pub struct Journal {
+    path: PathBuf,
+    after: NaiveTime,
+    day_start: NaiveTime,
+    /// The day last asked about in this run, answered or not
+    asked: Option<NaiveDate>,
+}
+
+impl Journal {
+    /// `None` unless turned on in the config.
+    pub fn new(config: &JournalConfig, day_start: NaiveTime) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        let path = config.file.clone().or_else(default_path)?;
+        Some(Self {
+            path,
+            after: config.after,
+            day_start,
+            asked: None,
+        })
+    }
+
+    /// Whether today still needs an entry: not asked yet in this run and
+    /// not already in the file from an earlier one.
+    pub fn due(&self, now: DateTime<Local>) -> bool {
+        let day = session_log::day_of(now, self.day_start);
+        self.asked != Some(day) && !written_on(&self.path, day)
+    }
+
+    /// Whether quitting now should ask first.
+    pub fn due_on_quit(&self, now: DateTime<Local>) -> bool {
+        now.time() >= self.after && self.due(now)
+    }
+
+    /// Note that the prompt was shown, so a skipped one is not asked again
+    /// until tomorrow.
+    pub fn asked(&mut self, now: DateTime<Local>) {
+        self.asked = Some(session_log::day_of(now, self.day_start));
+    }
+
+    /// Append today's section: the day's totals and the answer.
+    pub fn write(&self, now: DateTime<Local>, totals: &str, answer: &str) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let day = session_log::day_of(now, self.day_start);
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        write!(file, "{}\n\n{}\n\n**{}**\n\n{}\n\n", heading(day), totals, QUESTION, answer)
+    }
+}
+
+fn default_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("pomo").join("journal.md"))
+}
+
+/// e.g. `## Wed 2024-06-05`
+fn heading(day: NaiveDate) -> String {
+    format!("## {}", day.format("%a %Y-%m-%d"))
+}
+
+fn written_on(path: &Path, day: NaiveDate) -> bool {
+    let heading = heading(day);
+    fs::read_to_string(path).is_ok_and(|contents| contents.lines().any(|line| line == heading))
+}
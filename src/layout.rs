@@ -0,0 +1,178 @@
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+
+/// One block of the screen, in the order given by `--layout`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Section {
+    Header,
+    Focus,
+    Break,
+    Controls,
+    /// The focus and break timers side by side
+    Timers,
+}
+
+pub const DEFAULT_LAYOUT: [Section; 4] = [Section::Header, Section::Focus, Section::Break, Section::Controls];
+
+/// Size of the built-in big `00:00` digits, as (width, height).
+pub const BUILT_IN_DIGITS: (u16, u16) = (45, 5);
+
+/// Below this the compact view does not fit either, as (width, height).
+pub const COMPACT_MIN: (u16, u16) = (40, 15);
+
+/// How much of the UI fits on the screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Variant {
+    /// Big digits in the chosen layout
+    Full,
+    /// The same blocks with the time as plain text
+    Compact,
+    /// Only a notice asking for more room
+    TooSmall,
+}
+
+/// Pick the variant for the current screen size, on every frame so a resize
+/// takes effect right away.
+pub fn variant(area: Rect, layout: &[Section], no_break: bool, digits: (u16, u16)) -> Variant {
+    let fits = |(width, height): (u16, u16)| area.width >= width && area.height >= height;
+    if fits(minimum_size(layout, no_break, digits)) {
+        Variant::Full
+    } else if fits(COMPACT_MIN) {
+        Variant::Compact
+    } else {
+        Variant::TooSmall
+    }
+}
+
+/// The smallest screen `layout` fits on without blocks overlapping or the
+/// `digits` being cut off, as (width, height).
+pub fn minimum_size(layout: &[Section], no_break: bool, (digits_width, digits_height): (u16, u16)) -> (u16, u16) {
+    // Border plus the one-cell margin around everything
+    let timer_width = digits_width + 2;
+    let timer_height = digits_height + 2;
+    let mut width = timer_width;
+    let mut height = 0;
+    for section in layout {
+        match section {
+            Section::Header | Section::Controls => height += 3,
+            Section::Focus => height += timer_height,
+            Section::Break if no_break => {}
+            Section::Break => height += timer_height,
+            Section::Timers => {
+                height += timer_height;
+                if !no_break {
+                    width = 2 * timer_width;
+                }
+            }
+        }
+    }
+    (width + 2, height + 2)
+}
+
+/// Where each block ends up on screen.
+pub struct Areas {
+    pub header: Rect,
+    pub focus: Rect,
+    pub break_: Rect,
+    pub controls: Rect,
+}
+
+/// Parse a comma-separated list such as `controls,header,timers`. Every block
+/// has to appear exactly once, with `timers` standing in for `focus,break`.
+pub fn parse(spec: &str) -> Result<Vec<Section>, String> {
+    let mut layout = Vec::new();
+    for name in spec.split(',').map(str::trim) {
+        let section = match name {
+            "header" => Section::Header,
+            "focus" => Section::Focus,
+            "break" => Section::Break,
+            "controls" => Section::Controls,
+            "timers" => Section::Timers,
+            _ => return Err(format!("unknown section `{}`", name)),
+        };
+        if layout.contains(&section) {
+            return Err(format!("`{}` is listed twice", name));
+        }
+        layout.push(section);
+    }
+
+    let has = |section| layout.contains(&section);
+    if has(Section::Timers) && (has(Section::Focus) || has(Section::Break)) {
+        return Err("`timers` already includes `focus` and `break`".to_string());
+    }
+    let timers = has(Section::Timers) || (has(Section::Focus) && has(Section::Break));
+    if !(has(Section::Header) && has(Section::Controls) && timers) {
+        return Err("header, focus, break and controls must all be listed".to_string());
+    }
+    Ok(layout)
+}
+
+/// Split the screen by `layout`. Without breaks the break timer gets no
+/// space and focus takes it over. Compact timers only need room for one line,
+/// full ones for `digits_height` lines and the progress bar.
+pub fn split(area: Rect, layout: &[Section], no_break: bool, compact: bool, digits_height: u16) -> Areas {
+    let timer_constraint = Constraint::Min(if compact { 3 } else { digits_height + 3 });
+    let break_constraint = if no_break {
+        Constraint::Length(0)
+    } else {
+        timer_constraint
+    };
+    let constraints = layout.iter().map(|section| match section {
+        Section::Header | Section::Controls => Constraint::Length(3),
+        Section::Focus | Section::Timers => timer_constraint,
+        Section::Break => break_constraint,
+    });
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(constraints)
+        .split(area);
+
+    let mut areas = Areas {
+        header: Rect::default(),
+        focus: Rect::default(),
+        break_: Rect::default(),
+        controls: Rect::default(),
+    };
+    for (section, &chunk) in layout.iter().zip(chunks.iter()) {
+        match section {
+            Section::Header => areas.header = chunk,
+            Section::Focus => areas.focus = chunk,
+            Section::Break => areas.break_ = chunk,
+            Section::Controls => areas.controls = chunk,
+            Section::Timers => {
+                let break_width = if no_break { Constraint::Length(0) } else { Constraint::Percentage(50) };
+                let timers = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Min(0), break_width])
+                    .split(chunk);
+                areas.focus = timers[0];
+                areas.break_ = timers[1];
+            }
+        }
+    }
+    areas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_row_or_one_column_is_too_small() {
+        for (width, height) in [(1, 1), (1, 200), (300, 1), (0, 0)] {
+            let area = Rect::new(0, 0, width, height);
+            assert_eq!(variant(area, &DEFAULT_LAYOUT, false, BUILT_IN_DIGITS), Variant::TooSmall);
+        }
+    }
+
+    #[test]
+    fn the_variant_steps_down_at_the_minimum_sizes() {
+        let (width, height) = minimum_size(&DEFAULT_LAYOUT, false, BUILT_IN_DIGITS);
+        assert_eq!((width, height), (49, 22));
+        let pick = |width, height| variant(Rect::new(0, 0, width, height), &DEFAULT_LAYOUT, false, BUILT_IN_DIGITS);
+        assert_eq!(pick(width, height), Variant::Full);
+        assert_eq!(pick(width, height - 1), Variant::Compact);
+        assert_eq!(pick(COMPACT_MIN.0, COMPACT_MIN.1), Variant::Compact);
+        assert_eq!(pick(COMPACT_MIN.0 - 1, COMPACT_MIN.1), Variant::TooSmall);
+    }
+}
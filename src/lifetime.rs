@@ -0,0 +1,60 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// All-time counters kept in a tiny file next to the event log, so the UI can
+/// show lifetime totals without reading the whole log at startup.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Lifetime {
+    pub total_pomodoros: u64,
+    pub total_focus_minutes: u64,
+    /// Longest run of consecutive days with at least one pomodoro
+    pub best_streak: u32,
+    pub current_streak: u32,
+    pub last_active: Option<NaiveDate>,
+    /// The highest pomodoro milestone celebrated, so none is celebrated twice
+    pub celebrated: u64,
+}
+
+impl Lifetime {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("pomo").join("lifetime.json"))
+    }
+
+    /// Read the counters; a missing file means nothing has been recorded yet.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(io::Error::from),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write to a temporary file and rename it over the old one, so an
+    /// interrupted save never leaves a truncated file behind.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&temp, path)
+    }
+
+    pub fn record_pomodoro(&mut self, day: NaiveDate, focus_minutes: u64) {
+        self.total_pomodoros += 1;
+        self.total_focus_minutes += focus_minutes;
+
+        match self.last_active {
+            Some(last) if last == day => {}
+            Some(last) if last.succ_opt() == Some(day) => self.current_streak += 1,
+            _ => self.current_streak = 1,
+        }
+        self.last_active = Some(day);
+        self.best_streak = self.best_streak.max(self.current_streak);
+    }
+}
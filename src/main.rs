@@ -1,33 +1,589 @@
-use clap::Parser;
+mod adaptive;
+mod announce;
+mod attach;
+mod audio;
+mod celebration;
+mod checklist;
+mod clipboard;
+mod config;
+#[cfg(unix)]
+mod daemon;
+mod debug;
+mod desktop;
+mod dnd;
+mod escalation;
+mod font;
+mod gradient;
+mod i18n;
+mod icons;
+mod import;
+mod input;
+mod instance;
+mod journal;
+mod jitter;
+mod layout;
+mod lifetime;
+mod microbreak;
+mod noise;
+mod progress;
+mod review;
+mod selftest;
+mod session_log;
+mod shutdown;
+mod slack;
+mod sound;
+mod splash;
+mod state;
+mod stats;
+mod status;
+mod stopwatch;
+mod suggestions;
+mod sync_hook;
+mod tags;
+mod template;
+mod textarea;
+mod webhook;
+mod wizard;
+
+use adaptive::AdaptiveBreak;
+use announce::Announcer;
+use audio::{Audio, Volume};
+use celebration::Confetti;
+use checklist::Checklist;
+use chrono::{DateTime, Local, NaiveDate, NaiveTime};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use config::{ClockFormat, Config, ConfigWatcher, Preset};
+use debug::DebugPane;
+use dnd::Dnd;
+use escalation::{Alarm, Escalation, Nudge};
+use font::Font;
+use gradient::Gradient;
+use i18n::Strings;
+use icons::{IconSet, Icons};
+use input::Keys;
+use jitter::Jitter;
+use journal::Journal;
+use layout::Section;
+use lifetime::Lifetime;
+use microbreak::MicroBreaks;
+use noise::NoiseKind;
+use serde::{Deserialize, Serialize};
+use session_log::{Adjustment, Interruption, LateRating, LogEntry, Rating, Rounding, SessionLog};
+use state::State;
+use template::Template;
+use textarea::{Outcome, TextArea};
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::KeyCode,
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{self, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
-    backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout},
+    backend::{Backend, CrosstermBackend},
+    layout::{Alignment, Constraint, Flex, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Clear, Gauge, Paragraph, Wrap},
     Frame, Terminal,
 };
-use rodio::{OutputStream, Sink, Source};
 use std::{
-    io,
+    env,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
     time::{Duration, Instant},
 };
+use shutdown::Shutdown;
+use slack::Slack;
+use sync_hook::SyncHook;
+use sound::{Sound, SoundTheme};
+use suggestions::Suggestions;
+use webhook::Webhook;
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, after_help = EXIT_STATUS_HELP)]
 struct Args {
-    /// Focus time in minutes
-    #[arg(short, long, default_value_t = 25)]
-    focus: u64,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Use a preset rhythm: pomodoro (25/5, every fourth break 15),
+    /// 52-17, ultradian (90/20), or a preset from the config file
+    #[arg(long)]
+    preset: Option<String>,
+
+    /// Focus time in minutes [default: 25]
+    #[arg(short, long)]
+    focus: Option<u64>,
+
+    /// Break time in minutes [default: 5]
+    #[arg(short, long)]
+    break_time: Option<u64>,
+
+    /// Focus time in seconds, for testing and very short intervals
+    #[arg(long, conflicts_with = "focus", value_parser = clap::value_parser!(u64).range(1..))]
+    focus_secs: Option<u64>,
+
+    /// Break time in seconds
+    #[arg(long, conflicts_with = "break_time", value_parser = clap::value_parser!(u64).range(1..))]
+    break_secs: Option<u64>,
+
+    /// Keep the break at this fraction of the focus time, e.g. 0.2
+    #[arg(long, value_parser = parse_fraction, conflicts_with_all = ["break_time", "break_secs"])]
+    break_ratio: Option<f64>,
+
+    /// UI language, e.g. `de` (default: detected from LANG)
+    #[arg(long)]
+    lang: Option<String>,
+
+    /// Label for the sessions of this run, recorded in the event log; `#words` in it become tags
+    #[arg(short, long)]
+    task: Option<String>,
+
+    /// Tag the sessions of this run, e.g. `--tag client-a --tag writing`
+    #[arg(long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+
+    /// Pomodoros the task should take; also written as `--task "write report [4]"`
+    #[arg(long, requires = "task", value_parser = clap::value_parser!(u32).range(1..))]
+    estimate: Option<u32>,
+
+    /// Category for the focus sessions of this run, one of `categories` in the config
+    #[arg(long)]
+    category: Option<String>,
+
+    /// Built-in notification sound theme [default: beep]
+    #[arg(long, value_enum)]
+    sound_theme: Option<SoundTheme>,
+
+    /// Sound file to play instead of the theme (wav, mp3, ogg or flac)
+    #[arg(long, value_name = "PATH")]
+    sound_file: Option<PathBuf>,
+
+    /// Phase to start in, e.g. a break right after a long meeting
+    #[arg(long, value_enum, default_value_t = Phase::Focus, conflicts_with = "no_break")]
+    start_with: Phase,
+
+    /// How the first phase begins: right away, paused until a key, or after a 3-2-1 countdown
+    #[arg(long, value_enum, default_value_t = StartMode::Auto)]
+    start_mode: StartMode,
+
+    /// Go straight from one focus session to the next, without breaks
+    #[arg(long)]
+    no_break: bool,
+
+    /// Save break time cut short with s, to add to a later break with k
+    #[arg(long, conflicts_with = "no_break")]
+    break_bank: bool,
+
+    /// Length of the one-off break z starts in the middle of focus
+    #[arg(long, value_name = "MINUTES", default_value_t = QUICK_BREAK_MINUTES, value_parser = clap::value_parser!(u64).range(1..))]
+    quick_break_minutes: u64,
+
+    /// Make each break up to this many seconds longer or shorter, at random
+    #[arg(long, value_name = "SECONDS", conflicts_with = "no_break")]
+    break_jitter: Option<u64>,
+
+    /// Make each break the focus time actually worked divided by --break-divisor,
+    /// within --min-break and --max-break
+    #[arg(long, conflicts_with_all = ["no_break", "break_ratio"])]
+    adaptive_break: bool,
+
+    /// With --adaptive-break, what the focus time worked is divided by
+    #[arg(long, value_name = "N", default_value_t = 5.0, requires = "adaptive_break", value_parser = parse_divisor)]
+    break_divisor: f64,
+
+    /// With --adaptive-break, the shortest break in minutes
+    #[arg(long, value_name = "MINUTES", default_value_t = 1, requires = "adaptive_break")]
+    min_break: u64,
+
+    /// With --adaptive-break, the longest break in minutes
+    #[arg(long, value_name = "MINUTES", default_value_t = 30, requires = "adaptive_break")]
+    max_break: u64,
+
+    /// After this many focus sessions without a long break, the next break is a long one that cannot be skipped
+    #[arg(long, value_name = "N", conflicts_with = "no_break", value_parser = clap::value_parser!(u32).range(1..))]
+    max_consecutive: Option<u32>,
+
+    /// Disable pause, reset, skip, quick breaks and length changes, and ask before quitting
+    #[arg(long)]
+    strict: bool,
+
+    /// Hide the key hints below the timers
+    #[arg(long)]
+    hide_controls: bool,
+
+    /// Copy the summary printed on exit to the clipboard too
+    #[arg(long)]
+    copy_summary: bool,
+
+    /// Do not show a suggestion like "Refill your water" during breaks
+    #[arg(long)]
+    no_suggestions: bool,
+
+    /// Do not show the break checklist from the config
+    #[arg(long)]
+    no_checklist: bool,
+
+    /// High contrast without relying on color, with the state spelled out in the header;
+    /// with --headless, also print the state as plain text for screen readers
+    #[arg(long)]
+    accessible: bool,
+
+    /// Run the timer in this terminal without the TUI; `pomo attach` shows it
+    #[arg(long)]
+    headless: bool,
+
+    /// Distraction-free focus: same as --strict --hide-controls
+    #[arg(long)]
+    deep_work: bool,
+
+    /// While focusing, ask every this many minutes without a keypress whether
+    /// you are still there, and pause if nobody answers (with --strict, only ask)
+    #[arg(long, value_name = "MINUTES", value_parser = clap::value_parser!(u64).range(1..))]
+    presence_check: Option<u64>,
+
+    /// Wait for a key before starting each phase; the alarm repeats, more and
+    /// more insistently, until one is pressed
+    #[arg(long)]
+    manual: bool,
+
+    /// Show a progress ring beside each timer (a bar on small terminals)
+    #[arg(long)]
+    ring: bool,
+
+    /// Shift the running timer towards orange and red over the last third of a phase
+    #[arg(long)]
+    color_transition: bool,
+
+    /// Also ring the terminal bell on every transition
+    #[arg(long)]
+    terminal_bell: bool,
+
+    /// Start with every sound off, the terminal bell included; m turns it back on
+    #[arg(long)]
+    mute: bool,
+
+    /// Flash the whole screen on every transition instead of only the header
+    #[arg(long)]
+    full_flash: bool,
+
+    /// On quitting, let a notification that is still playing finish first
+    #[arg(long)]
+    wait_sound_on_exit: bool,
+
+    /// Show progress along the bottom border of each timer
+    #[arg(long)]
+    border_progress: bool,
+
+    /// What to do if the terminal is too small for the full layout at startup
+    #[arg(long, value_enum, default_value_t = SmallTerminal::Compact)]
+    small_terminal: SmallTerminal,
+
+    /// Start even if another timer is already running
+    #[arg(long)]
+    force: bool,
+
+    /// Figlet font (.flf) for the big digits
+    #[arg(long, value_name = "PATH")]
+    font_file: Option<PathBuf>,
+
+    /// Play a soft tone when a focus session is halfway done
+    #[arg(long)]
+    halfway_sound: bool,
+
+    /// Fraction of the focus session left when the halfway tone plays
+    #[arg(long, default_value_t = 0.5, value_parser = parse_fraction, requires = "halfway_sound")]
+    midpoint_fraction: f64,
+
+    /// Background noise played while focusing
+    #[arg(long, value_enum)]
+    noise: Option<NoiseKind>,
+
+    /// Volume of the background noise, from 0 to 1 [default: 0.1, or `ambient` under `[sound.volume]`]
+    #[arg(long, value_parser = parse_volume, requires = "noise")]
+    noise_volume: Option<f32>,
+
+    /// Exit after a single focus session, skipping its break
+    #[arg(long, conflicts_with = "cycles")]
+    once: bool,
+
+    /// Exit after this many focus and break cycles
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    cycles: Option<u32>,
+
+    /// Time of day (HH:MM) at which the daily cycle count starts over
+    #[arg(long, default_value = "00:00", value_parser = session_log::parse_day_start)]
+    day_start: NaiveTime,
+
+    /// Round the lengths written to the event log; the timer keeps exact time
+    #[arg(long, value_enum, value_name = "MODE")]
+    log_rounding: Option<Rounding>,
+
+    /// Exit after this many minutes of wall time, whatever the phase
+    #[arg(long, value_name = "MINUTES")]
+    max_runtime: Option<u64>,
+
+    /// Line printed by `pomo status`, e.g. "{phase} {remaining} ({cycles})"; replaces `[format] status`
+    #[arg(long, global = true, value_name = "TEMPLATE", value_parser = Template::parse)]
+    status_format: Option<Template>,
+
+    /// Order of the screen blocks, e.g. `controls,header,timers`
+    /// (`timers` puts focus and break side by side)
+    #[arg(long, value_name = "SECTIONS")]
+    layout: Option<String>,
+
+    /// Record key presses with their timing to this file
+    #[arg(long, value_name = "PATH", hide = true)]
+    record_keys: Option<PathBuf>,
+
+    /// Show the timer's internal state in a pane; F12 toggles it
+    #[arg(long)]
+    debug: bool,
+
+    /// Start without the splash showing the version and settings
+    #[arg(long)]
+    no_splash: bool,
+
+    /// Replay key presses recorded with --record-keys, then continue from the keyboard
+    #[arg(long, value_name = "PATH", hide = true)]
+    replay_keys: Option<PathBuf>,
+
+    /// Do not write to the event log
+    #[arg(long)]
+    no_log: bool,
+
+    /// Do not run the sync_command from the config, e.g. while offline
+    #[arg(long)]
+    no_sync: bool,
+
+    /// Shell command that turns do-not-disturb on, run as focus starts
+    #[arg(long, value_name = "CMD")]
+    enable_dnd_cmd: Option<String>,
+
+    /// Shell command that turns do-not-disturb off, run as focus stops and on exit
+    #[arg(long, value_name = "CMD")]
+    disable_dnd_cmd: Option<String>,
 
-    /// Break time in minutes  
-    #[arg(short, long, default_value_t = 5)]
-    break_time: u64,
+    /// Path to the config file (default: ~/.config/pomo/config.toml)
+    #[arg(long)]
+    config: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show completed phases from the event log
+    Log(LogArgs),
+    /// Play the notification sound once and exit
+    TestSound,
+    /// Summarize focus time from the event log
+    Stats(stats::StatsArgs),
+    /// Print one day's sessions from the event log in order, with the day's totals
+    Replay {
+        /// `today`, `yesterday` or a date like 2024-06-01
+        #[arg(default_value = "today")]
+        day: String,
+    },
+    /// Add sessions exported from another pomodoro app to the event log
+    Import(import::ImportArgs),
+    /// Page through the event log day by day, with each day's timeline and totals
+    Review {
+        /// Only show sessions tagged with this, e.g. client-a
+        #[arg(long, value_parser = tags::parse)]
+        tag: Option<String>,
+    },
+    /// Run the setup wizard and save its answers to the config file
+    Init,
+    /// Print a shell completion script, e.g. `pomo completions bash > ~/.local/share/bash-completion/completions/pomo`
+    Completions {
+        shell: clap_complete::Shell,
+    },
+    /// Inspect the config file
+    #[command(subcommand)]
+    Config(config::ConfigCommand),
+    /// Count up from zero like a stopwatch, with laps; prints the total on exit
+    Up,
+    /// Print the state of the running timer, e.g. `Focus 12:34 cycle 3`
+    Status {
+        /// Print the full status as JSON instead
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the built-in sound themes, or preview one
+    Sounds {
+        /// Theme to play (its focus-end and break-end sounds)
+        theme: Option<SoundTheme>,
+    },
+    /// Run the timer in the background, without the TUI; takes the same options as `pomo`
+    Start,
+    /// Time a focus phase against the real clock and report any drift
+    #[command(hide = true)]
+    Selftest(selftest::SelftestArgs),
+    /// Show the background timer started with `pomo start`
+    Attach,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
+struct LogArgs {
+    #[command(subcommand)]
+    action: Option<LogAction>,
+
+    /// Keep printing new entries as a running pomo appends them
+    #[arg(long)]
+    follow: bool,
+
+    /// Only show entries from today
+    #[arg(long, conflicts_with = "since")]
+    today: bool,
+
+    /// Only show entries since a date (YYYY-MM-DD) or a time today (HH:MM)
+    #[arg(long, value_parser = session_log::parse_since)]
+    since: Option<DateTime<Local>>,
+}
+
+#[derive(Subcommand, Debug)]
+enum LogAction {
+    /// Delete the event log
+    Clear {
+        /// Do not ask for confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+const EXIT_STATUS_HELP: &str = "\
+Exit status:
+  0  the goal set with --once or --cycles was reached, or none was set
+  1  an internal error occurred
+  2  quit before the goal was reached
+  3  the run was abandoned with x";
+
+/// Why the main loop stopped, which decides the exit status.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExitReason {
+    Quit,
+    Completed,
+    MaxRuntime,
+    Abandoned,
+}
+
+impl ExitReason {
+    /// The exit status for this reason, as `EXIT_STATUS_HELP` lists them;
+    /// `has_goal` is whether `--once` or `--cycles` set one.
+    fn code(self, has_goal: bool) -> ExitCode {
+        match self {
+            ExitReason::Completed => ExitCode::SUCCESS,
+            ExitReason::Abandoned => ExitCode::from(3),
+            // Scripts chaining on `pomo --once` must not proceed after an early exit
+            ExitReason::Quit | ExitReason::MaxRuntime if has_goal => ExitCode::from(2),
+            ExitReason::Quit | ExitReason::MaxRuntime => ExitCode::SUCCESS,
+        }
+    }
+}
+
+const DEFAULT_FOCUS_MINUTES: u64 = 25;
+const DEFAULT_BREAK_MINUTES: u64 = 5;
+/// Shortest break that counts as a long one, and so the shortest rest
+/// `--max-consecutive` forces; the pomodoro preset's long break.
+const LONG_BREAK_MINUTES: u64 = 15;
+/// Length of the break `z` takes in the middle of focus.
+const QUICK_BREAK_MINUTES: u64 = 5;
+
+/// Focus lengths for `d` followed by `1`–`9`.
+const QUICK_FOCUS_MINUTES: [u64; 9] = [5, 10, 15, 20, 25, 30, 35, 40, 45];
+
+const TOAST_DURATION: Duration = Duration::from_secs(2);
+
+/// Narrower headers leave out the clock to keep room for the title.
+const CLOCK_MIN_WIDTH: u16 = 60;
+
+/// Length of the get-ready count of `--start-mode countdown`, in seconds.
+const COUNTDOWN_SECS: u64 = 3;
+/// Length of the fade between the timer blocks when the phase changes.
+const TRANSITION_ANIMATION: Duration = Duration::from_millis(600);
+/// Poll interval while the fade runs; the rest of the time it is 100 ms.
+const ANIMATION_FRAME: Duration = Duration::from_millis(33);
+/// One fade out and back in of the paused banner.
+const PAUSE_PULSE: Duration = Duration::from_millis(1600);
+/// How long each on and off step of the `--full-flash` strobe lasts.
+const FULL_FLASH_STROBE: Duration = Duration::from_millis(250);
+/// Longest `--wait-sound-on-exit` holds up quitting.
+const SOUND_EXIT_LIMIT: Duration = Duration::from_secs(3);
+/// Longest the last `sync_command` holds up quitting.
+const SYNC_EXIT_LIMIT: Duration = Duration::from_secs(10);
+/// Master volume change per press of `v` or `V`.
+const VOLUME_STEP: f32 = 0.1;
+
+/// How long the presence prompt waits for a key before pausing.
+const PRESENCE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A short confirmation shown above the controls for a couple of seconds.
+struct Toast {
+    message: String,
+    shown_at: Instant,
+}
+
+impl Toast {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            shown_at: Instant::now(),
+        }
+    }
+
+    fn is_visible(&self) -> bool {
+        self.shown_at.elapsed() < TOAST_DURATION
+    }
+}
+
+/// What to do when the terminal is too small for the full layout at startup.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum SmallTerminal {
+    /// Exit with a message naming the size needed
+    Abort,
+    /// Start anyway with the compact view, saying so on screen
+    Compact,
+    /// Start without checking
+    Ignore,
+}
+
+/// The startup check for `--small-terminal`: an error with `abort`, and
+/// with `compact` a notice for the first toast, since a line on stderr
+/// would be hidden behind the alternate screen. Too small for even the
+/// compact view needs no notice; the screen itself says so.
+fn small_terminal_notice(
+    mode: SmallTerminal,
+    (width, height): (u16, u16),
+    layout: &[Section],
+    no_break: bool,
+    digits: (u16, u16),
+) -> Result<Option<String>, String> {
+    let variant = layout::variant(Rect::new(0, 0, width, height), layout, no_break, digits);
+    match (mode, variant) {
+        (SmallTerminal::Ignore, _) | (_, layout::Variant::Full) => Ok(None),
+        (SmallTerminal::Abort, _) => {
+            let (min_width, min_height) = layout::minimum_size(layout, no_break, digits);
+            Err(format!(
+                "the terminal is {}×{} but needs at least {}×{}; enlarge it or pass --small-terminal compact",
+                width, height, min_width, min_height
+            ))
+        }
+        (SmallTerminal::Compact, layout::Variant::Compact) => {
+            let (min_width, min_height) = layout::minimum_size(layout, no_break, digits);
+            Ok(Some(format!("Compact view; the full one needs {}×{}", min_width, min_height)))
+        }
+        (SmallTerminal::Compact, layout::Variant::TooSmall) => Ok(None),
+    }
+}
+
+/// How the first phase of a run begins.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum StartMode {
+    /// Start counting at launch
+    Auto,
+    /// Start paused, until a key is pressed
+    Prompt,
+    /// Count down from 3 with a beep each second first
+    Countdown,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -37,106 +593,674 @@ enum TimerState {
     Paused,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Phase {
+    Focus,
+    Break,
+}
+
+/// A phase boundary reported by `PomodoroTimer::update`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Transition {
+    FocusEnd,
+    BreakEnd,
+}
+
+impl From<Phase> for TimerState {
+    fn from(phase: Phase) -> Self {
+        match phase {
+            Phase::Focus => TimerState::Focus,
+            Phase::Break => TimerState::Break,
+        }
+    }
+}
+
+impl Transition {
+    fn event_name(self) -> &'static str {
+        match self {
+            Transition::FocusEnd => "focus_end",
+            Transition::BreakEnd => "break_end",
+        }
+    }
+}
+
+/// One phase of the repeating schedule. The default schedule is a single
+/// unnamed focus followed by a single unnamed break.
+#[derive(Debug, Clone)]
+struct Stage {
+    /// Shown in the timer titles; unnamed phases use the plain labels
+    name: Option<String>,
+    kind: Phase,
+    seconds: u64,
+}
+
+impl Stage {
+    fn default_schedule(focus_seconds: u64, break_seconds: u64) -> Vec<Stage> {
+        vec![
+            Stage {
+                name: None,
+                kind: Phase::Focus,
+                seconds: focus_seconds,
+            },
+            Stage {
+                name: None,
+                kind: Phase::Break,
+                seconds: break_seconds,
+            },
+        ]
+    }
+}
+
+/// Focus and break timers driven by a schedule. Each timer shows the running
+/// phase of its kind, or the next one to come; `focus_duration` and
+/// `break_duration` are those phases' lengths.
 struct PomodoroTimer {
+    schedule: Vec<Stage>,
+    /// Index of the running (or paused) phase in `schedule`
+    position: usize,
     focus_remaining: u64,
     break_remaining: u64,
     focus_duration: u64,
     break_duration: u64,
     state: TimerState,
     last_update: Instant,
+    /// When the timer was last paused; only meaningful while it is
+    paused_since: Instant,
     total_cycles: u32,
+    /// Cycles completed since the start of the current day
+    cycles_today: u32,
+    today: Option<NaiveDate>,
     notification_flash: bool,
     flash_timer: Instant,
+    last_transition: Option<(Transition, Instant)>,
+    session_start: Instant,
+    max_runtime: Option<Duration>,
+    finished: bool,
+    no_break: bool,
+    /// Break seconds skipped and not yet spent; `None` without `--break-bank`
+    break_bank: Option<u64>,
+    /// While set, the break length follows every focus change
+    break_ratio: Option<f64>,
+    /// Varies each break as it starts; the schedule keeps the nominal length
+    break_jitter: Option<Jitter>,
+    /// Sizes each break from `focus_worked` instead of the schedule
+    adaptive_break: Option<AdaptiveBreak>,
+    /// Seconds the running focus session has counted down so far
+    focus_worked: u64,
+    midpoint_fraction: Option<f64>,
+    /// Set by `update` when focus crosses the midpoint; cleared by the caller
+    midpoint_reached: bool,
+    once: bool,
+    cycle_limit: Option<u32>,
+    /// With `--manual` each phase waits for a key, so the end of the run can
+    /// only be estimated
+    manual: bool,
+    /// Set once the run reaches the end requested with `--once` or `--cycles`
+    completed: bool,
+    /// The config preset the lengths came from, until they are changed by hand
+    preset: Option<String>,
+    /// What focus sessions are logged under; `None` is uncategorized
+    category: Option<String>,
+    /// Pomodoros the task is expected to take, compared with `total_cycles`
+    estimate: Option<u32>,
+    /// Seconds left of the get-ready count before the first phase starts
+    countdown: Option<u64>,
+    /// Focus sessions allowed in a row before a long break is forced
+    max_consecutive: Option<u32>,
+    /// Focus sessions since the last long break
+    consecutive: u32,
+    /// Set while a forced long break runs; it cannot be skipped
+    mandatory_rest: bool,
+    /// How the running focus session was rated with `+`/`-`, logged when it ends
+    rating: Option<Rating>,
+    /// Set when a preset changes the running phase's length part way through,
+    /// so its time left no longer matches the length shown
+    resized: bool,
+    /// Length of the break `z` starts, in seconds
+    quick_break_secs: u64,
+    /// While a quick break runs, what it took over from focus
+    quick_break: Option<QuickBreak>,
+    /// Set by `update` when a quick break ends; cleared by the caller
+    quick_break_over: bool,
+}
+
+/// The timers as a quick break found them, put back when it ends.
+#[derive(Debug, Clone, Copy)]
+struct QuickBreak {
+    focus_remaining: u64,
+    break_remaining: u64,
+    break_duration: u64,
 }
 
 impl PomodoroTimer {
-    fn new(focus_minutes: u64, break_minutes: u64) -> Self {
-        let focus_duration = focus_minutes * 60;
-        let break_duration = break_minutes * 60;
-        Self {
-            focus_remaining: focus_duration,
-            break_remaining: break_duration,
-            focus_duration,
-            break_duration,
+    /// The timer never reads the clock itself: every time-dependent method
+    /// takes `now`, so callers (and tests) control how time advances.
+    fn new(schedule: Vec<Stage>, now: Instant) -> Self {
+        let mut timer = Self {
+            schedule,
+            position: 0,
+            focus_remaining: 0,
+            break_remaining: 0,
+            focus_duration: 0,
+            break_duration: 0,
             state: TimerState::Focus,
-            last_update: Instant::now(),
+            last_update: now,
+            paused_since: now,
             total_cycles: 0,
+            cycles_today: 0,
+            today: None,
             notification_flash: false,
-            flash_timer: Instant::now(),
+            flash_timer: now,
+            last_transition: None,
+            session_start: now,
+            max_runtime: None,
+            finished: false,
+            no_break: false,
+            break_bank: None,
+            break_ratio: None,
+            break_jitter: None,
+            adaptive_break: None,
+            focus_worked: 0,
+            midpoint_fraction: None,
+            midpoint_reached: false,
+            once: false,
+            cycle_limit: None,
+            manual: false,
+            completed: false,
+            preset: None,
+            category: None,
+            estimate: None,
+            countdown: None,
+            max_consecutive: None,
+            consecutive: 0,
+            mandatory_rest: false,
+            rating: None,
+            resized: false,
+            quick_break_secs: QUICK_BREAK_MINUTES * 60,
+            quick_break: None,
+            quick_break_over: false,
+        };
+        timer.start_at(Phase::Focus);
+        timer
+    }
+
+    /// Begin at the first phase of `kind` with both timers full.
+    fn start_at(&mut self, kind: Phase) {
+        self.position = 0;
+        self.position = self.upcoming(kind).unwrap_or(0);
+        // Cleared first, since a quick break makes any phase look like a break
+        self.quick_break = None;
+        self.state = self.current_phase().into();
+        self.resized = false;
+        self.focus_worked = 0;
+        self.load(Phase::Focus);
+        self.load(Phase::Break);
+    }
+
+    /// Index of the running phase of `kind`, or of the next one in the schedule.
+    fn upcoming(&self, kind: Phase) -> Option<usize> {
+        let len = self.schedule.len();
+        (0..len)
+            .map(|offset| (self.position + offset) % len)
+            .find(|&i| self.schedule[i].kind == kind)
+    }
+
+    /// Fill the timer for `kind` from its upcoming phase.
+    fn load(&mut self, kind: Phase) {
+        let seconds = self.upcoming(kind).map_or(0, |i| self.schedule[i].seconds);
+        match kind {
+            Phase::Focus => (self.focus_duration, self.focus_remaining) = (seconds, seconds),
+            Phase::Break => (self.break_duration, self.break_remaining) = (seconds, seconds),
+        }
+    }
+
+    /// The running phase, or the paused one; a quick break is a break.
+    fn current_phase(&self) -> Phase {
+        if self.quick_break.is_some() {
+            return Phase::Break;
+        }
+        self.schedule[self.position].kind
+    }
+
+    /// The phase that just ended, right after `update` or `skip` reported it.
+    fn ended(&self) -> &Stage {
+        &self.schedule[(self.position + self.schedule.len() - 1) % self.schedule.len()]
+    }
+
+    /// The name of the phase a timer shows, if the schedule names it.
+    fn stage_name(&self, kind: Phase) -> Option<&str> {
+        self.upcoming(kind).and_then(|i| self.schedule[i].name.as_deref())
+    }
+
+    /// The name of the phase after the running one, if the schedule names it.
+    fn next_name(&self) -> Option<&str> {
+        self.schedule[(self.position + 1) % self.schedule.len()].name.as_deref()
+    }
+
+    /// Length of a long break: the longest in the schedule, and at least
+    /// `LONG_BREAK_MINUTES`.
+    fn long_break_secs(&self) -> u64 {
+        self.schedule
+            .iter()
+            .filter(|stage| stage.kind == Phase::Break)
+            .map(|stage| stage.seconds)
+            .fold(LONG_BREAK_MINUTES * 60, u64::max)
+    }
+
+    /// Move on to the next phase in the schedule once the running one is over.
+    fn advance(&mut self, now: Instant) -> Transition {
+        let ended = self.current_phase();
+        match ended {
+            Phase::Focus => {
+                self.total_cycles += 1;
+                self.cycles_today += 1;
+                self.consecutive += 1;
+            }
+            // Any break of a long break's length is the rest the count is for
+            Phase::Break if self.mandatory_rest || self.break_duration >= self.long_break_secs() => {
+                self.consecutive = 0;
+                self.mandatory_rest = false;
+            }
+            Phase::Break => {}
+        }
+        self.position = (self.position + 1) % self.schedule.len();
+        let next = self.current_phase();
+        self.resized = false;
+
+        // The finished timer shows 00:00 until its kind comes round again,
+        // with the length of that next phase in the settings
+        if ended != next {
+            let seconds = self.upcoming(ended).map_or(0, |i| self.schedule[i].seconds);
+            match ended {
+                Phase::Focus => (self.focus_duration, self.focus_remaining) = (seconds, 0),
+                Phase::Break => (self.break_duration, self.break_remaining) = (seconds, 0),
+            }
+        }
+        self.load(next);
+        let worked = std::mem::take(&mut self.focus_worked);
+        if ended == Phase::Focus
+            && next == Phase::Break
+            && let Some(adaptive) = self.adaptive_break
+        {
+            self.break_duration = adaptive.length(worked);
+            self.break_remaining = self.break_duration;
+        }
+        if next == Phase::Break
+            && let Some(jitter) = &mut self.break_jitter
+        {
+            // The settings and progress show the length this break really has
+            self.break_duration = jitter.apply(self.break_duration);
+            self.break_remaining = self.break_duration;
+        }
+        if next == Phase::Break && self.max_consecutive.is_some_and(|max| self.consecutive >= max) {
+            self.mandatory_rest = true;
+            self.break_duration = self.break_duration.max(self.long_break_secs());
+            self.break_remaining = self.break_duration;
+        }
+        self.state = next.into();
+        self.notification_flash = true;
+        self.flash_timer = now;
+        match ended {
+            Phase::Focus => Transition::FocusEnd,
+            Phase::Break => Transition::BreakEnd,
         }
     }
 
-    fn update(&mut self) -> bool {
-        let now = Instant::now();
+    fn update(&mut self, now: Instant) -> Option<Transition> {
+        // Only whole seconds count; the rest carries over to the next update
         let elapsed = now.duration_since(self.last_update).as_secs();
-        self.last_update = now;
+        self.last_update += Duration::from_secs(elapsed);
 
-        let mut sound_needed = false;
+        // No phase time is spent until the get-ready count is over
+        if let Some(left) = self.countdown {
+            self.countdown = left.checked_sub(elapsed).filter(|&left| left > 0);
+            return None;
+        }
+
+        let mut transition = None;
 
         match self.state {
             TimerState::Focus => {
                 if self.focus_remaining > elapsed {
+                    let before = self.focus_remaining;
                     self.focus_remaining -= elapsed;
+                    self.focus_worked += elapsed;
+                    if let Some(fraction) = self.midpoint_fraction {
+                        let midpoint = self.focus_duration as f64 * fraction;
+                        if before as f64 > midpoint && self.focus_remaining as f64 <= midpoint {
+                            self.midpoint_reached = true;
+                        }
+                    }
                 } else {
-                    self.focus_remaining = 0;
-                    self.state = TimerState::Break;
-                    self.total_cycles += 1;
-                    sound_needed = true;
-                    self.notification_flash = true;
-                    self.flash_timer = Instant::now();
+                    self.focus_worked += self.focus_remaining;
+                    transition = Some(self.advance(now));
                 }
             }
             TimerState::Break => {
                 if self.break_remaining > elapsed {
                     self.break_remaining -= elapsed;
+                } else if self.quick_break.is_some() {
+                    self.end_quick_break(now);
+                    self.quick_break_over = true;
                 } else {
-                    self.break_remaining = 0;
-                    self.focus_remaining = self.focus_duration;
-                    self.state = TimerState::Focus;
-                    sound_needed = true;
-                    self.notification_flash = true;
-                    self.flash_timer = Instant::now();
+                    transition = Some(self.advance(now));
                 }
             }
             TimerState::Paused => {}
         }
 
-        // Update flash notification
-        if self.notification_flash && self.flash_timer.elapsed() > Duration::from_secs(2) {
-            self.notification_flash = false;
+        if let Some(transition) = transition {
+            self.last_transition = Some((transition, now));
+            self.completed = self.is_final(transition);
+        }
+
+        transition
+    }
+
+    /// Whether this transition ends the run. A cycle ends with its break,
+    /// or with its focus session when there are no breaks.
+    fn is_final(&self, transition: Transition) -> bool {
+        let cycle_done = transition == Transition::BreakEnd || self.no_break;
+        (self.once && transition == Transition::FocusEnd)
+            || self.cycle_limit.is_some_and(|limit| cycle_done && self.total_cycles >= limit)
+    }
+
+    /// Seconds until the run asked for with `--cycles` or `--once` is over,
+    /// following the schedule on from the running phase, and whether that
+    /// is only an estimate: with `--manual` each phase waits for a key, and
+    /// adaptive and jittered breaks are not known before they start.
+    fn run_left(&self) -> Option<(u64, bool)> {
+        if (self.cycle_limit.is_none() && !self.once) || self.completed {
+            return None;
+        }
+        let limit = self.cycle_limit.unwrap_or(1);
+        let mut estimate = self.manual;
+        let mut left = self.countdown.unwrap_or(0);
+        let (mut cycles, mut consecutive, mut position) = (self.total_cycles, self.consecutive, self.position);
+        let mut phase = self.current_phase();
+        let mut seconds = match phase {
+            Phase::Focus => self.focus_remaining,
+            Phase::Break => self.break_remaining,
+        };
+        // A quick break goes back to the focus session it interrupted
+        if self.quick_break.is_some() {
+            left += self.break_remaining;
+            (phase, seconds) = (Phase::Focus, self.focus_remaining);
+        }
+        let mut long = self.mandatory_rest;
+        // Focus time of the session before each break, for adaptive breaks
+        let mut worked = self.focus_worked;
+        loop {
+            left += seconds;
+            // As `advance` and `is_final` would see each phase end
+            match phase {
+                Phase::Focus => {
+                    worked += seconds;
+                    cycles += 1;
+                    consecutive += 1;
+                    if self.once || (self.no_break && cycles >= limit) {
+                        return Some((left, estimate));
+                    }
+                }
+                Phase::Break if cycles >= limit => return Some((left, estimate)),
+                Phase::Break if long || seconds >= self.long_break_secs() => consecutive = 0,
+                Phase::Break => {}
+            }
+            let focused = std::mem::take(&mut worked);
+            position = (position + 1) % self.schedule.len();
+            (phase, seconds) = (self.schedule[position].kind, self.schedule[position].seconds);
+            long = false;
+            if phase == Phase::Break {
+                if let Some(adaptive) = self.adaptive_break {
+                    seconds = adaptive.length(focused);
+                    estimate = true;
+                }
+                estimate |= self.break_jitter.is_some();
+                if self.max_consecutive.is_some_and(|max| consecutive >= max) {
+                    seconds = seconds.max(self.long_break_secs());
+                    long = true;
+                }
+            }
+        }
+    }
+
+    /// End the current phase now, exactly as if its time had run out.
+    fn skip(&mut self, now: Instant) -> Option<Transition> {
+        // During the get-ready count, skip only skips the count
+        if self.countdown.take().is_some() {
+            self.last_update = now;
+            return None;
+        }
+        match self.state {
+            TimerState::Focus => self.focus_remaining = 0,
+            TimerState::Break if self.quick_break.is_some() => {
+                self.end_quick_break(now);
+                return None;
+            }
+            TimerState::Break if self.mandatory_rest => return None,
+            TimerState::Break => {
+                // Catch up first so only time really left goes into the bank
+                if self.update(now).is_some() {
+                    return None;
+                }
+                if let Some(bank) = &mut self.break_bank {
+                    *bank += self.break_remaining;
+                }
+                self.break_remaining = 0;
+            }
+            TimerState::Paused => return None,
+        }
+        self.last_update = now;
+        self.update(now)
+    }
+
+    /// Take a break of `quick_break_secs` in the middle of focus, running or
+    /// paused, then pick focus up where it stopped. It is not part of the
+    /// schedule, so it counts no cycle and is not logged. Returns whether
+    /// one started.
+    fn start_quick_break(&mut self, now: Instant) -> bool {
+        if self.current_phase() != Phase::Focus || self.countdown.is_some() || self.no_break {
+            return false;
+        }
+        self.quick_break = Some(QuickBreak {
+            focus_remaining: self.focus_remaining,
+            break_remaining: self.break_remaining,
+            break_duration: self.break_duration,
+        });
+        (self.break_duration, self.break_remaining) = (self.quick_break_secs, self.quick_break_secs);
+        self.state = TimerState::Break;
+        self.last_update = now;
+        true
+    }
+
+    /// Back to the focus session the quick break interrupted, running.
+    fn end_quick_break(&mut self, now: Instant) {
+        let Some(before) = self.quick_break.take() else {
+            return;
+        };
+        self.focus_remaining = before.focus_remaining;
+        (self.break_duration, self.break_remaining) = (before.break_duration, before.break_remaining);
+        self.state = TimerState::Focus;
+        self.last_update = now;
+    }
+
+    /// Add the whole break bank to the running break. Returns the seconds added.
+    fn spend_break_bank(&mut self) -> Option<u64> {
+        if self.state != TimerState::Break {
+            return None;
+        }
+        let seconds = self.break_bank.as_mut().map(std::mem::take).filter(|&seconds| seconds > 0)?;
+        self.break_remaining += seconds;
+        Some(seconds)
+    }
+
+    /// Start the daily count over when the day changes, e.g. when pomo is
+    /// left running overnight. The run's total is kept for the summary.
+    fn roll_over_day(&mut self, today: NaiveDate) {
+        if self.today.is_some_and(|day| day != today) {
+            self.cycles_today = 0;
         }
+        self.today = Some(today);
+    }
+
+    /// Flash the most recent transition again; the caller replays its sound.
+    fn replay_notification(&mut self, now: Instant) -> Option<Transition> {
+        let (transition, _) = self.last_transition?;
+        self.notification_flash = true;
+        self.flash_timer = now;
+        Some(transition)
+    }
+
+    fn is_flashing(&self, now: Instant) -> bool {
+        self.notification_flash && now.duration_since(self.flash_timer) <= Duration::from_secs(2)
+    }
+
+    /// How far the fade after the latest phase change has run, from 0 to 1,
+    /// or `None` once it is over. Only drawing looks at this.
+    fn transition_progress(&self, now: Instant) -> Option<(Transition, f32)> {
+        let (transition, at) = self.last_transition?;
+        let elapsed = now.duration_since(at);
+        (elapsed < TRANSITION_ANIMATION)
+            .then(|| (transition, elapsed.as_secs_f32() / TRANSITION_ANIMATION.as_secs_f32()))
+    }
 
-        sound_needed
+    /// Hard cap on the whole run, checked every frame even while paused.
+    fn check_max_runtime(&mut self, now: Instant) {
+        if let Some(max_runtime) = self.max_runtime
+            && now.duration_since(self.session_start) >= max_runtime
+        {
+            self.finished = true;
+        }
     }
 
-    fn toggle_pause(&mut self) {
+    fn toggle_pause(&mut self, now: Instant) {
         self.state = match self.state {
             TimerState::Focus => TimerState::Paused,
             TimerState::Break => TimerState::Paused,
-            TimerState::Paused => TimerState::Focus,
+            TimerState::Paused => self.current_phase().into(),
         };
-        self.last_update = Instant::now();
+        self.last_update = now;
+        self.paused_since = now;
     }
 
-    fn reset(&mut self) {
-        self.focus_remaining = self.focus_duration;
-        self.break_remaining = self.break_duration;
-        self.state = TimerState::Focus;
-        self.last_update = Instant::now();
+    /// How long the timer has been paused, or `None` while it runs.
+    fn paused_for(&self, now: Instant) -> Option<Duration> {
+        (self.state == TimerState::Paused).then(|| now.duration_since(self.paused_since))
+    }
+
+    /// Start over from the first focus phase of the schedule; not during a
+    /// forced rest, which would end it early.
+    fn reset(&mut self, now: Instant) {
+        if self.mandatory_rest {
+            return;
+        }
+        self.start_at(Phase::Focus);
+        self.last_update = now;
         self.notification_flash = false;
+        self.countdown = None;
+        // It rated the session that was started over
+        self.rating = None;
+    }
+
+    /// Give the phases of `kind` a new length. Every focus phase changes;
+    /// of the breaks, those as long as the one the break timer shows, so a
+    /// long break keeps its own length unless it is the one shown.
+    fn resize_stages(&mut self, kind: Phase, seconds: u64) {
+        let Some(shown) = self.upcoming(kind) else {
+            return;
+        };
+        let before = self.schedule[shown].seconds;
+        for stage in self.schedule.iter_mut().filter(|stage| stage.kind == kind) {
+            if kind == Phase::Focus || stage.seconds == before {
+                stage.seconds = seconds;
+            }
+        }
     }
 
-    fn adjust_focus_time(&mut self, minutes: u64) {
-        self.focus_duration = minutes * 60;
-        if self.state == TimerState::Focus || self.state == TimerState::Paused {
+    /// Change the length of focus, for the phase the focus timer shows and
+    /// every one after it.
+    fn adjust_focus_time(&mut self, seconds: u64) {
+        // A waiting timer still full shows the new length too, rather than the
+        // old one until its phase starts
+        let untouched = self.focus_remaining == self.focus_duration;
+        self.focus_duration = seconds;
+        self.preset = None;
+        self.resize_stages(Phase::Focus, seconds);
+        if self.state == TimerState::Focus || self.state == TimerState::Paused || untouched {
             self.focus_remaining = self.focus_duration;
+            // The running phase starts over at its new length
+            self.resized &= self.current_phase() != Phase::Focus;
         }
+        // So does focus interrupted by a quick break, once it picks up again
+        if let Some(quick_break) = &mut self.quick_break {
+            quick_break.focus_remaining = self.focus_duration;
+            self.focus_remaining = self.focus_duration;
+        }
+        if let Some(ratio) = self.break_ratio {
+            self.link_break(ratio);
+        }
+    }
+
+    /// Derive the break from the focus time, to the nearest minute and at least one.
+    fn link_break(&mut self, ratio: f64) {
+        self.break_ratio = Some(ratio);
+        let minutes = (self.focus_duration as f64 / 60.0 * ratio).round() as u64;
+        self.adjust_break_time(minutes.max(1) * 60);
     }
 
-    fn adjust_break_time(&mut self, minutes: u64) {
-        self.break_duration = minutes * 60;
-        if self.state == TimerState::Break || self.state == TimerState::Paused {
+    fn adjust_break_time(&mut self, seconds: u64) {
+        let untouched = self.break_remaining == self.break_duration;
+        self.break_duration = seconds;
+        self.preset = None;
+        self.resize_stages(Phase::Break, seconds);
+        if self.state == TimerState::Break || self.state == TimerState::Paused || untouched {
             self.break_remaining = self.break_duration;
+            self.resized &= self.current_phase() != Phase::Break;
+        }
+    }
+
+    /// Switch to a preset's lengths without restarting: the running phase
+    /// keeps the same share of its time left, e.g. halfway stays halfway.
+    fn apply_preset(&mut self, preset: &Preset) {
+        let focus_left = self.focus_remaining as f64 / self.focus_duration.max(1) as f64;
+        let break_left = self.break_remaining as f64 / self.break_duration.max(1) as f64;
+        let (started, before) = match self.current_phase() {
+            Phase::Focus => (self.focus_remaining < self.focus_duration, self.focus_duration),
+            Phase::Break => (self.break_remaining < self.break_duration, self.break_duration),
+        };
+        let resized = self.resized;
+        // The preset names its break, so it replaces one derived from --break-ratio
+        self.break_ratio = None;
+        self.adjust_focus_time(preset.focus * 60);
+        self.adjust_break_time(preset.break_time * 60);
+        self.focus_remaining = (focus_left * self.focus_duration as f64).round() as u64;
+        self.break_remaining = (break_left * self.break_duration as f64).round() as u64;
+        self.preset = Some(preset.name.clone());
+        let after = match self.current_phase() {
+            Phase::Focus => self.focus_duration,
+            Phase::Break => self.break_duration,
+        };
+        self.resized = resized || (started && after != before);
+    }
+
+    fn summary(&self, now: Instant) -> String {
+        let elapsed = now.duration_since(self.session_start).as_secs();
+        format!(
+            "Completed {} pomodoro cycles in {}h {:02}m",
+            self.total_cycles,
+            elapsed / 3600,
+            elapsed % 3600 / 60
+        )
+    }
+
+    /// A setting for the footer: whole minutes as `25min`, otherwise `1m30s` or `45s`.
+    fn format_duration(seconds: u64, strings: &Strings) -> String {
+        match (seconds / 60, seconds % 60) {
+            (minutes, 0) => format!("{}{}", minutes, strings.minutes),
+            (0, seconds) => format!("{}s", seconds),
+            (minutes, seconds) => format!("{}m{}s", minutes, seconds),
         }
     }
 
@@ -246,209 +1370,3081 @@ impl PomodoroTimer {
     }
 }
 
-fn play_notification_sound() {
-    tokio::spawn(async {
-        // Try to play sound, but don't crash if audio device is unavailable
-        if let Ok((_stream, stream_handle)) = OutputStream::try_default() {
-            if let Ok(sink) = Sink::try_new(&stream_handle) {
-                // Generate 3 beeps with pauses between them
-                for i in 0..3 {
-                    // Generate a sine wave beep
-                    let beep = rodio::source::SineWave::new(800.0) // 800 Hz frequency
-                        .take_duration(Duration::from_millis(200)) // 0.2 seconds
-                        .amplify(0.20); // 20% volume
-                    
-                    sink.append(beep);
-                    
-                    // Add a pause between beeps (except after the last one)
-                    if i < 2 {
-                        let silence = rodio::source::SineWave::new(0.0) // Silent "beep"
-                            .take_duration(Duration::from_millis(150)) // 0.15 seconds pause
-                            .amplify(0.0); // 0% volume (silence)
-                        sink.append(silence);
-                    }
-                }
-                
-                let _ = sink.sleep_until_end(); // Ignore errors if audio playback fails
-            }
+impl Args {
+    /// Command line flags win over the config file; `--sound-file` also
+    /// replaces the per-transition files.
+    fn sound(&self, config: &Config) -> Sound {
+        let from_config = |file: &Option<PathBuf>| file.clone().filter(|_| self.sound_file.is_none());
+        Sound {
+            escalate: config.sound.escalate,
+            nudge: config.sound.nudge,
+            theme: self.sound_theme.or(config.sound.theme).unwrap_or_default(),
+            // `pomo config check` and the footer say why an invalid one is ignored
+            melody: sound::parse_melody(&config.sound.melody).unwrap_or_default(),
+            volume: Volume {
+                ambient: self.noise_volume.unwrap_or(config.sound.volume.ambient),
+                ..config.sound.volume
+            },
+            overlap: config.sound.overlap,
+            file: self.sound_file.clone().or_else(|| config.sound.file.clone()),
+            focus_end: from_config(&config.sound.focus_end),
+            break_end: from_config(&config.sound.break_end),
+            enabled: config.sound.enabled,
         }
-        // If audio fails, we simply continue without sound notification
-    });
-}
+    }
 
-fn draw_ui(f: &mut Frame, timer: &PomodoroTimer) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(1)
-        .constraints([
-            Constraint::Length(3),  // Header
-            Constraint::Min(8),     // Focus timer
-            Constraint::Min(8),     // Break timer
-            Constraint::Length(3),  // Controls
-        ])
-        .split(f.area());
+    /// The break suggestions, unless turned off. None when the suggestions
+    /// file cannot be read; `config::validate` says why.
+    fn suggestions(&self, config: &Config) -> Option<Suggestions> {
+        if self.no_suggestions {
+            return None;
+        }
+        Suggestions::new(config.ui.suggestion_list().unwrap_or_default(), config.ui.suggestions_order)
+    }
 
-    // Header
-    let header_text = if timer.notification_flash {
-        "🔔 NOTIFICATION! 🔔"
-    } else {
-        "🍅 POMODORO TIMER 🍅"
-    };
-    let header_color = if timer.notification_flash {
-        Color::Yellow
-    } else {
-        Color::Red
+    /// The command that syncs the log after each entry. Without a log
+    /// there is nothing to sync.
+    fn sync_command(&self, config: &Config, logging: bool) -> Option<String> {
+        config.log.sync_command.clone().filter(|_| logging && !self.no_sync)
+    }
+
+    /// The break checklist items from the config, unless turned off.
+    fn checklist(&self, config: &Config) -> Vec<String> {
+        if self.no_checklist {
+            return Vec::new();
+        }
+        config.ui.checklist.clone()
+    }
+
+    /// The task label without its `#tags` and `[estimate]`; the tags
+    /// together with the `--tag` ones, normalized and each once; and the
+    /// estimate, with `--estimate` taking precedence.
+    fn label(&self) -> (Option<String>, Vec<String>, Option<u32>) {
+        let (label, estimate) = match &self.task {
+            Some(label) => split_estimate(label),
+            None => (String::new(), None),
+        };
+        let (task, from_label) = tags::split_label(&label);
+        (
+            task,
+            tags::dedup(self.tags.iter().cloned().chain(from_label)),
+            self.estimate.or(estimate),
+        )
+    }
+
+    /// The `--category`, which has to be one of those in the config.
+    fn category(&self, config: &Config) -> Result<Option<String>, String> {
+        match &self.category {
+            Some(name) if !config.timer.categories.contains(name) => Err(format!(
+                "unknown category '{}'; valid categories: {}",
+                name,
+                if config.timer.categories.is_empty() {
+                    "none, add them with `categories` under [timer]".to_string()
+                } else {
+                    config.timer.categories.join(", ")
+                }
+            )),
+            category => Ok(category.clone()),
+        }
+    }
+
+    /// The preset named with `--preset`, looked up among the built-in ones
+    /// and then the config's.
+    fn preset(&self, config: &Config) -> Result<Option<Preset>, String> {
+        let Some(name) = &self.preset else {
+            return Ok(None);
+        };
+        let presets: Vec<Preset> = Preset::built_in().into_iter().chain(config.timer.presets.clone()).collect();
+        match presets.iter().find(|preset| &preset.name == name) {
+            Some(preset) => Ok(Some(preset.clone())),
+            None => {
+                let names: Vec<&str> = presets.iter().map(|preset| preset.name.as_str()).collect();
+                Err(format!("unknown preset '{}'; valid presets: {}", name, names.join(", ")))
+            }
+        }
+    }
+
+    /// The phases to cycle through: a preset, with any lengths given on the
+    /// command line taking precedence; else those lengths; else the config's
+    /// schedule; else its focus and break lengths.
+    fn schedule(&self, config: &Config) -> Result<Vec<Stage>, String> {
+        let focus_flag = self.focus_secs.or(self.focus.map(|minutes| minutes * 60));
+        let break_flag = self.break_secs.or(self.break_time.map(|minutes| minutes * 60));
+        if let Some(preset) = self.preset(config)? {
+            let focus_secs = focus_flag.unwrap_or(preset.focus * 60);
+            let break_secs = break_flag.unwrap_or(preset.break_time * 60);
+            let mut schedule = Stage::default_schedule(focus_secs, break_secs);
+            if let Some(long_break) = preset.long_break {
+                // Four cycles, the last one ending in the long break
+                schedule = std::iter::repeat_n(schedule, 4).flatten().collect();
+                schedule[7] = Stage {
+                    name: Some("Long break".to_string()),
+                    kind: Phase::Break,
+                    seconds: long_break * 60,
+                };
+            }
+            return Ok(schedule);
+        }
+
+        let lengths_given = focus_flag.is_some() || break_flag.is_some();
+        if lengths_given || config.timer.schedule.is_empty() {
+            let focus_secs = self
+                .focus_secs
+                .unwrap_or(self.focus.or(config.timer.focus).unwrap_or(DEFAULT_FOCUS_MINUTES) * 60);
+            let break_secs = self
+                .break_secs
+                .unwrap_or(self.break_time.or(config.timer.break_time).unwrap_or(DEFAULT_BREAK_MINUTES) * 60);
+            return Ok(Stage::default_schedule(focus_secs, break_secs));
+        }
+
+        if !config.timer.schedule.iter().any(|entry| entry.kind == Phase::Focus) {
+            return Err("timer.schedule needs at least one focus phase".into());
+        }
+        config
+            .timer
+            .schedule
+            .iter()
+            .map(|entry| match entry.mins {
+                0 => Err(format!("timer.schedule: \"{}\" needs mins of at least 1", entry.name)),
+                mins => Ok(Stage {
+                    name: Some(entry.name.clone()),
+                    kind: entry.kind,
+                    seconds: mins * 60,
+                }),
+            })
+            .collect()
+    }
+
+    /// The `--font-file` font, or none plus a warning if it cannot be used.
+    fn font(&self) -> (Option<Font>, Option<String>) {
+        match self.font_file.as_deref().map(Font::load) {
+            None => (None, None),
+            Some(Ok(font)) => (Some(font), None),
+            Some(Err(e)) => (None, Some(format!("font: {}, using the built-in digits", e))),
+        }
+    }
+
+    /// The screen layout, or the default plus a warning if the spec is invalid.
+    fn layout(&self, config: &Config) -> (Vec<Section>, Option<String>) {
+        match self.layout.as_deref().or(config.ui.layout.as_deref()) {
+            None => (layout::DEFAULT_LAYOUT.to_vec(), None),
+            Some(spec) => match layout::parse(spec) {
+                Ok(layout) => (layout, None),
+                Err(e) => (layout::DEFAULT_LAYOUT.to_vec(), Some(format!("layout: {}, using the default", e))),
+            },
+        }
+    }
+}
+
+fn gradients(config: &Config) -> Option<(Gradient, Gradient)> {
+    gradient::supported().then(|| {
+        (
+            config.ui.focus_gradient.unwrap_or(gradient::FOCUS),
+            config.ui.break_gradient.unwrap_or(gradient::BREAK),
+        )
+    })
+}
+
+/// Missing sound files only fall back to the theme, so they get a warning
+/// rather than an error.
+fn missing_sound_warning(sound: &Sound) -> Option<String> {
+    sound
+        .missing_files()
+        .first()
+        .map(|path| format!("sound file {} not found, using the theme", path.display()))
+}
+
+/// Ignored keys do not stop the timer, they only show up as a warning.
+fn ignored_keys_warning(problems: &[config::Problem]) -> Option<String> {
+    problems
+        .iter()
+        .find(|problem| !problem.fatal)
+        .map(|problem| format!("config {} (see `pomo config check`)", problem))
+}
+
+/// The lengths and sound the run starts with, for the splash.
+fn splash_settings(timer: &PomodoroTimer, sound: &Sound, muted: bool, strings: &Strings) -> splash::Settings {
+    let format = |seconds| PomodoroTimer::format_duration(seconds, strings);
+    let breaks = || timer.schedule.iter().filter(|stage| stage.kind == Phase::Break).map(|stage| stage.seconds);
+    let break_ = if timer.no_break {
+        "none".to_string()
+    } else if timer.adaptive_break.is_some() {
+        strings.auto.to_string()
+    } else {
+        format(timer.break_duration)
+    };
+    // A schedule's longest break if it has a longer one, or the rest --max-consecutive forces
+    let long_break = match (breaks().min(), breaks().max()) {
+        (Some(shortest), Some(longest)) if longest > shortest => format(longest),
+        _ if timer.max_consecutive.is_some() => format(timer.long_break_secs()),
+        _ => "none".to_string(),
+    };
+    let theme = match &sound.file {
+        Some(file) => file.file_name().map_or_else(|| file.display().to_string(), |name| name.to_string_lossy().into()),
+        None => sound.theme.to_possible_value().expect("no hidden themes").get_name().to_string(),
+    };
+    let sound = match (sound.enabled, muted) {
+        (false, _) => "off",
+        (true, true) => "muted",
+        (true, false) => "on",
+    };
+    vec![
+        ("Focus", format(timer.focus_duration)),
+        ("Break", break_),
+        ("Long break", long_break),
+        ("Theme", theme),
+        ("Sound", sound.to_string()),
+    ]
+}
+
+/// A longer stretch in hours and minutes, e.g. `1h 37m`, rounded up to the minute.
+fn format_hours(seconds: u64) -> String {
+    match seconds.div_ceil(60) {
+        minutes if minutes < 60 => format!("{}m", minutes),
+        minutes if minutes % 60 == 0 => format!("{}h", minutes / 60),
+        minutes => format!("{}h {}m", minutes / 60, minutes % 60),
+    }
+}
+
+/// What `y` copies, e.g. `3 pomodoros / 75 min focus on 'write report' as of 14:02`.
+fn run_summary(cycles: u32, focus_secs: u64, task: Option<&str>) -> String {
+    let mut summary = format!(
+        "{} pomodoro{} / {} min focus",
+        cycles,
+        if cycles == 1 { "" } else { "s" },
+        focus_secs / 60
+    );
+    if let Some(task) = task {
+        summary.push_str(&format!(" on '{}'", task));
+    }
+    summary.push_str(&format!(" as of {}", Local::now().format("%H:%M")));
+    summary
+}
+
+/// Run `pomo attach` against the timer answering at `path`.
+fn attach_to(path: &Path, icons: &Icons) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    if status::query(path)?.is_none() {
+        println!("not running");
+        return Ok(ExitCode::FAILURE);
+    }
+    match attach::run(path, icons)? {
+        attach::Detach::Left => println!("Detached; the timer keeps running in the background"),
+        attach::Detach::Stopped => println!("The background timer has stopped"),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Take a trailing `[4]` off a task label as its estimate.
+fn split_estimate(label: &str) -> (String, Option<u32>) {
+    if let Some(rest) = label.trim_end().strip_suffix(']')
+        && let Some((task, estimate)) = rest.rsplit_once('[')
+        && let Ok(estimate) = estimate.trim().parse::<u32>()
+        && estimate > 0
+    {
+        return (task.trim_end().to_string(), Some(estimate));
+    }
+    (label.to_string(), None)
+}
+
+/// Pomodoro counts in the header's corner.
+#[derive(Clone, Copy)]
+struct Counts {
+    /// From the lifetime stats
+    all_time: Option<u64>,
+    /// Only with a `daily_goal`
+    today: Option<DailyGoal>,
+}
+
+/// Progress towards `daily_goal`.
+#[derive(Clone, Copy)]
+struct DailyGoal {
+    done: u32,
+    target: u32,
+}
+
+impl DailyGoal {
+    fn reached(self) -> bool {
+        self.done >= self.target
+    }
+}
+
+/// Focus sessions logged on `day`, by this run or earlier ones.
+fn focus_sessions_on(log: Option<&SessionLog>, day: NaiveDate, day_start: NaiveTime) -> u32 {
+    let entries = log.and_then(|log| log.entries().ok()).unwrap_or_default();
+    entries
+        .iter()
+        .filter(|entry| entry.event == Transition::FocusEnd && session_log::day_of(entry.at, day_start) == day)
+        .count() as u32
+}
+
+/// Minutes of focus logged today, for `{today_minutes}`; 0 without a log.
+fn today_focus_minutes(log: Option<&SessionLog>, day_start: NaiveTime) -> u64 {
+    let today = session_log::day_of(Local::now(), day_start);
+    let entries = log.and_then(|log| log.entries().ok()).unwrap_or_default();
+    let seconds: u64 = entries
+        .iter()
+        .filter(|entry| entry.event == Transition::FocusEnd && session_log::day_of(entry.at, day_start) == today)
+        .map(|entry| entry.duration_secs)
+        .sum();
+    seconds / 60
+}
+
+fn elapsed_fraction(remaining: u64, duration: u64) -> f64 {
+    // A break topped up from the bank can have more left than its length
+    (1.0 - remaining as f64 / duration.max(1) as f64).max(0.0)
+}
+
+/// One press of f/F/b/B: a minute, or 10 seconds below two minutes so
+/// short timers stay adjustable. Never goes below 10 seconds.
+fn step_duration(seconds: u64, increase: bool) -> u64 {
+    if increase {
+        seconds + if seconds < 120 { 10 } else { 60 }
+    } else {
+        seconds.saturating_sub(if seconds <= 120 { 10 } else { 60 }).max(10)
+    }
+}
+
+fn parse_fraction(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(fraction) if fraction > 0.0 && fraction < 1.0 => Ok(fraction),
+        _ => Err(format!("expected a number between 0 and 1, got '{}'", value)),
+    }
+}
+
+fn parse_divisor(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(divisor) if divisor >= 1.0 && divisor.is_finite() => Ok(divisor),
+        _ => Err(format!("expected a number of at least 1, got '{}'", value)),
+    }
+}
+
+fn parse_volume(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(volume) if (0.0..=1.0).contains(&volume) => Ok(volume),
+        _ => Err(format!("expected a volume between 0 and 1, got '{}'", value)),
+    }
+}
+
+/// Show fatal config problems before the TUI takes over the screen, and only
+/// start once the user has seen them.
+fn confirm_default_config(problems: &[config::Problem]) -> io::Result<()> {
+    eprintln!("The config file cannot be used:");
+    for problem in problems.iter().filter(|problem| problem.fatal) {
+        eprintln!("  {}", problem);
+    }
+    eprintln!("Press Enter to start with the default settings, or Ctrl-C to quit.");
+    io::stdin().read_line(&mut String::new())?;
+    Ok(())
+}
+
+/// Ask a yes/no question on stdin; anything but yes means no.
+fn confirm(question: &str) -> io::Result<bool> {
+    eprint!("{} [y/N] ", question);
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Hand the terminal to `$VISUAL`/`$EDITOR` for `path` and take it back once
+/// the editor exits. The timer runs on wall-clock time, so it simply catches
+/// up on the next update. The inner error is for the footer; the outer one
+/// means the terminal could not be handed over or taken back.
+fn run_editor<B: Backend + Write>(terminal: &mut Terminal<B>, path: &Path) -> io::Result<Result<(), String>> {
+    let default_editor = if cfg!(windows) { "notepad" } else { "vi" };
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| default_editor.to_string());
+    // $EDITOR may carry arguments, e.g. `code --wait`
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or(default_editor);
+
+    let handed_over = disable_raw_mode()
+        .and_then(|()| execute!(terminal.backend_mut(), LeaveAlternateScreen))
+        .and_then(|()| terminal.show_cursor());
+    let status = handed_over.map(|()| std::process::Command::new(program).args(words).arg(path).status());
+    // Take the terminal back whatever happened, so the timer can carry on
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    terminal.clear()?;
+
+    Ok(match status? {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("{} exited with {}, config not reloaded", program, status)),
+        Err(e) => Err(format!("cannot start {}: {}", program, e)),
+    })
+}
+
+/// The completion script for `shell`, as `pomo completions` prints it.
+fn write_completions(shell: clap_complete::Shell, out: &mut impl Write) {
+    clap_complete::generate(shell, &mut Args::command(), "pomo", out);
+}
+
+fn run_log(log: &SessionLog, args: &LogArgs) -> io::Result<()> {
+    if let Some(LogAction::Clear { yes }) = args.action {
+        if yes || confirm(&format!("Delete the event log {}?", log.path().display()))? {
+            log.clear()?;
+        }
+        return Ok(());
+    }
+
+    let since = if args.today {
+        Some(session_log::start_of_today())
+    } else {
+        args.since
+    };
+
+    // Plain `--follow` behaves like `tail -f` and only shows new entries
+    if since.is_some() || !args.follow {
+        let mut current_day = None;
+        for entry in log.records()? {
+            if since.is_some_and(|since| entry.at() < since) {
+                continue;
+            }
+            let day = entry.at().date_naive();
+            if current_day != Some(day) {
+                println!("── {} ──", day.format("%a %Y-%m-%d"));
+                current_day = Some(day);
+            }
+            println!("{}", entry.describe());
+        }
+    }
+
+    if args.follow {
+        log.follow(|entry| println!("{}", entry.describe()))?;
+    }
+    Ok(())
+}
+
+/// How the UI looks, fixed for the whole run.
+struct Appearance {
+    strings: &'static Strings,
+    icons: &'static Icons,
+    layout: Vec<Section>,
+    hide_controls: bool,
+    ring: bool,
+    /// `--border-progress`
+    border_progress: bool,
+    /// `--full-flash`
+    full_flash: bool,
+    /// Stick to ASCII where a glyph is only decoration, like the icon set
+    ascii: bool,
+    /// Focus and break digit gradients; flat colors when the terminal lacks true color
+    gradients: Option<(Gradient, Gradient)>,
+    /// Fade between the timer blocks on phase changes; needs true color too
+    animations: bool,
+    /// `--color-transition`
+    urgency_colors: bool,
+    truecolor: bool,
+    clock: ClockFormat,
+    /// `--font-file`, when it loaded
+    font: Option<Font>,
+    /// Active modes, shown in the top-left corner of the header
+    badges: Vec<String>,
+    /// `--task` and `--tag`, shown under the header
+    task: Option<String>,
+    tags: Vec<String>,
+    /// `--accessible`: white and gray with bold text, and the state in words
+    high_contrast: bool,
+    /// Under the break digits while a break runs
+    suggestions: Option<Suggestions>,
+    /// Under the break digits, above the suggestion, during scheduled breaks
+    checklist: Option<Checklist>,
+    /// Shown with the badges
+    muted: bool,
+}
+
+impl Appearance {
+    /// How the run looks, from the flags and the config.
+    fn new(args: &Args, config: &Config, layout: Vec<Section>, font: Option<Font>, tags: Vec<String>) -> Self {
+        let strings = Strings::detect(args.lang.as_deref());
+        let icon_set = config.ui.icons.unwrap_or_else(IconSet::detect);
+        let icons = icon_set.icons();
+        let mut appearance = Appearance {
+            strings,
+            icons,
+            layout,
+            hide_controls: args.hide_controls,
+            ring: args.ring,
+            border_progress: args.border_progress,
+            full_flash: args.full_flash,
+            ascii: icon_set == IconSet::Ascii,
+            gradients: gradients(config),
+            animations: config.ui.animations && gradient::supported(),
+            urgency_colors: args.color_transition,
+            truecolor: gradient::supported(),
+            clock: config.ui.clock,
+            font,
+            badges: [
+                args.deep_work.then(|| "DEEP WORK".to_string()),
+                args.presence_check.map(|minutes| format!("{} {}m", icons.presence, minutes)),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            task: args.task.clone(),
+            tags,
+            high_contrast: args.accessible,
+            suggestions: args.suggestions(config),
+            checklist: Checklist::new(args.checklist(config)),
+            muted: false,
+        };
+        if args.accessible {
+            // Shape and weight carry the state, not shades of color
+            appearance.gradients = None;
+            appearance.animations = false;
+            appearance.urgency_colors = false;
+        }
+        appearance
+    }
+}
+
+/// The paused banner's color: fading between yellow and gray, timed from
+/// the pause since the countdown stands still, or steady yellow without
+/// animations.
+fn pause_pulse(appearance: &Appearance, paused_for: Duration) -> Color {
+    if !appearance.animations {
+        return Color::Yellow;
+    }
+    let phase = (paused_for.as_millis() % PAUSE_PULSE.as_millis()) as f32 / PAUSE_PULSE.as_millis() as f32;
+    gradient::blend(gradient::YELLOW, gradient::DARK_GRAY, 1.0 - (2.0 * phase - 1.0).abs())
+}
+
+/// Fraction of the phase in which `--color-transition` shifts the color.
+const URGENT_FRACTION: f64 = 1.0 / 3.0;
+
+/// The running timer's digit color for `--color-transition`, once less than
+/// a third of the phase is left.
+fn urgency_color(
+    appearance: &Appearance,
+    remaining: u64,
+    duration: u64,
+    base: (u8, u8, u8),
+    base_named: Color,
+) -> Option<Color> {
+    let left = remaining as f64 / duration.max(1) as f64;
+    (appearance.urgency_colors && left < URGENT_FRACTION).then(|| {
+        let urgency = (1.0 - left / URGENT_FRACTION) as f32;
+        gradient::urgency(base, base_named, urgency, appearance.truecolor)
+    })
+}
+
+/// A timer's color: the phase's own while it runs and gray otherwise, or
+/// white and light gray with `--accessible`.
+fn timer_color(appearance: &Appearance, active: bool, color: Color) -> Color {
+    match (appearance.high_contrast, active) {
+        (true, true) => Color::White,
+        (true, false) => Color::Gray,
+        (false, true) => color,
+        (false, false) => Color::DarkGray,
+    }
+}
+
+/// The running timer's title, with what comes next when the schedule names it.
+fn stage_title(label: &str, icon: &str, next: Option<&str>) -> String {
+    match next {
+        Some(next) => format!("{} {} · next: {}", label, icon, next),
+        None => format!("{} {}", label, icon),
+    }
+}
+
+/// The big digits for `time`, in the `--font-file` font if there is one.
+fn big_digits(time: &str, font: Option<&Font>) -> Vec<String> {
+    font.and_then(|font| font.render(time))
+        .unwrap_or_else(|| PomodoroTimer::get_ascii_digits(time))
+}
+
+/// Style each line of big digits, fading down the gradient when there is one.
+fn digit_lines(ascii: &[String], style: Style, gradient: Option<Gradient>) -> Vec<Line<'static>> {
+    ascii
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let style = match gradient {
+                Some(gradient) => style.fg(gradient.at(i, ascii.len())),
+                None => style,
+            };
+            Line::from(Span::styled(line.clone(), style))
+        })
+        .collect()
+}
+
+/// The micro-break countdown in a box over the middle of the screen.
+fn draw_microbreak(f: &mut Frame, strings: &Strings, seconds_left: u64) {
+    let text = format!("{} — {}s", strings.look_away, seconds_left);
+    let width = (text.chars().count() as u16 + 6).min(f.area().width);
+    let [row] = Layout::vertical([Constraint::Length(3)]).flex(Flex::Center).areas(f.area());
+    let [area] = Layout::horizontal([Constraint::Length(width)]).flex(Flex::Center).areas(row);
+    let paragraph = Paragraph::new(text)
+        .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+/// Progress towards `--cycles` in the bottom margin, below every block
+/// whatever the layout, so it never takes room from the timers.
+fn render_goal(f: &mut Frame, done: u32, goal: u32, appearance: &Appearance) {
+    let screen = f.area();
+    let area = Rect {
+        x: screen.x + 1,
+        y: screen.bottom().saturating_sub(1),
+        width: screen.width.saturating_sub(2),
+        height: 1,
+    };
+    let color = if appearance.high_contrast { Color::White } else { Color::Magenta };
+    let gauge = Gauge::default()
+        .gauge_style(Style::default().fg(color).bg(Color::DarkGray))
+        .ratio((f64::from(done) / f64::from(goal.max(1))).min(1.0))
+        .label(Span::styled(
+            format!("{}: {}/{}", appearance.strings.session_goal, done.min(goal), goal),
+            Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+        ));
+    f.render_widget(gauge, area);
+}
+
+/// The break checklist in the rows under the break digits, as much of it as
+/// fits above the block's last row. Returns the rows it took.
+fn render_checklist(
+    f: &mut Frame,
+    inner: Rect,
+    digit_rows: u16,
+    checklist: &Checklist,
+    appearance: &Appearance,
+) -> u16 {
+    let inset = if appearance.ring { progress::RING_WIDTH + 2 } else { 1 };
+    let width = inner.width.saturating_sub(2 * inset);
+    let lines = checklist.lines(width, appearance.ascii);
+    let area = Rect {
+        x: inner.x + inset,
+        y: inner.y + digit_rows + 1,
+        width,
+        height: (lines.len() as u16).min(inner.height.saturating_sub(digit_rows + 2)),
+    };
+    if area.height == 0 || area.width == 0 {
+        return 0;
+    }
+    f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), area);
+    area.height
+}
+
+/// A suggestion wrapped in the rows between the break digits and the
+/// block's last row, which the line gauge uses; left out if there are none.
+fn render_suggestion(f: &mut Frame, inner: Rect, digit_rows: u16, suggestion: &str, ring: bool) {
+    let inset = if ring { progress::RING_WIDTH + 2 } else { 1 };
+    let area = Rect {
+        x: inner.x + inset,
+        y: inner.y + digit_rows + 1,
+        width: inner.width.saturating_sub(2 * inset),
+        height: inner.height.saturating_sub(digit_rows + 2),
+    };
+    if area.height == 0 || area.width == 0 {
+        return;
+    }
+    let paragraph = Paragraph::new(suggestion.to_string())
+        .style(Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC))
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    f.render_widget(paragraph, area);
+}
+
+fn draw_ui(
+    f: &mut Frame,
+    timer: &PomodoroTimer,
+    now: Instant,
+    appearance: &Appearance,
+    counts: Counts,
+    warning: Option<&str>,
+    toast: Option<&str>,
+) {
+    let strings = appearance.strings;
+    let icons = appearance.icons;
+
+    // Rather than squashing blocks on top of each other, fall back to plain
+    // digits and then to asking for more room
+    let digits = appearance.font.as_ref().map_or(layout::BUILT_IN_DIGITS, Font::time_size);
+    let variant = layout::variant(f.area(), &appearance.layout, timer.no_break, digits);
+    if variant == layout::Variant::TooSmall {
+        let (min_width, min_height) = layout::COMPACT_MIN;
+        let message = Paragraph::new(vec![
+            Line::from("Terminal too small"),
+            Line::from(format!("needs at least {}×{}", min_width, min_height)),
+        ])
+        .style(Style::default().fg(Color::Yellow))
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+        let [message_area] = Layout::vertical([Constraint::Length(2)]).flex(Flex::Center).areas(f.area());
+        f.render_widget(message, message_area);
+        return;
+    }
+
+    let compact = variant == layout::Variant::Compact;
+    let areas = layout::split(f.area(), &appearance.layout, timer.no_break, compact, digits.1);
+
+    // Header
+    let flashing = timer.is_flashing(now);
+    let header_text = match timer.last_transition {
+        Some((transition, _)) if flashing => {
+            format!("{0} {1} {0}", icons.bell, strings.transition(transition).to_uppercase())
+        }
+        // Spelled out, so the running phase does not depend on telling colors apart
+        _ if appearance.high_contrast => match timer.current_phase() {
+            Phase::Focus => format!("{} {}", strings.focus_time, PomodoroTimer::format_time(timer.focus_remaining)),
+            Phase::Break => format!("{} {}", strings.break_time, PomodoroTimer::format_time(timer.break_remaining)),
+        },
+        _ => format!("{0} {1} {0}", icons.tomato, strings.title),
+    };
+    let header_text = match timer.countdown {
+        Some(left) if !flashing => format!("{0} {1} {2} {0}", icons.tomato, strings.get_ready, left),
+        _ => header_text,
+    };
+    let header_text = if timer.mandatory_rest && !flashing {
+        format!("{0} {1} {0}", icons.break_, strings.mandatory_rest)
+    } else {
+        header_text
+    };
+    let paused_for = timer.paused_for(now);
+    let header_text = match paused_for {
+        Some(_) if !flashing => format!("{0} {1} {0}", icons.pause, strings.paused),
+        _ => header_text,
+    };
+    let header_color = if appearance.high_contrast {
+        Color::White
+    } else if flashing {
+        Color::Yellow
+    } else if let Some(paused_for) = paused_for {
+        pause_pulse(appearance, paused_for)
+    } else if timer.countdown.is_some() {
+        Color::Yellow
+    } else if timer.mandatory_rest {
+        Color::Magenta
+    } else {
+        Color::Red
+    };
+    // Paused, both timers fade into the background so the banner stands out
+    let dim = |style: Style| {
+        if paused_for.is_some() {
+            style.add_modifier(Modifier::DIM)
+        } else {
+            style
+        }
     };
+    let mut header_block = Block::default().borders(Borders::ALL).style(Style::default().fg(Color::Cyan));
+    let mut badges = appearance.badges.clone();
+    if appearance.muted {
+        badges.push(icons.muted.to_string());
+    }
+    if !badges.is_empty() {
+        header_block = header_block.title(Span::styled(
+            format!(" {} ", badges.join(" · ")),
+            Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some(goal) = counts.today {
+        let color = if goal.reached() { Color::Green } else { Color::Cyan };
+        header_block = header_block.title(
+            Line::from(Span::styled(
+                format!(" {}: {}/{} {} ", strings.today, goal.done, goal.target, icons.tomato),
+                Style::default().fg(color),
+            ))
+            .right_aligned(),
+        );
+    }
+    if let Some(all_time) = counts.all_time {
+        header_block = header_block
+            .title(Line::from(format!(" {}: {} {} ", strings.all_time, all_time, icons.tomato)).right_aligned());
+    }
+    if appearance.task.is_some() || !appearance.tags.is_empty() || timer.rating.is_some() {
+        let mut label = vec![Span::raw(" ")];
+        let mut width = areas.header.width.saturating_sub(4) as usize;
+        if let Some(task) = &appearance.task {
+            width = width.saturating_sub(task.chars().count() + 1);
+            label.push(Span::styled(task.clone(), Style::default().fg(Color::White)));
+        }
+        if let Some(estimate) = timer.estimate {
+            let progress = format!(" {}/{} {}", timer.total_cycles, estimate, icons.tomato);
+            width = width.saturating_sub(progress.chars().count());
+            let color = if timer.total_cycles > estimate { Color::Red } else { Color::White };
+            label.push(Span::styled(progress, Style::default().fg(color)));
+        }
+        if let Some(rating) = timer.rating {
+            let (text, color) = match rating {
+                Rating::Win => (format!(" + {}", rating.label()), Color::Green),
+                Rating::Fail => (format!(" - {}", rating.label()), Color::Red),
+            };
+            width = width.saturating_sub(text.chars().count());
+            label.push(Span::styled(text, Style::default().fg(color)));
+        }
+        label.extend(tags::chips(&appearance.tags, width));
+        label.push(Span::raw(" "));
+        header_block = header_block.title_bottom(Line::from(label).centered());
+    }
+    if let Some((left, estimate)) = timer.run_left() {
+        let at = chrono::Local::now() + chrono::Duration::seconds(left as i64);
+        let at = at.format(appearance.clock.pattern().unwrap_or("%H:%M")).to_string();
+        let at = if estimate { format!("~{}", at) } else { at };
+        let text = strings.run_ends.replace("{at}", &at).replace("{left}", &format_hours(left));
+        header_block = header_block.title_bottom(Line::from(format!(" {} ", text)).right_aligned());
+    }
+    // Equal columns either side keep the title centered whether or not the
+    // clock fits
+    let header_inner = header_block.inner(areas.header);
+    let clock = appearance
+        .clock
+        .pattern()
+        .filter(|_| areas.header.width >= CLOCK_MIN_WIDTH)
+        .map(|pattern| chrono::Local::now().format(pattern).to_string());
+    let clock_width = clock.as_ref().map_or(0, |clock| clock.len() as u16);
+    let [_, title_area, clock_area] = Layout::horizontal([
+        Constraint::Length(clock_width),
+        Constraint::Fill(1),
+        Constraint::Length(clock_width),
+    ])
+    .areas(header_inner);
+    f.render_widget(header_block, areas.header);
     let header = Paragraph::new(header_text)
         .style(Style::default().fg(header_color).add_modifier(Modifier::BOLD))
-        .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::ALL).style(Style::default().fg(Color::Cyan)));
-    f.render_widget(header, chunks[0]);
+        .alignment(Alignment::Center);
+    f.render_widget(header, title_area);
+    if let Some(clock) = clock {
+        f.render_widget(Paragraph::new(clock).alignment(Alignment::Right), clock_area);
+    }
+
+    // Right after a phase change the outgoing block dims while the incoming one
+    // brightens; this overrides both blocks' colors until the fade is over
+    let fade = timer
+        .transition_progress(now)
+        .filter(|_| appearance.animations)
+        .map(|(transition, t)| match transition {
+            Transition::FocusEnd => (
+                gradient::blend(gradient::GREEN, gradient::DARK_GRAY, t),
+                gradient::blend(gradient::DARK_GRAY, gradient::YELLOW, t),
+            ),
+            Transition::BreakEnd => (
+                gradient::blend(gradient::DARK_GRAY, gradient::GREEN, t),
+                gradient::blend(gradient::YELLOW, gradient::DARK_GRAY, t),
+            ),
+        });
 
     // Focus Timer
     let focus_active = timer.state == TimerState::Focus;
-    let focus_style = if focus_active {
-        Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
+    let mut focus_style = if focus_active {
+        Style::default().fg(timer_color(appearance, true, Color::Green)).add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(timer_color(appearance, false, Color::Green))
     };
+    let focus_urgency = urgency_color(
+        appearance,
+        timer.focus_remaining,
+        timer.focus_duration,
+        gradient::GREEN,
+        Color::Green,
+    )
+    .filter(|_| focus_active);
+    if let Some(color) = focus_urgency {
+        focus_style = focus_style.fg(color);
+    }
+    if let Some((focus_color, _)) = fade {
+        focus_style = focus_style.fg(focus_color);
+    }
+    focus_style = dim(focus_style);
     
     let focus_time = PomodoroTimer::format_time(timer.focus_remaining);
-    let focus_ascii = PomodoroTimer::get_ascii_digits(&focus_time);
+    let focus_ascii = if compact {
+        vec![focus_time]
+    } else {
+        big_digits(&focus_time, appearance.font.as_ref())
+    };
     
-    let focus_lines: Vec<Line> = focus_ascii
-        .iter()
-        .map(|line| Line::from(Span::styled(line.clone(), focus_style)))
-        .collect();
+    let focus_gradient = appearance
+        .gradients
+        .filter(|_| focus_active && fade.is_none() && focus_urgency.is_none())
+        .map(|(focus, _)| focus);
+    let focus_lines = digit_lines(&focus_ascii, focus_style, focus_gradient);
     
-    let focus_title = if focus_active { "FOCUS TIME ⚡" } else { "FOCUS TIME" };
+    let focus_label = timer.stage_name(Phase::Focus).unwrap_or(strings.focus_time);
+    let focus_title = if focus_active {
+        stage_title(focus_label, icons.focus, timer.next_name())
+    } else {
+        focus_label.to_string()
+    };
     let focus_block = Block::default()
         .title(focus_title)
         .borders(Borders::ALL)
-        .style(if focus_active {
-            Style::default().fg(Color::Green)
-        } else {
-            Style::default().fg(Color::DarkGray)
-        });
+        .style(dim(match fade {
+            Some((focus_color, _)) => Style::default().fg(focus_color),
+            None => Style::default().fg(timer_color(appearance, focus_active, Color::Green)),
+        }));
     
+    let focus_inner = focus_block.inner(areas.focus);
     let focus_paragraph = Paragraph::new(focus_lines)
         .block(focus_block)
         .alignment(Alignment::Center);
-    f.render_widget(focus_paragraph, chunks[1]);
+    f.render_widget(focus_paragraph, areas.focus);
+    let focus_elapsed = elapsed_fraction(timer.focus_remaining, timer.focus_duration);
+    let color = timer_color(appearance, focus_active, Color::Green);
+    if appearance.ring {
+        progress::render(f, focus_inner, focus_elapsed, color, digits);
+    }
+    if appearance.border_progress {
+        progress::render_border(f, areas.focus, focus_elapsed, color, appearance.ascii);
+    }
 
     // Break Timer
     let break_active = timer.state == TimerState::Break;
-    let break_style = if break_active {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+    let mut break_style = if break_active {
+        Style::default().fg(timer_color(appearance, true, Color::Yellow)).add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::DarkGray)
+        Style::default().fg(timer_color(appearance, false, Color::Yellow))
     };
+    let break_urgency = urgency_color(
+        appearance,
+        timer.break_remaining,
+        timer.break_duration,
+        gradient::YELLOW,
+        Color::Yellow,
+    )
+    .filter(|_| break_active);
+    if let Some(color) = break_urgency {
+        break_style = break_style.fg(color);
+    }
+    if let Some((_, break_color)) = fade {
+        break_style = break_style.fg(break_color);
+    }
+    break_style = dim(break_style);
     
     let break_time = PomodoroTimer::format_time(timer.break_remaining);
-    let break_ascii = PomodoroTimer::get_ascii_digits(&break_time);
+    let break_ascii = if compact {
+        vec![break_time]
+    } else {
+        big_digits(&break_time, appearance.font.as_ref())
+    };
     
-    let break_lines: Vec<Line> = break_ascii
-        .iter()
-        .map(|line| Line::from(Span::styled(line.clone(), break_style)))
-        .collect();
+    let break_gradient = appearance
+        .gradients
+        .filter(|_| break_active && fade.is_none() && break_urgency.is_none())
+        .map(|(_, break_)| break_);
+    let break_lines = digit_lines(&break_ascii, break_style, break_gradient);
+    let break_lines_count = break_lines.len() as u16;
     
-    let break_title = if break_active { "BREAK TIME ☕" } else { "BREAK TIME" };
+    let break_label = match timer.quick_break {
+        Some(_) => strings.quick_break,
+        None => timer.stage_name(Phase::Break).unwrap_or(strings.break_time),
+    };
+    let mut break_title = if break_active {
+        // A quick break goes back to the focus session it interrupted
+        let next = timer.quick_break.map_or(timer.next_name(), |_| timer.stage_name(Phase::Focus));
+        stage_title(break_label, icons.break_, next)
+    } else {
+        break_label.to_string()
+    };
+    if let Some(bank) = timer.break_bank.filter(|&bank| bank > 0) {
+        break_title.push_str(&format!(" · bank {}", PomodoroTimer::format_time(bank)));
+    }
     let break_block = Block::default()
         .title(break_title)
         .borders(Borders::ALL)
-        .style(if break_active {
-            Style::default().fg(Color::Yellow)
-        } else {
-            Style::default().fg(Color::DarkGray)
-        });
+        .style(dim(match fade {
+            Some((_, break_color)) => Style::default().fg(break_color),
+            None => Style::default().fg(timer_color(appearance, break_active, Color::Yellow)),
+        }));
     
+    let break_inner = break_block.inner(areas.break_);
     let break_paragraph = Paragraph::new(break_lines)
         .block(break_block)
         .alignment(Alignment::Center);
-    f.render_widget(break_paragraph, chunks[2]);
+    if !timer.no_break {
+        f.render_widget(break_paragraph, areas.break_);
+        let break_elapsed = elapsed_fraction(timer.break_remaining, timer.break_duration);
+        let color = timer_color(appearance, break_active, Color::Yellow);
+        if appearance.ring {
+            progress::render(f, break_inner, break_elapsed, color, digits);
+        }
+        if appearance.border_progress {
+            progress::render_border(f, areas.break_, break_elapsed, color, appearance.ascii);
+        }
+        let mut below_digits = break_lines_count;
+        if timer.current_phase() == Phase::Break
+            && timer.quick_break.is_none()
+            && !compact
+            && let Some(checklist) = &appearance.checklist
+        {
+            below_digits += render_checklist(f, break_inner, below_digits, checklist, appearance);
+        }
+        if timer.current_phase() == Phase::Break
+            && !compact
+            && let Some(suggestion) = appearance.suggestions.as_ref().and_then(Suggestions::current)
+        {
+            render_suggestion(f, break_inner, below_digits, suggestion, appearance.ring);
+        }
+    }
 
     // Controls
-    let controls = match timer.state {
-        TimerState::Paused => "SPACE: Resume | R: Reset | Q: Quit",
-        _ => "SPACE: Pause | R: Reset | Q: Quit",
+    let pause_label = match timer.state {
+        TimerState::Paused => strings.resume,
+        _ => strings.pause,
     };
+    let controls = format!("SPACE: {} | R: {} | Q: {}", pause_label, strings.reset, strings.quit);
     
-    let focus_min = timer.focus_duration / 60;
-    let break_min = timer.break_duration / 60;
-    let settings_text = format!("Focus: {}min | Break: {}min", focus_min, break_min);
-    let controls_text = format!("Cycles: {} | {} | f/F: focus +/- | b/B: break +/- | {}", 
-                               timer.total_cycles, settings_text, controls);
+    let mut focus_setting = PomodoroTimer::format_duration(timer.focus_duration, strings);
+    let mut break_setting = PomodoroTimer::format_duration(timer.break_duration, strings);
+    // The running phase's length changed after it started, so its time left
+    // was scaled rather than counted from the length shown
+    if timer.resized {
+        match timer.current_phase() {
+            Phase::Focus => focus_setting.push('*'),
+            Phase::Break => break_setting.push('*'),
+        }
+    }
+    let break_setting = if timer.break_ratio.is_some() || timer.adaptive_break.is_some() {
+        format!("{} ({})", strings.auto, break_setting)
+    } else {
+        break_setting
+    };
+    let mut settings_text = format!("{}: {} | {}: {}",
+                                strings.focus, focus_setting,
+                                strings.break_, break_setting);
+    if let Some(preset) = &timer.preset {
+        settings_text = format!("[{}] {}", preset, settings_text);
+    }
+    if let Some(category) = &timer.category {
+        settings_text = format!("#{} {}", category, settings_text);
+    }
+    let controls_text = if appearance.hide_controls {
+        format!("{}: {} | {}", strings.cycles, timer.cycles_today, settings_text)
+    } else {
+        format!("{}: {} | {} | f/F: {} +/- | b/B: {} +/- | {}",
+                strings.cycles, timer.cycles_today, settings_text,
+                strings.focus.to_lowercase(), strings.break_.to_lowercase(), controls)
+    };
+    let mut controls_block = Block::default().borders(Borders::ALL);
+    if let Some(warning) = warning {
+        controls_block = controls_block.title(Span::styled(
+            format!(" {} {} ", icons.warning, warning),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some(toast) = toast {
+        controls_block = controls_block.title(
+            Line::from(Span::styled(
+                format!(" {} ", toast),
+                Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+            ))
+            .right_aligned(),
+        );
+    }
+    if let Some((transition, at)) = timer.last_transition {
+        let last = strings
+            .last_event
+            .replace("{event}", strings.transition(transition))
+            .replace("{minutes}", &(now.duration_since(at).as_secs() / 60).to_string());
+        controls_block = controls_block.title_bottom(format!(" {} ", last));
+    }
     let controls_paragraph = Paragraph::new(controls_text)
         .style(Style::default().fg(Color::Cyan))
         .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::ALL));
-    f.render_widget(controls_paragraph, chunks[3]);
+        .block(controls_block);
+    f.render_widget(controls_paragraph, areas.controls);
+    if let Some(limit) = timer.cycle_limit {
+        render_goal(f, timer.total_cycles, limit, appearance);
+    }
+
+    // With --full-flash the whole screen takes the flash color, strobing
+    // unless animations are off
+    let strobe_step = now.duration_since(timer.flash_timer).as_millis() / FULL_FLASH_STROBE.as_millis();
+    let strobe_on = !appearance.animations || strobe_step.is_multiple_of(2);
+    if appearance.full_flash
+        && strobe_on
+        && let Some((transition, _)) = timer.last_transition.filter(|_| flashing)
+    {
+        let message = Paragraph::new(strings.transition(transition).to_uppercase())
+            .style(Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD))
+            .alignment(Alignment::Center);
+        let [message_area] = Layout::vertical([Constraint::Length(1)]).flex(Flex::Center).areas(f.area());
+        f.render_widget(Block::default().style(Style::default().bg(Color::Yellow)), f.area());
+        f.render_widget(message, message_area);
+    }
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
-    
-    // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+/// Everything a run works with, set up by `main` from the flags and the
+/// config, and handed back after the event loop for the summary.
+struct Session {
+    timer: PomodoroTimer,
+    appearance: Appearance,
+    sound: Sound,
+    audio: Audio,
+    muted: bool,
+    /// The config's `muted`, to tell when a reload changes it
+    config_muted: bool,
+    /// The config's master volume, likewise
+    config_master: f32,
+    state: State,
+    state_path: Option<PathBuf>,
+    session_log: Option<SessionLog>,
+    tags: Vec<String>,
+    lifetime: Option<Lifetime>,
+    sync: Option<SyncHook>,
+    log_rounding: Rounding,
+    dnd: Option<Dnd>,
+    /// Set by a signal to end the run
+    shutdown: Shutdown,
+    #[cfg(unix)]
+    status_server: Option<status::StatusServer>,
+    config_warning: Option<String>,
+    layout_error: Option<String>,
+    font_warning: Option<String>,
+    sound_warning: Option<String>,
+    /// From `--small-terminal compact`, shown as the first toast
+    size_notice: Option<String>,
+    /// The last phase change's sound, for letting it finish on exit
+    last_notification: Option<tokio::sync::oneshot::Receiver<Result<(), String>>>,
+}
+
+/// The event loop: keys in, timer updates, a frame drawn each round, until
+/// the run ends one way or another. Time comes from `keys`, so a script can
+/// drive it on a virtual clock.
+fn run_tui<B: Backend + Write>(
+    terminal: &mut Terminal<B>,
+    keys: &mut Keys,
+    args: &Args,
+    config: &Config,
+    session: &mut Session,
+) -> io::Result<ExitReason> {
+    let Session {
+        timer,
+        appearance,
+        sound,
+        audio,
+        muted,
+        config_muted,
+        config_master,
+        state,
+        state_path,
+        session_log,
+        tags,
+        lifetime,
+        sync,
+        log_rounding,
+        dnd,
+        shutdown,
+        #[cfg(unix)]
+        status_server,
+        config_warning,
+        layout_error,
+        font_warning,
+        sound_warning,
+        size_notice,
+        last_notification,
+    } = session;
+    let mut presets = config.timer.presets.clone();
+    let mut categories = config.timer.categories.clone();
+    let mut note_key = config.ui.note_key;
+    let mut summary_format = config.format.summary.clone();
+    let mut webhook = config.webhook.as_ref().map(Webhook::new);
+    let mut slack = config.slack.as_ref().and_then(Slack::new);
+    let mut config_watcher = ConfigWatcher::new(args.config.as_deref());
+    // Trim the log before the first append, so this run's entries are never at risk
+    let mut log_error = match (&session_log, config.log.keep_days) {
+        (Some(log), Some(days)) => log
+            .rotate(chrono::Local::now() - chrono::Duration::days(days.into()))
+            .err()
+            .map(|e| format!("event log rotation: {}", e)),
+        _ => None,
+    };
+    let mut toast: Option<Toast> = size_notice.take().map(Toast::new);
+    let mut quick_focus_mode = false;
+    // With --strict, the key that quits or abandons once pressed again
+    let mut confirm_quit: Option<KeyCode> = None;
+    // The interruption note being typed, with the phase it interrupted
+    let mut note_input: Option<(Phase, String)> = None;
+    let mut last_input = keys.now();
+    let mut presence_asked: Option<Instant> = None;
+    let mut microbreaks = MicroBreaks::new(config.timer.microbreak_interval());
+    let mut journal = Journal::new(&config.journal, args.day_start);
+    let mut journal_input: Option<TextArea> = None;
+    // Set when quitting opened the journal, so closing it finishes the quit
+    let mut quit_after_journal = false;
+    // Set while a phase waits for a key: the first with --start-mode prompt,
+    // even in strict mode, and each finished one with --manual
+    let mut awaiting = (args.start_mode == StartMode::Prompt).then(|| Escalation::new(keys.now()));
+    // Set with --manual while a finished break waits, to get focus going again
+    let mut nudge: Option<Nudge> = None;
+    let mut nudge_every = config.ui.nudge_every;
+    let mut last_tick = keys.now();
+    // Completed focus time this run, for the clipboard summary
+    let mut focus_secs_done = 0;
+    let mut clipboard_warning = None;
+    // When the last focus session was logged, for rating it during the break
+    let mut last_focus_at: Option<DateTime<Local>> = None;
+
+    // Seeded from the log, so restarting the same day does not count anything twice
+    let mut goal_day = session_log::day_of(keys.local_now(), args.day_start);
+    let mut done_today = focus_sessions_on(session_log.as_ref(), goal_day, args.day_start);
+    let mut daily_goal = config.timer.daily_goal;
+    // Every focus session in the log, for the lifetime milestones
+    let mut logged_pomodoros = session_log
+        .as_ref()
+        .and_then(|log| log.entries().ok())
+        .map_or(0, |entries| entries.iter().filter(|entry| entry.event == Transition::FocusEnd).count() as u64);
+    let mut celebrations = config.ui.celebrations;
+    let mut confetti: Option<Confetti> = None;
+    let mut debug = args.debug.then(DebugPane::new);
 
-    let mut timer = PomodoroTimer::new(args.focus, args.break_time);
-    let mut last_tick = Instant::now();
+    let counts = Counts {
+        all_time: lifetime.as_ref().map(|lifetime| lifetime.total_pomodoros),
+        today: daily_goal.map(|target| DailyGoal { done: done_today, target }),
+    };
+    // Draw right away instead of after the first poll, so the freshly cleared
+    // alternate screen is never shown blank
+    terminal.draw(|f| draw_ui(f, timer, keys.now(), appearance, counts, None, None))?;
+
+    let exit_reason = loop {
+        let mut transition = None;
 
-    loop {
         // Handle events
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    match key.code {
-                        KeyCode::Char('q') => break,
-                        KeyCode::Char(' ') => timer.toggle_pause(),
-                        KeyCode::Char('r') => timer.reset(),
-                        KeyCode::Char('f') => {
-                            let current_focus = timer.focus_duration / 60;
-                            timer.adjust_focus_time((current_focus + 1).max(1));
-                        },
-                        KeyCode::Char('F') => {
-                            let current_focus = timer.focus_duration / 60;
-                            timer.adjust_focus_time((current_focus.saturating_sub(1)).max(1));
-                        },
-                        KeyCode::Char('b') => {
-                            let current_break = timer.break_duration / 60;
-                            timer.adjust_break_time((current_break + 1).max(1));
-                        },
-                        KeyCode::Char('B') => {
-                            let current_break = timer.break_duration / 60;
-                            timer.adjust_break_time((current_break.saturating_sub(1)).max(1));
-                        },
-                        _ => {}
+        // Poll faster only while the phase change fade is on screen
+        let animating = appearance.animations && timer.transition_progress(keys.now()).is_some();
+        let poll = if animating {
+            ANIMATION_FRAME
+        } else if confetti.is_some() {
+            celebration::FRAME
+        } else {
+            Duration::from_millis(100)
+        };
+        let pressed = keys.next_with_modifiers(poll)?;
+        // Before this frame's keys and tick, so a cycle completed just after
+        // midnight (or --day-start) counts toward the new day
+        let today = session_log::day_of(keys.local_now(), args.day_start);
+        timer.roll_over_day(today);
+        // The header's count too, even with nothing completing past midnight
+        if today != goal_day {
+            goal_day = today;
+            done_today = focus_sessions_on(session_log.as_ref(), goal_day, args.day_start);
+        }
+        if shutdown.requested() {
+            break ExitReason::Quit;
+        }
+        if let Some((key, modifiers)) = pressed {
+            let durations_before = (timer.focus_duration, timer.break_duration);
+            let quick_break_before = timer.quick_break.is_some();
+            last_input = keys.now();
+            if let Some(input) = &mut journal_input {
+                // Like a note, the answer takes every key while the timer runs on
+                match input.handle(key, modifiers) {
+                    Outcome::Editing => {}
+                    outcome => {
+                        let answer = input.text();
+                        if outcome == Outcome::Save
+                            && !answer.is_empty()
+                            && let Some(journal) = &journal
+                        {
+                            let now = Local::now();
+                            let day = session_log::day_of(now, args.day_start);
+                            let totals = session_log
+                                .as_ref()
+                                .and_then(|log| log.records().ok())
+                                .map(|records| review::day_totals(records, args.day_start, day))
+                                .unwrap_or_default();
+                            match journal.write(now, &totals, &answer) {
+                                Ok(()) => toast = Some(Toast::new("Saved to the journal")),
+                                Err(e) => log_error = Some(format!("journal: {}", e)),
+                            }
+                        }
+                        journal_input = None;
+                        if quit_after_journal {
+                            break ExitReason::Quit;
+                        }
                     }
                 }
-            }
-        }
-
-        // Update timer
-        if timer.state != TimerState::Paused {
-            let now = Instant::now();
-            if now.duration_since(last_tick) >= Duration::from_secs(1) {
-                if timer.update() {
-                    play_notification_sound();
+            } else if let Some((during, note)) = &mut note_input {
+                // Typing a note swallows every key; the timer keeps running meanwhile
+                match key {
+                    KeyCode::Char(c) => note.push(c),
+                    KeyCode::Backspace => {
+                        note.pop();
+                    }
+                    KeyCode::Esc => note_input = None,
+                    KeyCode::Enter => {
+                        let note = note.trim().to_string();
+                        if !note.is_empty()
+                            && let Some(log) = &session_log
+                        {
+                            let interruption = Interruption {
+                                at: Local::now(),
+                                during: *during,
+                                note,
+                            };
+                            log_error = log.append(&interruption).err().map(|e| format!("event log: {}", e));
+                            toast = Some(Toast::new("Interruption noted"));
+                        }
+                        note_input = None;
+                    }
+                    _ => {}
+                }
+            } else if presence_asked.take().is_some() {
+                // Any key answers the presence prompt and does nothing else
+            } else if microbreaks.dismiss(keys.now()) {
+                // Any key ends a micro-break early and does nothing else
+            } else if awaiting.is_some() && key != KeyCode::Char('q') {
+                // Any key but q starts the phase that is waiting
+                awaiting = None;
+                nudge = None;
+                timer.toggle_pause(keys.now());
+            } else if std::mem::take(&mut quick_focus_mode) {
+                // `d` arms the quick focus presets for the next key only
+                if let KeyCode::Char(digit @ '1'..='9') = key {
+                    let minutes = QUICK_FOCUS_MINUTES[digit as usize - '1' as usize];
+                    timer.adjust_focus_time(minutes * 60);
+                    toast = Some(Toast::new(format!("Focus set to {} min", minutes)));
+                }
+            } else if let Some(quit_key) = confirm_quit.take() {
+                if key == KeyCode::Char('x') && quit_key == key {
+                    break ExitReason::Abandoned;
+                } else if key == KeyCode::Char('q') && quit_key == key {
+                    if let Some(journal) = &mut journal
+                        && journal.due_on_quit(Local::now())
+                    {
+                        journal.asked(Local::now());
+                        journal_input = Some(TextArea::default());
+                        quit_after_journal = true;
+                    } else {
+                        break ExitReason::Quit;
+                    }
+                }
+            } else if let Some(checklist) = &mut appearance.checklist
+                && timer.current_phase() == Phase::Break
+                && timer.quick_break.is_none()
+                // With --break-bank, k keeps spending the bank; the up arrow moves instead
+                && !(key == KeyCode::Char('k') && timer.break_bank.is_some())
+                && checklist.handle(key)
+            {
+                // The checklist took the key
+            } else {
+                match key {
+                    KeyCode::Char('q' | 'x') if args.strict => confirm_quit = Some(key),
+                    KeyCode::Char('q') => {
+                        // The end of the day's journal comes before leaving
+                        if let Some(journal) = &mut journal
+                            && journal.due_on_quit(Local::now())
+                        {
+                            journal.asked(Local::now());
+                            journal_input = Some(TextArea::default());
+                            quit_after_journal = true;
+                        } else {
+                            break ExitReason::Quit;
+                        }
+                    }
+                    KeyCode::Char('x') => break ExitReason::Abandoned,
+                    // Strict mode leaves no way to interrupt or shorten the session
+                    // except quitting: no pausing, skipping, quick break, length
+                    // steps, `d` lengths, presets or break bank
+                    KeyCode::Char(' ' | 'r' | 's' | 'z' | 'f' | 'F' | 'd' | 'p' | 'b' | 'B' | 'k') if args.strict => {}
+                    // Nothing cuts a forced rest short or changes the lengths under
+                    // it; pausing only makes it longer
+                    KeyCode::Char('r' | 's' | 'b' | 'B' | 'p' | 'd' | 'f' | 'F') if timer.mandatory_rest => {
+                        toast = Some(Toast::new("Mandatory rest: this break cannot be cut short"));
+                    }
+                    KeyCode::Char(' ') => timer.toggle_pause(keys.now()),
+                    KeyCode::Char('r') => timer.reset(keys.now()),
+                    KeyCode::Char('s') => {
+                        let quick_break = timer.quick_break.is_some();
+                        transition = timer.skip(keys.now());
+                        if quick_break {
+                            toast = Some(Toast::new("Back to focus"));
+                        }
+                    }
+                    KeyCode::Char('z') => {
+                        toast = Some(Toast::new(if timer.start_quick_break(keys.now()) {
+                            let length = PomodoroTimer::format_duration(timer.break_duration, appearance.strings);
+                            format!("Quick break: {}", length)
+                        } else {
+                            "Quick breaks are taken from focus".to_string()
+                        }));
+                    }
+                    KeyCode::Char('d') => quick_focus_mode = true,
+                    KeyCode::Char('m') => {
+                        *muted = !*muted;
+                        audio.set_muted(*muted);
+                        appearance.muted = *muted;
+                        state.muted = Some(*muted);
+                        let saved = state_path.as_deref().map_or(Ok(()), |path| state.save(path));
+                        toast = Some(Toast::new(match saved {
+                            Err(e) => format!("cannot save the mute setting: {}", e),
+                            Ok(()) if *muted => format!("{} Muted", appearance.icons.muted),
+                            Ok(()) => "Sound on".to_string(),
+                        }));
+                    }
+                    KeyCode::Char('y') => {
+                        let summary = match &summary_format {
+                            Some(template) => {
+                                let status = status::Status {
+                                    muted: *muted,
+                                    ..status::Status::of(timer, args.task.as_deref(), focus_secs_done / 60)
+                                };
+                                let today_minutes = today_focus_minutes(session_log.as_ref(), args.day_start);
+                                template.render(&status.values(today_minutes))
+                            }
+                            None => run_summary(timer.total_cycles, focus_secs_done, args.task.as_deref()),
+                        };
+                        clipboard_warning = clipboard::copy(terminal.backend_mut(), &summary).err();
+                        if clipboard_warning.is_none() {
+                            toast = Some(Toast::new("Copied summary"));
+                        }
+                    }
+                    KeyCode::Char(c) if c == note_key => note_input = Some((timer.current_phase(), String::new())),
+                    KeyCode::Char('k') if timer.break_bank.is_some() => {
+                        if let Some(seconds) = timer.spend_break_bank() {
+                            let added = PomodoroTimer::format_time(seconds);
+                            toast = Some(Toast::new(format!("Added {} to the break", added)));
+                        }
+                    }
+                    KeyCode::Char('p') => {
+                        // Cycle through the presets in config order, starting with the first
+                        let current = presets
+                            .iter()
+                            .position(|preset| timer.preset.as_ref() == Some(&preset.name));
+                        let next = current.map_or(0, |i| (i + 1) % presets.len().max(1));
+                        match presets.get(next) {
+                            Some(preset) => {
+                                timer.apply_preset(preset);
+                                toast = Some(Toast::new(format!("Preset: {}", preset.name)));
+                            }
+                            None => toast = Some(Toast::new("No presets in the config file")),
+                        }
+                    }
+                    KeyCode::Char('c') => {
+                        // Uncategorized, then each category in config order, then round again
+                        let current = categories
+                            .iter()
+                            .position(|category| timer.category.as_ref() == Some(category));
+                        timer.category = match current {
+                            None => categories.first().cloned(),
+                            Some(i) => categories.get(i + 1).cloned(),
+                        };
+                        toast = Some(Toast::new(match (&timer.category, categories.is_empty()) {
+                            (_, true) => "No categories in the config file".to_string(),
+                            (Some(category), _) => format!("Category: {}", category),
+                            (None, _) => "Uncategorized".to_string(),
+                        }));
+                    }
+                    KeyCode::Char('e') => {
+                        match Config::ensure_exists(args.config.as_deref()) {
+                            Ok(path) => match run_editor(terminal, &path) {
+                                Ok(Ok(())) => config_watcher.force(),
+                                Ok(Err(e)) => *config_warning = Some(e),
+                                // Never worth ending the run over
+                                Err(e) => toast = Some(Toast::new(format!("Cannot open the editor: {}", e))),
+                            },
+                            Err(e) => *config_warning = Some(format!("cannot create config: {}", e)),
+                        }
+                    },
+                    KeyCode::Char('n') => {
+                        if let Some(transition) = timer.replay_notification(keys.now())
+                            && sound.enabled
+                        {
+                            audio.play_notification(sound, transition);
+                        }
+                    },
+                    KeyCode::Char('g') if timer.current_phase() == Phase::Break => {
+                        if let Some(suggestions) = &mut appearance.suggestions {
+                            suggestions.next();
+                        }
+                    },
+                    KeyCode::Char('a') if args.noise.is_some() => {
+                        audio.ambient_enabled = !audio.ambient_enabled;
+                    },
+                    KeyCode::F(12) => debug = debug.is_none().then(DebugPane::new),
+                    KeyCode::Char(key @ ('v' | 'V')) => {
+                        let master = audio.adjust_master(if key == 'v' { -VOLUME_STEP } else { VOLUME_STEP });
+                        sound.volume.master = master;
+                        state.master = Some(master);
+                        let saved = state_path.as_deref().map_or(Ok(()), |path| state.save(path));
+                        toast = Some(Toast::new(match saved {
+                            Err(e) => format!("cannot save the volume: {}", e),
+                            Ok(()) => format!("Volume {}%", (master * 100.0).round()),
+                        }));
+                    },
+                    KeyCode::Char(key @ ('t' | 'T')) if args.task.is_some() => {
+                        let estimate = timer.estimate.unwrap_or(timer.total_cycles.max(1));
+                        timer.estimate = Some(if key == 't' { estimate + 1 } else { estimate.saturating_sub(1).max(1) });
+                    },
+                    KeyCode::Char(key @ ('+' | '-')) => {
+                        let rating = if key == '+' { Rating::Win } else { Rating::Fail };
+                        if timer.current_phase() == Phase::Focus {
+                            // Pressing the same key again takes the rating back
+                            timer.rating = (timer.rating != Some(rating)).then_some(rating);
+                        } else if let Some(session) = last_focus_at
+                            && let Some(log) = &session_log
+                        {
+                            let late = LateRating {
+                                at: Local::now(),
+                                session,
+                                rating,
+                            };
+                            log_error = log.append(&late).err().map(|e| format!("event log: {}", e));
+                            toast = Some(Toast::new(format!("Rated the last focus session a {}", rating.label())));
+                        }
+                    },
+                    KeyCode::Char('f') => timer.adjust_focus_time(step_duration(timer.focus_duration, true)),
+                    KeyCode::Char('F') => timer.adjust_focus_time(step_duration(timer.focus_duration, false)),
+                    KeyCode::Char(key @ ('b' | 'B')) => {
+                        // An explicit break length replaces the one derived from --break-ratio
+                        if timer.break_ratio.take().is_some() {
+                            toast = Some(Toast::new("Break unlinked from focus time"));
+                        }
+                        timer.adjust_break_time(step_duration(timer.break_duration, key == 'b'));
+                    },
+                    _ => {}
+                }
+            }
+
+            // Keep an audit trail of manual length changes, whatever key caused
+            // them; a quick break starting or ending is not one
+            let (focus_before, break_before) = durations_before;
+            let quick_break_changed = timer.quick_break.is_some() != quick_break_before;
+            for (adjusted, from_secs, to_secs) in [
+                (Phase::Focus, focus_before, timer.focus_duration),
+                (Phase::Break, break_before, timer.break_duration),
+            ] {
+                if from_secs != to_secs
+                    && !quick_break_changed
+                    && let Some(log) = &session_log
+                {
+                    let adjustment = Adjustment {
+                        at: Local::now(),
+                        adjusted,
+                        from_secs,
+                        to_secs,
+                    };
+                    log_error = log.append(&adjustment).err().map(|e| format!("event log: {}", e));
+                }
+            }
+        }
+
+        // Update timer
+        let now = keys.now();
+        if timer.state != TimerState::Paused && now.duration_since(last_tick) >= Duration::from_secs(1) {
+            let counting = timer.countdown;
+            if let Some(debug) = &mut debug {
+                debug.tick(now, now.duration_since(timer.last_update));
+            }
+            if let Some(ended) = timer.update(now) {
+                transition = Some(ended);
+            }
+            // A beep for each number of the get-ready count, and a higher one as it ends
+            if counting.is_some() && timer.countdown != counting && sound.enabled {
+                audio.play_countdown_tone(timer.countdown.is_none());
+            }
+            if std::mem::take(&mut timer.midpoint_reached) && sound.enabled {
+                audio.play_soft_tone();
+            }
+            if std::mem::take(&mut timer.quick_break_over) {
+                if sound.enabled {
+                    audio.play_notification(sound, Transition::BreakEnd);
+                }
+                toast = Some(Toast::new("Back to focus"));
+            }
+            last_tick = timer.last_update;
+        }
+
+        if let Some(transition) = transition {
+            if let Some(debug) = &mut debug {
+                debug.event(format!("transition {:?}", transition));
+            }
+            // If audio fails, we simply continue without sound notification
+            *last_notification = sound.enabled.then(|| audio.play_notification(sound, transition));
+            if transition == Transition::FocusEnd
+                && let Some(suggestions) = &mut appearance.suggestions
+            {
+                suggestions.next();
+            }
+            if args.manual && !timer.completed {
+                timer.toggle_pause(now);
+                awaiting = Some(Escalation::new(now));
+                nudge = (transition == Transition::BreakEnd && nudge_every > 0)
+                    .then(|| Nudge::new(Duration::from_secs(nudge_every), now));
+            }
+            // Through the backend so it cannot land in the middle of a frame
+            if args.terminal_bell && !*muted {
+                terminal.backend_mut().write_all(b"\x07")?;
+                Write::flush(terminal.backend_mut())?;
+            }
+            if transition == Transition::FocusEnd {
+                focus_secs_done += timer.ended().seconds;
+            }
+            if transition == Transition::FocusEnd {
+                done_today += 1;
+                // Equal only once a day, however often pomo is restarted after
+                if daily_goal == Some(done_today) {
+                    let message = format!("Daily goal reached: {} {}", done_today, appearance.icons.tomato);
+                    desktop::notify("pomo", &message);
+                    if sound.enabled {
+                        audio.play_soft_tone();
+                    }
+                    toast = Some(Toast::new(message));
+                    if let Some(journal) = &mut journal
+                        && journal.due(Local::now())
+                    {
+                        journal.asked(Local::now());
+                        journal_input = Some(TextArea::default());
+                    }
+                }
+            }
+            // Only the session that goes past the estimate, not every one after it
+            if transition == Transition::FocusEnd
+                && let Some(estimate) = timer.estimate
+                && timer.total_cycles == estimate + 1
+            {
+                toast = Some(Toast::new(format!(
+                    "Over the estimate: {}/{} {}",
+                    timer.total_cycles, estimate, appearance.icons.tomato
+                )));
+                if sound.enabled {
+                    audio.play_soft_tone();
+                }
+            }
+            if transition == Transition::FocusEnd
+                && let Some(lifetime) = lifetime
+            {
+                lifetime.record_pomodoro(
+                    session_log::day_of(Local::now(), args.day_start),
+                    timer.ended().seconds / 60,
+                );
+            }
+            // Each break starts the checklist afresh, reminding of what the last
+            // one left undone; the break ending is logged with how much got done
+            let checklist = match transition {
+                Transition::FocusEnd => {
+                    if let Some(reminder) = appearance.checklist.as_mut().and_then(Checklist::begin) {
+                        toast = Some(Toast::new(reminder));
+                    }
+                    None
+                }
+                Transition::BreakEnd => appearance.checklist.as_mut().map(Checklist::finish),
+            };
+            let rating = timer.rating.take().filter(|_| transition == Transition::FocusEnd);
+            if let Some(log) = &session_log {
+                let entry = LogEntry {
+                    at: Local::now(),
+                    event: transition,
+                    duration_secs: log_rounding.apply(timer.ended().seconds),
+                    cycles: timer.total_cycles,
+                    task: args.task.clone(),
+                    tags: tags.clone(),
+                    estimate: timer.estimate,
+                    preset: timer.preset.clone(),
+                    category: timer.category.clone().filter(|_| transition == Transition::FocusEnd),
+                    source: None,
+                    rating,
+                    microbreaks: if transition == Transition::FocusEnd { microbreaks.take_count() } else { 0 },
+                    checklist,
+                };
+                if transition == Transition::FocusEnd {
+                    last_focus_at = Some(entry.at);
+                }
+                log_error = log
+                    .append(&entry)
+                    .err()
+                    .map(|e| format!("event log: {}", e));
+                if log_error.is_none()
+                    && let Some(sync) = sync
+                {
+                    sync.logged(now);
+                }
+                if log_error.is_none() && transition == Transition::FocusEnd {
+                    let before = logged_pomodoros;
+                    logged_pomodoros += 1;
+                    if let Some(lifetime) = lifetime
+                        && let Some(milestone) = celebration::reached(before, logged_pomodoros, lifetime.celebrated)
+                    {
+                        // Recorded even when celebrations are off, like any milestone passed
+                        lifetime.celebrated = milestone;
+                        if celebrations {
+                            let message = format!("{} pomodoros! {}", milestone, appearance.icons.tomato);
+                            desktop::notify("pomo", &message);
+                            if sound.enabled {
+                                audio.play_fanfare(sound);
+                            }
+                            toast = Some(Toast::new(message));
+                            confetti = Some(Confetti::new(now, Local::now().timestamp_subsec_nanos().into()));
+                        }
+                    }
+                }
+            }
+            if let Some(webhook) = &webhook {
+                webhook.notify(transition, timer.total_cycles, args.task.as_deref());
+            }
+        }
+
+        if let Some(sync) = sync {
+            sync.tick(now);
+        }
+
+        #[cfg(unix)]
+        if let Some(server) = &status_server {
+            // Controls come from `pomo attach`, which is for background timers only
+            server.serve(|_| status::Status {
+                muted: *muted,
+                ..status::Status::of(timer, args.task.as_deref(), focus_secs_done / 60)
+            });
+        }
+
+        if let Some(escalation) = &mut awaiting
+            && let Some(alarm) = escalation.due(now)
+            && sound.enabled
+            && sound.escalate
+            && !(nudge.is_some() && sound.nudge)
+            && let Some((transition, _)) = timer.last_transition
+        {
+            match alarm {
+                Alarm::Soft => audio.play_soft_tone(),
+                Alarm::Full => {
+                    audio.play_notification(sound, transition);
                 }
-                last_tick = now;
+            }
+        }
+
+        if let Some(nudge) = &mut nudge
+            && nudge.due(now)
+            && let Some(transition) = timer.replay_notification(now)
+            && sound.enabled
+            && sound.nudge
+        {
+            audio.play_notification(sound, transition);
+        }
+
+        if microbreaks.tick(timer.state == TimerState::Focus, timer.focus_remaining, now) && sound.enabled {
+            audio.play_soft_tone();
+        }
+
+        // Only focus time needs a witness; breaks and pauses restart the clock
+        if let Some(minutes) = args.presence_check {
+            if timer.state != TimerState::Focus {
+                last_input = now;
+            } else if let Some(asked) = presence_asked {
+                // Strict mode could never resume, so the question just stays up
+                if !args.strict && now.duration_since(asked) >= PRESENCE_TIMEOUT {
+                    timer.toggle_pause(now);
+                    presence_asked = None;
+                    toast = Some(Toast::new("Paused: nobody answered"));
+                }
+            } else if now.duration_since(last_input) >= Duration::from_secs(minutes * 60) {
+                presence_asked = Some(now);
+            }
+        }
+
+        // Pick up config edits without restarting; a broken edit keeps the running settings
+        if config_watcher.changed(now) {
+            let problems = config::check(args.config.as_deref());
+            match problems.iter().find(|problem| problem.fatal) {
+                Some(problem) => *config_warning = Some(format!("config not reloaded: {}", problem)),
+                None => match Config::load(args.config.as_deref()) {
+                    Ok(config) => {
+                        *config_warning = ignored_keys_warning(&problems);
+                        (appearance.layout, *layout_error) = args.layout(&config);
+                        let icon_set = config.ui.icons.unwrap_or_else(IconSet::detect);
+                        appearance.icons = icon_set.icons();
+                        appearance.ascii = icon_set == IconSet::Ascii;
+                        if !args.accessible {
+                            appearance.gradients = gradients(&config);
+                            appearance.animations = config.ui.animations && gradient::supported();
+                        }
+                        appearance.clock = config.ui.clock;
+                        appearance.suggestions = args.suggestions(&config);
+                        // Only a changed list starts over, so a reload mid-break keeps the ticks
+                        let items = args.checklist(&config);
+                        if appearance.checklist.as_ref().map_or(&[][..], Checklist::items) != items {
+                            appearance.checklist = Checklist::new(items);
+                        }
+                        *sound = args.sound(&config);
+                        // Changed in the config since it was read: the config has the last word
+                        // again, for the master volume as for `muted`
+                        if sound.volume.master != *config_master {
+                            *config_master = sound.volume.master;
+                            state.master = None;
+                            if let Some(path) = &state_path {
+                                let _ = state.save(path);
+                            }
+                        }
+                        sound.volume.master = state.master.unwrap_or(*config_master);
+                        audio.set_volume(sound.volume);
+                        if config.sound.muted != *config_muted {
+                            *config_muted = config.sound.muted;
+                            *muted = *config_muted;
+                            audio.set_muted(*muted);
+                            appearance.muted = *muted;
+                            state.muted = None;
+                            if let Some(path) = &state_path {
+                                let _ = state.save(path);
+                            }
+                        }
+                        presets = config.timer.presets.clone();
+                        categories = config.timer.categories.clone();
+                        daily_goal = config.timer.daily_goal;
+                        microbreaks.set_every(config.timer.microbreak_interval());
+                        *log_rounding = args.log_rounding.or(config.log.rounding).unwrap_or_default();
+                        note_key = config.ui.note_key;
+                        nudge_every = config.ui.nudge_every;
+                        celebrations = config.ui.celebrations;
+                        summary_format = config.format.summary.clone();
+                        *sound_warning = missing_sound_warning(sound);
+                        webhook = config.webhook.as_ref().map(Webhook::new);
+                        // Keep a sync that is waiting to run
+                        *sync = match (sync.take(), args.sync_command(&config, session_log.is_some())) {
+                            (Some(mut sync), Some(command)) => {
+                                sync.set_command(command);
+                                Some(sync)
+                            }
+                            (_, command) => command.map(SyncHook::new),
+                        };
+                        // Dropping the old client clears any status it set
+                        slack = config.slack.as_ref().and_then(Slack::new);
+                        toast = Some(Toast::new("Config reloaded"));
+                    }
+                    Err(e) => *config_warning = Some(format!("config not reloaded: {}", e)),
+                },
             }
         }
 
         // Draw UI
-        terminal.draw(|f| draw_ui(f, &timer))?;
+        timer.check_max_runtime(now);
+        if timer.completed {
+            break ExitReason::Completed;
+        }
+        if timer.finished {
+            break ExitReason::MaxRuntime;
+        }
+
+        audio.set_ambient_playing(timer.state == TimerState::Focus);
+
+        // A break that began with nothing to show yet, e.g. at launch or after a reload
+        if timer.current_phase() == Phase::Break
+            && let Some(suggestions) = &mut appearance.suggestions
+            && suggestions.current().is_none()
+        {
+            suggestions.next();
+        }
+
+        // Mirror focus into Slack whenever it starts or stops, however that happened
+        if let Some(slack) = &mut slack {
+            let focus_until = (timer.state == TimerState::Focus).then(|| {
+                chrono::Local::now() + chrono::Duration::seconds(timer.focus_remaining as i64)
+            });
+            slack.sync(focus_until);
+        }
+        if let Some(dnd) = dnd {
+            dnd.sync(timer.state == TimerState::Focus);
+        }
+
+        let warning = log_error
+            .clone()
+            .or_else(|| config_warning.clone())
+            .or_else(|| layout_error.clone())
+            .or_else(|| font_warning.clone())
+            .or_else(|| sound_warning.clone())
+            .or_else(|| clipboard_warning.clone())
+            .or_else(|| webhook.as_ref().and_then(Webhook::last_error))
+            .or_else(|| sync.as_ref().and_then(SyncHook::last_error))
+            .or_else(|| slack.as_ref().and_then(Slack::last_error))
+            .or_else(|| dnd.as_ref().and_then(Dnd::last_error));
+        let counts = Counts {
+            all_time: lifetime.as_ref().map(|lifetime| lifetime.total_pomodoros),
+            today: daily_goal.map(|target| DailyGoal { done: done_today, target }),
+        };
+        let toast_text = if let Some((_, note)) = &note_input {
+            Some(format!("Interrupted by: {}_ (Enter: save, Esc: cancel)", note))
+        } else if presence_asked.is_some() {
+            Some("Still focusing? Press any key".to_string())
+        } else if let Some(escalation) = &awaiting {
+            Some(match nudge {
+                Some(_) => format!(
+                    "Break's over — start focusing ({} since) · press any key",
+                    PomodoroTimer::format_time(escalation.waited(now).as_secs())
+                ),
+                None if timer.last_transition.is_none() => "Press any key to start".to_string(),
+                None => "Press any key to start the next phase".to_string(),
+            })
+        } else if quick_focus_mode {
+            Some("Focus preset: 1-9 = 5-45 min".to_string())
+        } else if let Some(quit_key) = confirm_quit {
+            Some(match quit_key {
+                KeyCode::Char('x') => "Press x again to abandon the run".to_string(),
+                _ => "Press q again to quit".to_string(),
+            })
+        } else {
+            toast.as_ref().filter(|toast| toast.is_visible()).map(|toast| toast.message.clone())
+        };
+        if confetti.as_ref().is_some_and(|confetti| confetti.is_over(now)) {
+            confetti = None;
+        }
+        if let Some(debug) = &mut debug {
+            debug.observe(timer);
+        }
+        terminal.draw(|f| {
+            draw_ui(f, timer, now, appearance, counts, warning.as_deref(), toast_text.as_deref());
+            if let Some(seconds) = microbreaks.remaining(now) {
+                draw_microbreak(f, appearance.strings, seconds);
+            }
+            if let Some(confetti) = &confetti {
+                confetti.render(f, now);
+            }
+            if let Some(debug) = &debug {
+                debug.render(f, timer, audio.pending(), now);
+            }
+            if let Some(input) = &journal_input {
+                let [row] = Layout::vertical([Constraint::Length(12)]).flex(Flex::Center).areas(f.area());
+                let [area] = Layout::horizontal([Constraint::Length(70)]).flex(Flex::Center).areas(row);
+                input.render(f, area, journal::QUESTION, "Enter: new line · Ctrl+S: save · Esc: skip");
+            }
+        })?;
+    };
+    Ok(exit_reason)
+}
+
+#[tokio::main]
+async fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let mut args = Args::parse();
+    if args.deep_work {
+        args.strict = true;
+        args.hide_controls = true;
+    }
+    // These run before loading, since they have to work on a broken config
+    match &args.command {
+        Some(Command::Config(command)) => {
+            let usable = config::run(command, args.config.as_deref());
+            return Ok(if usable { ExitCode::SUCCESS } else { ExitCode::FAILURE });
+        }
+        Some(Command::Completions { shell }) => {
+            write_completions(*shell, &mut io::stdout());
+            return Ok(ExitCode::SUCCESS);
+        }
+        _ => {}
+    }
+
+    // Offer the wizard on the very first launch, or with --config naming a new
+    // file; skipping it writes nothing, so it comes back until the file exists
+    let first_run = args.command.is_none()
+        && io::stdout().is_terminal()
+        && Config::path(args.config.as_deref()).is_some_and(|path| !path.exists());
+    if first_run || matches!(args.command, Some(Command::Init)) {
+        let path = Config::path(args.config.as_deref()).ok_or("cannot locate the config directory")?;
+        let question = format!("{} already exists; replace its timer and sound settings?", path.display());
+        // Nothing to overwrite on a first run
+        if !first_run && path.exists() && !confirm(&question)? {
+            return Ok(ExitCode::SUCCESS);
+        }
+        if let Some(choices) = wizard::run()? {
+            choices.save(&path).map_err(|e| format!("cannot write config {}: {}", path.display(), e))?;
+            println!("Saved {}", path.display());
+        }
+        if !first_run {
+            return Ok(ExitCode::SUCCESS);
+        }
+    }
+
+    let config_problems = config::check(args.config.as_deref());
+    let config = if args.command.is_none() && config_problems.iter().any(|problem| problem.fatal) {
+        confirm_default_config(&config_problems)?;
+        Config::default()
+    } else {
+        Config::load(args.config.as_deref())?
+    };
+    let config_warning = ignored_keys_warning(&config_problems);
+    let (layout, layout_error) = args.layout(&config);
+    let (font, font_warning) = args.font();
+    let (task, tags, estimate) = args.label();
+    args.task = task;
+    let mut appearance = Appearance::new(&args, &config, layout, font, tags.clone());
+    let session_log = SessionLog::default_path().map(SessionLog::new);
+    let mut sound = args.sound(&config);
+    // The last `m` wins over the config, and --mute over both for this run;
+    // the last `v`/`V` likewise over the config's master volume
+    let state_path = State::default_path();
+    let state = state_path.as_deref().and_then(|path| State::load(path).ok()).unwrap_or_default();
+    let config_master = sound.volume.master;
+    sound.volume.master = state.master.unwrap_or(config_master);
+    let config_muted = config.sound.muted;
+    let muted = args.mute || state.muted.unwrap_or(config_muted);
+    appearance.muted = muted;
+    let sound_warning = missing_sound_warning(&sound);
+    let lifetime_path = Lifetime::default_path();
+
+    // The background copy of `pomo start` runs the timer below, without a
+    // screen, and so does --headless in the foreground
+    #[cfg(unix)]
+    let headless = match args.command {
+        Some(Command::Start) => daemon::is_child(),
+        None => args.headless,
+        Some(_) => false,
+    };
+    #[cfg(not(unix))]
+    let headless = if args.headless {
+        return Err("--headless needs Unix sockets".into());
+    } else {
+        false
+    };
+
+    if let Some(command) = &args.command
+        && !headless
+    {
+        let log = || session_log.as_ref().ok_or("cannot locate the data directory for the event log");
+        match command {
+            Command::Config(_) | Command::Completions { .. } | Command::Init => {
+                unreachable!("handled before loading the config")
+            }
+            Command::Log(log_args) => run_log(log()?, log_args)?,
+            Command::Up => {
+                let elapsed = stopwatch::run(appearance.icons)?;
+                println!("Elapsed: {}", PomodoroTimer::format_time(elapsed.as_secs()));
+            }
+            Command::Stats(stats_args) => {
+                stats::run(&log()?.entries()?, stats_args, args.day_start, config.timer.daily_goal)?
+            }
+            Command::Replay { day } => {
+                let day = session_log::parse_day(day, args.day_start)?;
+                review::print_day(log()?.records()?, args.day_start, day);
+            }
+            Command::Import(import_args) => import::run(log()?, import_args)?,
+            Command::Review { tag } => review::run(log()?.records()?, args.day_start, tag.as_deref())?,
+            Command::Status { json } => {
+                let path = status::socket_path().ok_or("cannot locate the runtime directory")?;
+                let Some(status) = status::query(&path)? else {
+                    println!("not running");
+                    return Ok(ExitCode::FAILURE);
+                };
+                if *json {
+                    println!("{}", serde_json::to_string(&status)?);
+                } else {
+                    let template = args
+                        .status_format
+                        .clone()
+                        .or_else(|| config.format.status.clone())
+                        .unwrap_or_else(Template::default_status);
+                    let today_minutes = today_focus_minutes(session_log.as_ref(), args.day_start);
+                    println!("{}", template.render(&status.values(today_minutes)));
+                }
+            }
+            Command::Selftest(selftest_args) => {
+                if !selftest::run(selftest_args) {
+                    std::process::exit(1);
+                }
+            }
+            Command::TestSound => {
+                // The timer itself stays silent without audio; here it is worth saying so
+                if let Err(e) = Audio::start(None, sound.volume).play_notification(&sound, Transition::FocusEnd).await? {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+            Command::Sounds { theme: None } => {
+                for theme in SoundTheme::value_variants() {
+                    let name = theme.to_possible_value().expect("no hidden themes");
+                    println!("{:<8} {}", name.get_name(), name.get_help().unwrap_or_default());
+                }
+            }
+            Command::Start => {
+                let path = status::socket_path().ok_or("cannot locate the runtime directory")?;
+                if status::query(&path)?.is_some() {
+                    return Err("a timer is already running; `pomo attach` shows it".into());
+                }
+                #[cfg(unix)]
+                println!("Started the timer in the background (PID {}); `pomo attach` shows it", daemon::spawn()?);
+                #[cfg(not(unix))]
+                return Err("background timers need Unix sockets".into());
+            }
+            Command::Attach => {
+                let path = status::socket_path().ok_or("cannot locate the runtime directory")?;
+                return attach_to(&path, appearance.icons);
+            }
+            Command::Sounds { theme: Some(theme) } => {
+                let audio = Audio::start(None, Volume::default());
+                let preview = Sound {
+                    theme: *theme,
+                    ..Sound::default()
+                };
+                for transition in [Transition::FocusEnd, Transition::BreakEnd] {
+                    println!("{}", transition.event_name());
+                    if let Err(e) = audio.play_notification(&preview, transition).await? {
+                        eprintln!("{}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+    
+    let session_log = session_log.filter(|_| !args.no_log);
+    let mut keys = Keys::new();
+    if let Some(path) = &args.replay_keys {
+        keys = keys.replay(path).map_err(|e| format!("cannot replay {}: {}", path.display(), e))?;
+    }
+    if let Some(path) = &args.record_keys {
+        keys = keys.record(path).map_err(|e| format!("cannot record to {}: {}", path.display(), e))?;
     }
 
+    let mut schedule = args.schedule(&config)?;
+    if args.no_break {
+        schedule.retain(|stage| stage.kind == Phase::Focus);
+    }
+    let no_break = !schedule.iter().any(|stage| stage.kind == Phase::Break);
+
+    // Checked before raw mode, while an error still reaches the normal screen
+    let size_notice = if headless {
+        None
+    } else {
+        let digits = appearance.font.as_ref().map_or(layout::BUILT_IN_DIGITS, Font::time_size);
+        small_terminal_notice(args.small_terminal, terminal::size()?, &appearance.layout, no_break, digits)?
+    };
+
+    // One timer at a time, or both would log the same hours
+    let _instance_lock = match instance::InstanceLock::default_path().filter(|_| !args.force) {
+        Some(path) => match instance::InstanceLock::acquire(&path)? {
+            Ok(lock) => Some(lock),
+            Err(pid) => {
+                // A background timer has no screen of its own, so show it instead
+                if !headless
+                    && let Some(path) = status::socket_path()
+                    && status::query(&path)?.is_some_and(|status| status.daemon)
+                {
+                    return attach_to(&path, appearance.icons);
+                }
+                let holder = pid.map_or(String::new(), |pid| format!(" (PID {})", pid));
+                return Err(format!(
+                    "another pomo timer is already running{}; check it with `pomo status` \
+                     or pass --force to start a second one",
+                    holder
+                )
+                .into());
+            }
+        },
+        None => None,
+    };
+
+    let mut timer = PomodoroTimer::new(schedule, Instant::now());
+    timer.preset = args.preset.clone();
+    timer.category = args.category(&config)?;
+    timer.estimate = estimate;
+    timer.max_runtime = args.max_runtime.map(|minutes| Duration::from_secs(minutes * 60));
+    timer.no_break = no_break;
+    timer.break_bank = args.break_bank.then_some(0);
+    timer.quick_break_secs = args.quick_break_minutes * 60;
+    timer.break_jitter = args.break_jitter.map(Jitter::from_clock);
+    if args.adaptive_break {
+        if args.min_break > args.max_break {
+            return Err("--min-break cannot be longer than --max-break".into());
+        }
+        timer.adaptive_break = Some(AdaptiveBreak {
+            divisor: args.break_divisor,
+            min: args.min_break * 60,
+            max: args.max_break * 60,
+        });
+    }
+    timer.max_consecutive = args.max_consecutive;
+    if args.start_with == Phase::Break && !no_break {
+        // The break does not complete a cycle, so the count still starts at zero
+        timer.start_at(Phase::Break);
+    }
+    if let Some(ratio) = args.break_ratio {
+        timer.link_break(ratio);
+    }
+    timer.midpoint_fraction = args.halfway_sound.then_some(args.midpoint_fraction);
+    timer.once = args.once;
+    timer.manual = args.manual;
+    timer.cycle_limit = args.cycles;
+    match args.start_mode {
+        StartMode::Auto => {}
+        StartMode::Prompt => timer.toggle_pause(Instant::now()),
+        StartMode::Countdown => timer.countdown = Some(COUNTDOWN_SECS),
+    }
+    let sync = args.sync_command(&config, session_log.is_some()).map(SyncHook::new);
+    let log_rounding = args.log_rounding.or(config.log.rounding).unwrap_or_default();
+
+    #[cfg(unix)]
+    if headless {
+        let path = status::socket_path().ok_or("cannot locate the runtime directory")?;
+        let Some(server) = status::StatusServer::bind(path)? else {
+            return Err("a timer is already running".into());
+        };
+        let recorder = daemon::Recorder {
+            sound,
+            session_log,
+            lifetime_path,
+            task: args.task.clone(),
+            tags,
+            day_start: args.day_start,
+            rounding: log_rounding,
+            sync,
+            webhook: config.webhook.as_ref().map(Webhook::new),
+            slack: config.slack.as_ref().and_then(Slack::new),
+            noise: args.noise,
+            dnd: Dnd::new(args.enable_dnd_cmd.clone(), args.disable_dnd_cmd.clone()),
+            shutdown: Shutdown::listen(),
+            muted,
+            manual: args.manual,
+            strict: args.strict,
+            announcer: args
+                .accessible
+                .then(|| Announcer::new(config.ui.announce_every(), Instant::now())),
+        };
+        let exit_reason = daemon::run(timer, server, recorder)?;
+        return Ok(exit_reason.code(args.once || args.cycles.is_some()));
+    }
+
+    // Another running instance keeps answering `pomo status`; this one stays quiet.
+    // Bound before raw mode, so a failure is reported on the normal screen
+    #[cfg(unix)]
+    let status_server = match status::socket_path() {
+        Some(path) => status::StatusServer::bind(path)
+            .map_err(|e| format!("cannot open the status socket: {}", e))?,
+        None => None,
+    };
+
+    // Read before raw mode, so an unreadable file is reported on the normal screen
+    let lifetime = match &lifetime_path {
+        Some(path) => Some(Lifetime::load(path).map_err(|e| {
+            format!("cannot read lifetime stats {}: {}", path.display(), e)
+        })?),
+        None => None,
+    };
+
+    // Setup terminal
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+    if !args.no_splash {
+        let settings = splash_settings(&timer, &sound, muted, appearance.strings);
+        splash::show(&mut terminal, &mut keys, &settings, appearance.icons.tomato)?;
+        // The run starts once the splash is gone
+        let now = keys.now();
+        (timer.last_update, timer.paused_since, timer.session_start) = (now, now, now);
+    }
+
+    let mut audio = Audio::start(args.noise, sound.volume);
+    audio.set_muted(muted);
+    if timer.countdown.is_some() && sound.enabled {
+        audio.play_countdown_tone(false);
+    }
+    let mut session = Session {
+        timer,
+        appearance,
+        sound,
+        audio,
+        muted,
+        config_muted,
+        config_master,
+        state,
+        state_path,
+        session_log,
+        tags,
+        lifetime,
+        sync,
+        log_rounding,
+        dnd: Dnd::new(args.enable_dnd_cmd.clone(), args.disable_dnd_cmd.clone()),
+        shutdown: Shutdown::listen(),
+        #[cfg(unix)]
+        status_server,
+        config_warning,
+        layout_error,
+        font_warning,
+        sound_warning,
+        size_notice,
+        last_notification: None,
+    };
+    let exit_reason = run_tui(&mut terminal, &mut keys, &args, &config, &mut session)?;
+
     // Restore terminal
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
 
-    Ok(())
+    // Do-not-disturb goes off before anything else, even when quitting mid-focus
+    drop(session.dnd);
+
+    if args.wait_sound_on_exit {
+        session.audio.finish(SOUND_EXIT_LIMIT);
+    }
+
+    if let Some(sync) = session.sync
+        && let Some(e) = sync.finish(SYNC_EXIT_LIMIT)
+    {
+        eprintln!("{}", e);
+    }
+
+    if let (Some(path), Some(lifetime)) = (&lifetime_path, &session.lifetime)
+        && let Err(e) = lifetime.save(path)
+    {
+        eprintln!("Could not save lifetime stats to {}: {}", path.display(), e);
+    }
+
+    if exit_reason == ExitReason::MaxRuntime {
+        println!("Maximum runtime reached.");
+    }
+    let summary = session.timer.summary(Instant::now());
+    println!("{}", summary);
+    // The TUI is gone, so OSC 52 goes straight to the terminal through stdout
+    if args.copy_summary
+        && let Err(e) = clipboard::copy(&mut io::stdout(), &summary)
+    {
+        eprintln!("{}", e);
+    }
+
+    // Let the final notification finish before the process exits
+    if exit_reason == ExitReason::Completed
+        && let Some(notification) = session.last_notification
+    {
+        let _ = notification.await;
+    }
+    Ok(exit_reason.code(args.once || args.cycles.is_some()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use proptest::prelude::*;
+
+    fn secs(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn max_runtime_finishes_the_run_once_exceeded_even_while_paused() {
+        let start = Instant::now();
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(25 * 60, 5 * 60), start);
+        timer.max_runtime = Some(secs(10 * 60));
+        timer.toggle_pause(start + secs(60));
+
+        timer.check_max_runtime(start + secs(10 * 60 - 1));
+        assert!(!timer.finished);
+        timer.check_max_runtime(start + secs(10 * 60));
+        assert!(timer.finished);
+    }
+
+    /// Two pomodoros, the second followed by a long break.
+    fn with_long_break(now: Instant) -> PomodoroTimer {
+        let stage = |kind, minutes: u64| Stage {
+            name: None,
+            kind,
+            seconds: minutes * 60,
+        };
+        let schedule = vec![
+            stage(Phase::Focus, 25),
+            stage(Phase::Break, 5),
+            stage(Phase::Focus, 25),
+            stage(Phase::Break, 15),
+        ];
+        PomodoroTimer::new(schedule, now)
+    }
+
+    #[test]
+    fn a_full_cycle_runs_focus_break_focus_long_break() {
+        let t0 = Instant::now();
+        let mut timer = with_long_break(t0);
+
+        assert_eq!(timer.update(t0 + secs(25 * 60 - 1)), None);
+        assert_eq!(timer.focus_remaining, 1);
+        assert_eq!(timer.update(t0 + secs(25 * 60)), Some(Transition::FocusEnd));
+        assert_eq!(timer.state, TimerState::Break);
+        assert_eq!(timer.break_remaining, 5 * 60);
+        assert_eq!(timer.total_cycles, 1);
+
+        assert_eq!(timer.update(t0 + secs(30 * 60)), Some(Transition::BreakEnd));
+        assert_eq!(timer.state, TimerState::Focus);
+        assert_eq!(timer.focus_remaining, 25 * 60);
+
+        assert_eq!(timer.update(t0 + secs(55 * 60)), Some(Transition::FocusEnd));
+        assert_eq!(timer.break_remaining, 15 * 60);
+        assert_eq!(timer.total_cycles, 2);
+
+        assert_eq!(timer.update(t0 + secs(70 * 60)), Some(Transition::BreakEnd));
+        assert_eq!((timer.position, timer.state), (0, TimerState::Focus));
+        assert_eq!(timer.break_remaining, 0);
+        assert_eq!(timer.break_duration, 5 * 60);
+    }
+
+    #[test]
+    fn new_lengths_last_beyond_the_next_cycle_but_spare_the_long_break() {
+        let t0 = Instant::now();
+        let mut schedule: Vec<Stage> = std::iter::repeat_n(Stage::default_schedule(25 * 60, 5 * 60), 4)
+            .flatten()
+            .collect();
+        schedule[7].seconds = 15 * 60;
+        let mut timer = PomodoroTimer::new(schedule, t0);
+        timer.adjust_focus_time(50 * 60);
+        timer.adjust_break_time(10 * 60);
+
+        let mut at = 0;
+        for _ in 0..2 {
+            at += 50 * 60;
+            assert_eq!(timer.update(t0 + secs(at)), Some(Transition::FocusEnd));
+            assert_eq!(timer.break_remaining, 10 * 60);
+            at += 10 * 60;
+            assert_eq!(timer.update(t0 + secs(at)), Some(Transition::BreakEnd));
+            assert_eq!(timer.focus_remaining, 50 * 60);
+        }
+        assert_eq!(timer.schedule[7].seconds, 15 * 60);
+    }
+
+    #[test]
+    fn a_pause_freezes_the_time_left() {
+        let t0 = Instant::now();
+        let mut timer = with_long_break(t0);
+        timer.update(t0 + secs(60));
+        timer.toggle_pause(t0 + secs(60));
+
+        assert_eq!(timer.update(t0 + secs(600)), None);
+        assert_eq!(timer.state, TimerState::Paused);
+        assert_eq!(timer.focus_remaining, 24 * 60);
+
+        timer.toggle_pause(t0 + secs(600));
+        timer.update(t0 + secs(660));
+        assert_eq!(timer.state, TimerState::Focus);
+        assert_eq!(timer.focus_remaining, 23 * 60);
+    }
+
+    #[test]
+    fn skipping_focus_counts_a_cycle_and_skipping_a_break_does_not() {
+        let t0 = Instant::now();
+        let mut timer = with_long_break(t0);
+
+        assert_eq!(timer.skip(t0 + secs(10)), Some(Transition::FocusEnd));
+        assert_eq!(timer.total_cycles, 1);
+        assert_eq!(timer.skip(t0 + secs(20)), Some(Transition::BreakEnd));
+        assert_eq!(timer.total_cycles, 1);
+        assert_eq!(timer.skip(t0 + secs(30)), Some(Transition::FocusEnd));
+        assert_eq!(timer.total_cycles, 2);
+        assert_eq!(timer.break_remaining, 15 * 60);
+    }
+
+    #[test]
+    fn skipping_while_paused_does_nothing() {
+        let t0 = Instant::now();
+        let mut timer = with_long_break(t0);
+        timer.toggle_pause(t0);
+
+        assert_eq!(timer.skip(t0 + secs(10)), None);
+        assert_eq!(timer.total_cycles, 0);
+    }
+
+    #[test]
+    fn reset_starts_over_at_the_first_focus_keeping_the_count() {
+        let t0 = Instant::now();
+        let mut timer = with_long_break(t0);
+        timer.update(t0 + secs(25 * 60));
+
+        timer.reset(t0 + secs(27 * 60));
+        assert_eq!((timer.position, timer.state), (0, TimerState::Focus));
+        assert_eq!(timer.focus_remaining, 25 * 60);
+        assert_eq!(timer.break_remaining, 5 * 60);
+        assert_eq!(timer.total_cycles, 1);
+
+        // The clock restarts from the reset, not from the last update
+        timer.update(t0 + secs(28 * 60));
+        assert_eq!(timer.focus_remaining, 24 * 60);
+    }
+
+    #[test]
+    fn reset_drops_the_rating_of_the_session_started_over() {
+        let t0 = Instant::now();
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(25 * 60, 5 * 60), t0);
+        timer.rating = Some(Rating::Win);
+        timer.reset(t0 + secs(60));
+        assert_eq!(timer.rating, None);
+    }
+
+    #[test]
+    fn a_new_day_resets_the_daily_count_but_not_the_total() {
+        let t0 = Instant::now();
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(60, 60), t0);
+        let june = |day| NaiveDate::from_ymd_opt(2024, 6, day).unwrap();
+        timer.roll_over_day(june(1));
+        timer.update(t0 + secs(60));
+        timer.update(t0 + secs(120));
+        timer.update(t0 + secs(180));
+
+        timer.roll_over_day(june(1));
+        assert_eq!((timer.cycles_today, timer.total_cycles), (2, 2));
+        timer.roll_over_day(june(2));
+        assert_eq!((timer.cycles_today, timer.total_cycles), (0, 2));
+    }
+
+    #[test]
+    fn update_carries_part_seconds_over() {
+        let t0 = Instant::now();
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(60, 60), t0);
+        timer.update(t0 + Duration::from_millis(1500));
+        assert_eq!(timer.focus_remaining, 59);
+        timer.update(t0 + Duration::from_millis(2000));
+        assert_eq!(timer.focus_remaining, 58);
+    }
+
+    #[test]
+    fn exit_codes_tell_a_reached_goal_from_an_early_exit() {
+        assert_eq!(ExitReason::Completed.code(true), ExitCode::SUCCESS);
+        assert_eq!(ExitReason::Quit.code(true), ExitCode::from(2));
+        assert_eq!(ExitReason::MaxRuntime.code(true), ExitCode::from(2));
+        assert_eq!(ExitReason::Abandoned.code(true), ExitCode::from(3));
+        assert_eq!(ExitReason::Quit.code(false), ExitCode::SUCCESS);
+        assert_eq!(ExitReason::Abandoned.code(false), ExitCode::from(3));
+    }
+
+    #[test]
+    fn every_shell_gets_a_completion_script_with_the_subcommands() {
+        for &shell in clap_complete::Shell::value_variants() {
+            let mut script = Vec::new();
+            write_completions(shell, &mut script);
+            let script = String::from_utf8(script).unwrap();
+            for subcommand in ["stats", "status", "completions", "start", "attach"] {
+                assert!(script.contains(subcommand), "{} completions lack {}", shell, subcommand);
+            }
+        }
+    }
+
+    #[test]
+    fn starting_with_a_break_goes_on_to_focus_without_counting_a_cycle() {
+        let t0 = Instant::now();
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(25 * 60, 5 * 60), t0);
+        timer.start_at(Phase::Break);
+        assert_eq!((timer.state, timer.break_remaining), (TimerState::Break, 5 * 60));
+
+        assert_eq!(timer.update(t0 + secs(5 * 60)), Some(Transition::BreakEnd));
+        assert_eq!((timer.state, timer.focus_remaining), (TimerState::Focus, 25 * 60));
+        assert_eq!(timer.total_cycles, 0);
+
+        assert_eq!(timer.update(t0 + secs(30 * 60)), Some(Transition::FocusEnd));
+        assert_eq!(timer.total_cycles, 1);
+    }
+
+    /// A test terminal that also takes the bytes the loop writes around
+    /// ratatui, like the bell, and drops them.
+    struct Screen(ratatui::backend::TestBackend);
+
+    impl Backend for Screen {
+        fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+        where
+            I: Iterator<Item = (u16, u16, &'a ratatui::buffer::Cell)>,
+        {
+            self.0.draw(content)
+        }
+
+        fn hide_cursor(&mut self) -> io::Result<()> {
+            self.0.hide_cursor()
+        }
+
+        fn show_cursor(&mut self) -> io::Result<()> {
+            self.0.show_cursor()
+        }
+
+        fn get_cursor_position(&mut self) -> io::Result<ratatui::layout::Position> {
+            self.0.get_cursor_position()
+        }
+
+        fn set_cursor_position<P: Into<ratatui::layout::Position>>(&mut self, position: P) -> io::Result<()> {
+            self.0.set_cursor_position(position)
+        }
+
+        fn clear(&mut self) -> io::Result<()> {
+            self.0.clear()
+        }
+
+        fn size(&self) -> io::Result<ratatui::layout::Size> {
+            self.0.size()
+        }
+
+        fn window_size(&mut self) -> io::Result<ratatui::backend::WindowSize> {
+            self.0.window_size()
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Backend::flush(&mut self.0)
+        }
+    }
+
+    impl Write for Screen {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// The outcome of running the event loop with `flags` on an 80×24
+    /// screen, pressing each key of `script` the given time after the start,
+    /// with the session and what was last on screen.
+    fn run_script(flags: &[&str], script: &[(u64, char)]) -> (io::Result<ExitReason>, Session, String) {
+        run_script_from(Local::now(), &Config::default(), flags, script)
+    }
+
+    /// `run_script` with the script's clock starting at `local` on the wall clock.
+    fn run_script_from(
+        local: DateTime<Local>,
+        config: &Config,
+        flags: &[&str],
+        script: &[(u64, char)],
+    ) -> (io::Result<ExitReason>, Session, String) {
+        let t0 = Instant::now();
+        let fixed = ["pomo", "--config", "/nonexistent/pomo.toml", "--no-log", "--no-splash"];
+        let args = Args::try_parse_from(fixed.iter().chain(flags)).unwrap();
+        let mut timer = PomodoroTimer::new(args.schedule(config).unwrap(), t0);
+        timer.manual = args.manual;
+        timer.max_consecutive = args.max_consecutive;
+        if args.start_mode == StartMode::Prompt {
+            timer.toggle_pause(t0);
+        }
+        let (layout, layout_error) = args.layout(config);
+        let mut session = Session {
+            timer,
+            appearance: Appearance::new(&args, config, layout, None, Vec::new()),
+            sound: Sound {
+                enabled: false,
+                ..args.sound(config)
+            },
+            audio: Audio::start(None, Volume::default()),
+            muted: true,
+            config_muted: false,
+            config_master: Volume::default().master,
+            state: State::default(),
+            state_path: None,
+            session_log: None,
+            tags: Vec::new(),
+            lifetime: None,
+            sync: None,
+            log_rounding: Rounding::default(),
+            dnd: None,
+            shutdown: Shutdown::default(),
+            #[cfg(unix)]
+            status_server: None,
+            config_warning: None,
+            layout_error,
+            font_warning: None,
+            sound_warning: None,
+            size_notice: None,
+            last_notification: None,
+        };
+        let mut keys =
+            Keys::scripted(t0, script.iter().map(|&(at, key)| (secs(at), KeyCode::Char(key)))).starting_at(local);
+        let mut terminal = Terminal::new(Screen(ratatui::backend::TestBackend::new(80, 24))).unwrap();
+        let exit_reason = run_tui(&mut terminal, &mut keys, &args, config, &mut session);
+        let screen = terminal.backend().0.buffer().content().iter().map(|cell| cell.symbol()).collect();
+        (exit_reason, session, screen)
+    }
+
+    #[test]
+    fn a_small_terminal_gets_a_notice_with_compact_and_an_error_with_abort() {
+        let digits = layout::BUILT_IN_DIGITS;
+        let check = |mode, size| small_terminal_notice(mode, size, &layout::DEFAULT_LAYOUT, false, digits);
+        let notice = check(SmallTerminal::Compact, (45, 18)).unwrap().expect("a notice");
+        assert!(notice.contains("49×22"), "{}", notice);
+        assert!(check(SmallTerminal::Abort, (45, 18)).unwrap_err().contains("needs at least 49×22"));
+        assert_eq!(check(SmallTerminal::Ignore, (45, 18)), Ok(None));
+        assert_eq!(check(SmallTerminal::Compact, (80, 24)), Ok(None));
+    }
+
+    #[test]
+    fn a_session_ending_right_at_midnight_counts_toward_the_new_day() {
+        let evening = Local.with_ymd_and_hms(2024, 6, 1, 23, 59, 0).unwrap();
+        let flags = ["--focus-secs", "60", "--break-secs", "60"];
+        let (_, session, _) = run_script_from(evening, &Config::default(), &flags, &[(70, 'q')]);
+        assert_eq!((session.timer.cycles_today, session.timer.total_cycles), (1, 1));
+        assert_eq!(session.timer.today, NaiveDate::from_ymd_opt(2024, 6, 2));
+    }
+
+    #[test]
+    fn the_daily_goal_in_the_header_starts_over_at_midnight_while_idle() {
+        let evening = Local.with_ymd_and_hms(2024, 6, 1, 23, 58, 0).unwrap();
+        let mut config = Config::default();
+        config.timer.daily_goal = Some(4);
+        let flags = ["--focus-secs", "60", "--break-secs", "600"];
+        // The break runs through midnight, so no transition comes to roll the count over
+        let (_, _, screen) = run_script_from(evening, &config, &flags, &[(90, 'q')]);
+        assert!(screen.contains("Today: 1/4"), "{}", screen);
+        let (_, _, screen) = run_script_from(evening, &config, &flags, &[(150, 'q')]);
+        assert!(screen.contains("Today: 0/4"), "{}", screen);
+    }
+
+    #[test]
+    fn a_paused_cycle_runs_through_focus_and_break_late_by_the_pause() {
+        let flags = ["--focus-secs", "60", "--break-secs", "30"];
+        // Paused from 20s to 50s, so the break ends at 120s instead of 90s
+        let (exit_reason, session, _) = run_script(&flags, &[(20, ' '), (50, ' '), (100, 'q')]);
+        assert_eq!(exit_reason.unwrap(), ExitReason::Quit);
+        assert_eq!((session.timer.state, session.timer.total_cycles), (TimerState::Break, 1));
+
+        let (_, session, screen) = run_script(&flags, &[(20, ' '), (50, ' '), (125, 'q')]);
+        assert_eq!((session.timer.state, session.timer.total_cycles), (TimerState::Focus, 1));
+        assert!(session.timer.focus_remaining > 50, "{}", session.timer.focus_remaining);
+        assert!(screen.contains("Cycles: 1"));
+    }
+
+    #[test]
+    fn lengthening_focus_midway_restarts_it_at_the_new_length() {
+        let (exit_reason, session, screen) = run_script(&["--focus-secs", "60"], &[(30, 'f'), (80, 'q')]);
+        assert_eq!(exit_reason.unwrap(), ExitReason::Quit);
+        assert_eq!(session.timer.focus_duration, 70);
+        // Restarted at 30s, so 20 of the 70 seconds are left and no break yet
+        assert_eq!((session.timer.state, session.timer.total_cycles), (TimerState::Focus, 0));
+        assert_eq!(session.timer.focus_remaining, 20);
+        assert!(screen.contains("Focus: 1m10s"));
+    }
+
+    #[test]
+    fn strict_mode_asks_before_quitting_and_ignores_the_other_keys() {
+        let flags = ["--strict", "--focus-secs", "60"];
+        // Once the script runs out, the screen shows what the last q asked
+        let (exit_reason, session, screen) = run_script(&flags, &[(5, 's'), (10, 'q')]);
+        assert_eq!(exit_reason.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        assert!(screen.contains("Press q again to quit"));
+        assert_eq!((session.timer.state, session.timer.total_cycles), (TimerState::Focus, 0));
+
+        let (exit_reason, _, _) = run_script(&flags, &[(5, 's'), (10, 'q'), (11, 'q')]);
+        assert_eq!(exit_reason.unwrap(), ExitReason::Quit);
+    }
+
+    #[test]
+    fn strict_mode_asks_before_abandoning_too() {
+        let flags = ["--strict", "--focus-secs", "60"];
+        let (exit_reason, _, screen) = run_script(&flags, &[(10, 'x')]);
+        assert_eq!(exit_reason.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        assert!(screen.contains("Press x again to abandon the run"));
+
+        // Any other key, q included, only takes the question back
+        let (exit_reason, _, _) = run_script(&flags, &[(10, 'x'), (11, 'q'), (12, 'x'), (13, 'x')]);
+        assert_eq!(exit_reason.unwrap(), ExitReason::Abandoned);
+        let (exit_reason, _, _) = run_script(&flags, &[(10, 'x'), (11, 'q'), (12, 'q'), (13, 'q')]);
+        assert_eq!(exit_reason.unwrap(), ExitReason::Quit);
+    }
+
+    #[test]
+    fn run_left_counts_every_phase_up_to_the_last_cycle_break() {
+        let t0 = Instant::now();
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(25 * 60, 5 * 60), t0);
+        assert_eq!(timer.run_left(), None);
+
+        timer.cycle_limit = Some(2);
+        assert_eq!(timer.run_left(), Some((60 * 60, false)));
+        // Ten minutes in, ten minutes less
+        timer.update(t0 + secs(10 * 60));
+        assert_eq!(timer.run_left(), Some((50 * 60, false)));
+        timer.once = true;
+        assert_eq!(timer.run_left(), Some((15 * 60, false)));
+    }
+
+    #[test]
+    fn run_left_takes_the_long_break_from_the_schedule() {
+        let t0 = Instant::now();
+        let phase = |kind, minutes: u64| Stage {
+            name: None,
+            kind,
+            seconds: minutes * 60,
+        };
+        let schedule = vec![
+            phase(Phase::Focus, 50),
+            phase(Phase::Break, 10),
+            phase(Phase::Focus, 50),
+            phase(Phase::Break, 30),
+        ];
+        let mut timer = PomodoroTimer::new(schedule, t0);
+        timer.cycle_limit = Some(3);
+        assert_eq!(timer.run_left(), Some(((50 + 10 + 50 + 30 + 50 + 10) * 60, false)));
+
+        // Skipping the first focus leaves the rest as it was
+        timer.skip(t0 + secs(60));
+        assert_eq!(timer.run_left(), Some(((10 + 50 + 30 + 50 + 10) * 60, false)));
+    }
+
+    #[test]
+    fn run_left_lengthens_a_forced_long_break() {
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(25 * 60, 5 * 60), Instant::now());
+        timer.cycle_limit = Some(3);
+        timer.max_consecutive = Some(2);
+        let long = LONG_BREAK_MINUTES * 60;
+        assert_eq!(timer.run_left(), Some((3 * 25 * 60 + 5 * 60 + long + 5 * 60, false)));
+    }
+
+    #[test]
+    fn run_left_is_only_an_estimate_with_manual_starts() {
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(25 * 60, 5 * 60), Instant::now());
+        timer.cycle_limit = Some(1);
+        timer.manual = true;
+        assert_eq!(timer.run_left(), Some((30 * 60, true)));
+    }
+
+    #[test]
+    fn drawing_on_a_single_row_or_column_does_not_panic() {
+        let args = Args::try_parse_from(["pomo"]).unwrap();
+        let appearance = Appearance::new(&args, &Config::default(), layout::DEFAULT_LAYOUT.to_vec(), None, Vec::new());
+        let t0 = Instant::now();
+        let timer = PomodoroTimer::new(Stage::default_schedule(25 * 60, 5 * 60), t0);
+        let counts = Counts {
+            all_time: None,
+            today: None,
+        };
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(80, 24)).unwrap();
+        // Shrinking while running, then growing back
+        for (width, height) in [(80, 24), (1, 24), (80, 1), (1, 1), (40, 15), (80, 24)] {
+            terminal.backend_mut().resize(width, height);
+            terminal.autoresize().unwrap();
+            terminal.draw(|f| draw_ui(f, &timer, t0, &appearance, counts, Some("warning"), Some("toast"))).unwrap();
+        }
+    }
+
+    #[test]
+    fn skipping_a_break_banks_the_time_left() {
+        let t0 = Instant::now();
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(25 * 60, 5 * 60), t0);
+        timer.break_bank = Some(0);
+        timer.update(t0 + secs(25 * 60));
+
+        timer.skip(t0 + secs(27 * 60));
+        assert_eq!(timer.break_bank, Some(3 * 60));
+        // A second skipped break adds to it
+        timer.update(t0 + secs(52 * 60));
+        timer.skip(t0 + secs(53 * 60));
+        assert_eq!(timer.break_bank, Some(7 * 60));
+    }
+
+    #[test]
+    fn without_a_bank_skipped_breaks_are_gone() {
+        let t0 = Instant::now();
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(25 * 60, 5 * 60), t0);
+        timer.update(t0 + secs(25 * 60));
+        timer.skip(t0 + secs(26 * 60));
+        assert_eq!(timer.break_bank, None);
+        assert_eq!(timer.spend_break_bank(), None);
+    }
+
+    #[test]
+    fn spending_the_bank_lengthens_only_a_running_break() {
+        let t0 = Instant::now();
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(25 * 60, 5 * 60), t0);
+        timer.break_bank = Some(4 * 60);
+        assert_eq!(timer.spend_break_bank(), None);
+        assert_eq!(timer.break_bank, Some(4 * 60));
+
+        timer.update(t0 + secs(25 * 60));
+        assert_eq!(timer.spend_break_bank(), Some(4 * 60));
+        assert_eq!((timer.break_remaining, timer.break_bank), (9 * 60, Some(0)));
+        // An empty bank has nothing to give
+        assert_eq!(timer.spend_break_bank(), None);
+    }
+
+    #[test]
+    fn a_malformed_font_file_falls_back_to_the_built_in_digits() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/segments.flf");
+        let args = Args::try_parse_from(["pomo", "--font-file", fixture.to_str().unwrap()]).unwrap();
+        let (font, warning) = args.font();
+        assert_eq!((font.map(|font| font.time_size()), warning), (Some((13, 3)), None));
+
+        let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        let args = Args::try_parse_from(["pomo", "--font-file", manifest.to_str().unwrap()]).unwrap();
+        let (font, warning) = args.font();
+        assert!(font.is_none());
+        assert!(warning.unwrap().ends_with("not a figlet font (no flf2a header), using the built-in digits"));
+    }
+
+    #[test]
+    fn jittered_breaks_vary_around_the_scheduled_length_without_drifting() {
+        let t0 = Instant::now();
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(25 * 60, 5 * 60), t0);
+        timer.break_jitter = Some(Jitter::new(60, 3));
+        for i in 0..50 {
+            assert_eq!(timer.skip(t0 + secs(2 * i)), Some(Transition::FocusEnd));
+            assert!((4 * 60..=6 * 60).contains(&timer.break_remaining), "{}", timer.break_remaining);
+            assert_eq!(timer.break_duration, timer.break_remaining);
+            timer.skip(t0 + secs(2 * i + 1));
+        }
+    }
+
+    #[test]
+    fn a_prompt_start_waits_for_any_key_even_in_strict_mode() {
+        let flags = ["--strict", "--start-mode", "prompt", "--focus-secs", "60"];
+        // q is the one key that does not start it
+        let (_, session, screen) = run_script(&flags, &[(3, 'q')]);
+        assert_eq!(session.timer.state, TimerState::Paused);
+        assert!(screen.contains("Press any key to start"));
+
+        // The key only starts the timer, so `f` leaves the length alone
+        let (exit_reason, session, _) = run_script(&flags, &[(5, 'f'), (20, 'q'), (21, 'q')]);
+        assert_eq!(exit_reason.unwrap(), ExitReason::Quit);
+        assert_eq!((session.timer.state, session.timer.focus_duration), (TimerState::Focus, 60));
+        assert!((44..=46).contains(&session.timer.focus_remaining), "{}", session.timer.focus_remaining);
+    }
+
+    /// One thing that can happen to a running timer, for the properties below.
+    #[derive(Debug, Clone)]
+    enum Op {
+        /// Let this many milliseconds pass, then update
+        Wait(u64),
+        TogglePause,
+        Skip,
+        Reset,
+        FocusLength(u64),
+        BreakLength(u64),
+        QuickBreak,
+        SpendBank,
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            4 => (0..20 * 60 * 1000u64).prop_map(Op::Wait),
+            1 => Just(Op::TogglePause),
+            1 => Just(Op::Skip),
+            1 => Just(Op::Reset),
+            1 => (10..60 * 60u64).prop_map(Op::FocusLength),
+            1 => (10..30 * 60u64).prop_map(Op::BreakLength),
+            1 => Just(Op::QuickBreak),
+            1 => Just(Op::SpendBank),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn the_timer_keeps_its_invariants_whatever_happens(
+            focus_secs in 1..60 * 60u64,
+            break_secs in 1..30 * 60u64,
+            ops in prop::collection::vec(op(), 1..200),
+        ) {
+            let mut now = Instant::now();
+            let mut timer = PomodoroTimer::new(Stage::default_schedule(focus_secs, break_secs), now);
+            timer.break_bank = Some(0);
+            timer.quick_break_secs = 5 * 60;
+            // Spending the bank makes a break longer than its length
+            let mut extended = false;
+            for op in ops {
+                let cycles = timer.total_cycles;
+                let transition = match op {
+                    Op::Wait(millis) => {
+                        now += Duration::from_millis(millis);
+                        timer.update(now)
+                    }
+                    Op::TogglePause => {
+                        timer.toggle_pause(now);
+                        None
+                    }
+                    Op::Skip => timer.skip(now),
+                    Op::Reset => {
+                        timer.reset(now);
+                        extended = false;
+                        None
+                    }
+                    Op::FocusLength(seconds) => {
+                        timer.adjust_focus_time(seconds);
+                        None
+                    }
+                    Op::BreakLength(seconds) => {
+                        timer.adjust_break_time(seconds);
+                        None
+                    }
+                    Op::QuickBreak => {
+                        timer.start_quick_break(now);
+                        None
+                    }
+                    Op::SpendBank => {
+                        extended |= timer.spend_break_bank().is_some();
+                        None
+                    }
+                };
+                if transition.is_some() {
+                    extended = false;
+                }
+                if transition == Some(Transition::FocusEnd) {
+                    prop_assert_eq!(timer.current_phase(), Phase::Break);
+                }
+                prop_assert!(timer.total_cycles >= cycles);
+                prop_assert!(timer.focus_remaining <= timer.focus_duration);
+                prop_assert!(extended || timer.break_remaining <= timer.break_duration);
+            }
+        }
+    }
+
+    #[test]
+    fn reset_during_a_quick_break_goes_back_to_focus() {
+        let t0 = Instant::now();
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(25 * 60, 5 * 60), t0);
+        timer.quick_break_secs = 2 * 60;
+        assert!(timer.start_quick_break(t0 + secs(60)));
+        timer.reset(t0 + secs(90));
+        assert_eq!((timer.state, timer.current_phase()), (TimerState::Focus, Phase::Focus));
+        assert_eq!(timer.focus_remaining, 25 * 60);
+    }
+
+    #[test]
+    fn a_quick_break_picks_focus_up_where_it_stopped() {
+        let t0 = Instant::now();
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(25 * 60, 5 * 60), t0);
+        timer.quick_break_secs = 2 * 60;
+        timer.update(t0 + secs(10 * 60));
+        assert!(timer.start_quick_break(t0 + secs(10 * 60)));
+        assert_eq!((timer.state, timer.break_remaining), (TimerState::Break, 2 * 60));
+
+        assert_eq!(timer.update(t0 + secs(12 * 60)), None);
+        assert!(timer.quick_break_over);
+        assert_eq!((timer.state, timer.focus_remaining), (TimerState::Focus, 15 * 60));
+        assert_eq!((timer.break_duration, timer.break_remaining), (5 * 60, 5 * 60));
+        assert_eq!(timer.total_cycles, 0);
+    }
+
+    #[test]
+    fn the_break_after_max_consecutive_sessions_is_a_forced_long_one() {
+        let t0 = Instant::now();
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(25 * 60, 5 * 60), t0);
+        timer.max_consecutive = Some(2);
+
+        timer.update(t0 + secs(25 * 60));
+        assert!(!timer.mandatory_rest);
+        assert_eq!(timer.break_remaining, 5 * 60);
+        timer.update(t0 + secs(30 * 60));
+        timer.update(t0 + secs(55 * 60));
+        assert!(timer.mandatory_rest);
+        assert_eq!(timer.break_remaining, LONG_BREAK_MINUTES * 60);
+
+        // Neither skipping nor resetting ends it
+        assert_eq!(timer.skip(t0 + secs(56 * 60)), None);
+        timer.reset(t0 + secs(56 * 60));
+        assert_eq!((timer.state, timer.mandatory_rest), (TimerState::Break, true));
+
+        // After it the count starts over, so the next break is a normal one
+        timer.update(t0 + secs(70 * 60));
+        timer.update(t0 + secs(95 * 60));
+        assert!(!timer.mandatory_rest);
+        assert_eq!(timer.break_remaining, 5 * 60);
+    }
+
+    #[test]
+    fn length_keys_and_presets_do_nothing_during_a_mandatory_rest() {
+        let flags = ["--focus-secs", "10", "--break-secs", "10", "--max-consecutive", "1"];
+        let script = [(20, 'f'), (21, 'F'), (22, 'b'), (23, 'd'), (24, '1'), (25, 'p'), (30, 'q')];
+        let (exit_reason, session, screen) = run_script(&flags, &script);
+        assert_eq!(exit_reason.unwrap(), ExitReason::Quit);
+        assert_eq!((session.timer.state, session.timer.mandatory_rest), (TimerState::Break, true));
+        assert_eq!((session.timer.focus_duration, session.timer.break_duration), (10, LONG_BREAK_MINUTES * 60));
+        assert_eq!(session.timer.preset, None);
+        assert!(screen.contains("Mandatory rest: this break cannot be cut short"));
+    }
+
+    #[test]
+    fn an_adaptive_break_follows_the_focus_time_worked() {
+        let t0 = Instant::now();
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(100 * 60, 5 * 60), t0);
+        timer.adaptive_break = Some(AdaptiveBreak {
+            divisor: 5.0,
+            min: 2 * 60,
+            max: 15 * 60,
+        });
+
+        // Cut short after five minutes: a fifth is one minute, raised to the minimum
+        timer.update(t0 + secs(5 * 60));
+        timer.skip(t0 + secs(5 * 60));
+        assert_eq!((timer.break_duration, timer.break_remaining), (2 * 60, 2 * 60));
+
+        // The whole hundred minutes: a fifth is twenty, capped at the maximum
+        timer.update(t0 + secs(7 * 60));
+        timer.update(t0 + secs(107 * 60));
+        assert_eq!(timer.break_remaining, 15 * 60);
+    }
 }
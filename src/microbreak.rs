@@ -0,0 +1,87 @@
+use std::time::{Duration, Instant};
+
+/// How long each micro-break asks you to look away.
+pub const LENGTH: Duration = Duration::from_secs(20);
+/// None start this close to the end of the focus session, which is nearly
+/// a break anyway.
+const QUIET_END_SECS: u64 = 120;
+
+/// 20-20-20 style reminders during long focus sessions: after every
+/// `microbreak_interval` minutes of focus without a pause, a short countdown
+/// to look away. The timer keeps running throughout.
+pub struct MicroBreaks {
+    every: Option<Duration>,
+    /// Start of the current stretch of uninterrupted focus
+    since: Option<Instant>,
+    /// End of the micro-break on screen
+    until: Option<Instant>,
+    /// Started during this focus session, logged when it ends
+    taken: u32,
+}
+
+impl MicroBreaks {
+    /// `every` of `None` never reminds.
+    pub fn new(every: Option<Duration>) -> Self {
+        Self {
+            every,
+            since: None,
+            until: None,
+            taken: 0,
+        }
+    }
+
+    /// Takes effect from the next stretch of focus.
+    pub fn set_every(&mut self, every: Option<Duration>) {
+        self.every = every;
+    }
+
+    /// Follow the timer; call every frame. Anything but running focus, a
+    /// pause included, starts the interval over. Returns whether a
+    /// micro-break starts now.
+    pub fn tick(&mut self, focusing: bool, focus_left_secs: u64, now: Instant) -> bool {
+        let Some(every) = self.every.filter(|_| focusing) else {
+            self.since = None;
+            self.until = None;
+            return false;
+        };
+        let since = *self.since.get_or_insert(now);
+        if let Some(until) = self.until {
+            if now >= until {
+                self.end(now);
+            }
+            return false;
+        }
+        if now.duration_since(since) < every || focus_left_secs <= QUIET_END_SECS {
+            return false;
+        }
+        self.until = Some(now + LENGTH);
+        self.taken += 1;
+        true
+    }
+
+    /// Seconds left of the micro-break on screen, if one is.
+    pub fn remaining(&self, now: Instant) -> Option<u64> {
+        let left = self.until?.saturating_duration_since(now);
+        Some(left.as_secs() + u64::from(left.subsec_nanos() > 0))
+    }
+
+    /// End the micro-break on screen early. Returns whether there was one.
+    pub fn dismiss(&mut self, now: Instant) -> bool {
+        if self.until.is_none() {
+            return false;
+        }
+        self.end(now);
+        true
+    }
+
+    /// Micro-breaks since the last call, for the focus session just logged.
+    pub fn take_count(&mut self) -> u32 {
+        std::mem::take(&mut self.taken)
+    }
+
+    /// The next interval counts from the end of the micro-break.
+    fn end(&mut self, now: Instant) {
+        self.until = None;
+        self.since = Some(now);
+    }
+}
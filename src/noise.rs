@@ -0,0 +1,141 @@
+use clap::ValueEnum;
+use rodio::Source;
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+const SAMPLE_RATE: u32 = 44_100;
+const CHUNK_SIZE: usize = 1024;
+const FADE_SECONDS: f32 = 1.0;
+const DUCK_FADE_SECONDS: f32 = 0.15;
+/// Noise level while a notification plays over it
+const DUCK_LEVEL: f32 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum NoiseKind {
+    White,
+    Pink,
+    Brown,
+}
+
+/// An endless noise source that follows a shared target gain, ramping
+/// towards it over `FADE_SECONDS` instead of jumping, and dipping to
+/// `DUCK_LEVEL` while `ducked` is set.
+pub struct Noise {
+    kind: NoiseKind,
+    target_gain: Arc<AtomicU32>,
+    ducked: Arc<AtomicBool>,
+    gain: f32,
+    duck_gain: f32,
+    seed: u32,
+    brown: f32,
+    pink: [f32; 7],
+    chunk: [f32; CHUNK_SIZE],
+    position: usize,
+}
+
+impl Noise {
+    pub fn new(kind: NoiseKind, target_gain: Arc<AtomicU32>, ducked: Arc<AtomicBool>) -> Self {
+        Self {
+            kind,
+            target_gain,
+            ducked,
+            gain: 0.0,
+            duck_gain: 1.0,
+            seed: 0x9E37_79B9,
+            brown: 0.0,
+            pink: [0.0; 7],
+            chunk: [0.0; CHUNK_SIZE],
+            position: CHUNK_SIZE,
+        }
+    }
+
+    /// Uniform white noise in [-1, 1) from a xorshift generator.
+    fn white(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed as f32 / u32::MAX as f32) * 2.0 - 1.0
+    }
+
+    fn sample(&mut self) -> f32 {
+        let white = self.white();
+        match self.kind {
+            NoiseKind::White => white * 0.5,
+            // Paul Kellet's refined pink noise filter
+            NoiseKind::Pink => {
+                let b = &mut self.pink;
+                b[0] = 0.99886 * b[0] + white * 0.0555179;
+                b[1] = 0.99332 * b[1] + white * 0.0750759;
+                b[2] = 0.96900 * b[2] + white * 0.153852;
+                b[3] = 0.86650 * b[3] + white * 0.3104856;
+                b[4] = 0.55000 * b[4] + white * 0.5329522;
+                b[5] = -0.7616 * b[5] - white * 0.0168980;
+                let pink = b.iter().sum::<f32>() + white * 0.5362;
+                b[6] = white * 0.115926;
+                pink * 0.11
+            }
+            // Leaky integration of white noise
+            NoiseKind::Brown => {
+                self.brown = (self.brown + white * 0.02) / 1.02;
+                self.brown * 3.5
+            }
+        }
+    }
+
+    fn fill_chunk(&mut self) {
+        let target = f32::from_bits(self.target_gain.load(Ordering::Relaxed));
+        let duck_target = if self.ducked.load(Ordering::Relaxed) { DUCK_LEVEL } else { 1.0 };
+        // Sized so a full fade in or out takes FADE_SECONDS at any volume
+        let step = target.max(self.gain) / (SAMPLE_RATE as f32 * FADE_SECONDS);
+        let duck_step = (1.0 - DUCK_LEVEL) / (SAMPLE_RATE as f32 * DUCK_FADE_SECONDS);
+        for i in 0..CHUNK_SIZE {
+            self.gain = approach(self.gain, target, step);
+            self.duck_gain = approach(self.duck_gain, duck_target, duck_step);
+            self.chunk[i] = self.sample() * self.gain * self.duck_gain;
+        }
+        self.position = 0;
+    }
+}
+
+fn approach(value: f32, target: f32, step: f32) -> f32 {
+    if value < target {
+        (value + step).min(target)
+    } else {
+        (value - step).max(target)
+    }
+}
+
+impl Iterator for Noise {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position == CHUNK_SIZE {
+            self.fill_chunk();
+        }
+        self.position += 1;
+        Some(self.chunk[self.position - 1])
+    }
+}
+
+impl Source for Noise {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
@@ -0,0 +1,142 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    symbols::Marker,
+    text::Span,
+    widgets::{
+        canvas::{Canvas, Context, Points},
+        LineGauge,
+    },
+    Frame,
+};
+use std::f64::consts::{FRAC_PI_2, TAU};
+
+const RING_POINTS: usize = 96;
+/// Braille dots are about twice as tall as they are wide
+const RING_HEIGHT: u16 = 6;
+pub const RING_WIDTH: u16 = RING_HEIGHT * 2;
+/// One to eight eighths of a cell, filled from the left
+const EIGHTHS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+
+/// Draw how much of a phase has elapsed inside a timer block: a ring to the
+/// left of the `digits` (width, height), or a line gauge under them when there
+/// is no room for it.
+pub fn render(f: &mut Frame, inner: Rect, elapsed: f64, color: Color, (digits_width, digits_height): (u16, u16)) {
+    let elapsed = elapsed.clamp(0.0, 1.0);
+    if inner.width >= digits_width + 2 * (RING_WIDTH + 1) && inner.height >= RING_HEIGHT {
+        let ring = Rect {
+            x: inner.x + 1,
+            y: inner.y + (inner.height - RING_HEIGHT) / 2,
+            width: RING_WIDTH,
+            height: RING_HEIGHT,
+        };
+        f.render_widget(ring_widget(elapsed, color), ring);
+    } else if inner.height > digits_height {
+        let line = Rect {
+            y: inner.y + inner.height - 1,
+            height: 1,
+            ..inner
+        };
+        let gauge = LineGauge::default()
+            .filled_style(Style::default().fg(color))
+            .unfilled_style(Style::default().fg(Color::DarkGray))
+            .label("")
+            .ratio(elapsed);
+        f.render_widget(gauge, line);
+    }
+}
+
+/// Points around a circle, filled clockwise from twelve o'clock.
+fn ring_widget(elapsed: f64, color: Color) -> Canvas<'static, impl Fn(&mut Context<'_>)> {
+    let filled = (elapsed * RING_POINTS as f64).round() as usize;
+    let point = |i: usize| {
+        let angle = FRAC_PI_2 - TAU * i as f64 / RING_POINTS as f64;
+        (angle.cos(), angle.sin())
+    };
+    let done: Vec<(f64, f64)> = (0..filled).map(point).collect();
+    let left: Vec<(f64, f64)> = (filled..RING_POINTS).map(point).collect();
+
+    Canvas::default()
+        .marker(Marker::Braille)
+        .x_bounds([-1.1, 1.1])
+        .y_bounds([-1.1, 1.1])
+        .paint(move |ctx| {
+            ctx.draw(&Points {
+                coords: &left,
+                color: Color::DarkGray,
+            });
+            ctx.draw(&Points { coords: &done, color });
+        })
+}
+
+/// Draw how much of a phase has elapsed over the bottom border of its timer
+/// `block`, between the corners, so it needs no row of its own.
+pub fn render_border(f: &mut Frame, block: Rect, elapsed: f64, color: Color, ascii: bool) {
+    if block.width <= 2 || block.height == 0 {
+        return;
+    }
+    let width = block.width - 2;
+    let bar = Span::styled(border_bar(elapsed, width as usize, ascii), Style::default().fg(color));
+    let line = Rect {
+        x: block.x + 1,
+        y: block.y + block.height - 1,
+        width,
+        height: 1,
+    };
+    f.render_widget(bar, line);
+}
+
+/// `elapsed` (0 to 1) as a bar `width` cells wide, at 1/8-cell resolution
+/// with partial blocks, or in whole cells of `#` when only ASCII is safe. The
+/// rest of the width keeps the look of the border.
+fn border_bar(elapsed: f64, width: usize, ascii: bool) -> String {
+    let elapsed = elapsed.clamp(0.0, 1.0);
+    if ascii {
+        let filled = (elapsed * width as f64).round() as usize;
+        return "#".repeat(filled) + &"-".repeat(width - filled);
+    }
+    let eighths = (elapsed * width as f64 * 8.0).round() as usize;
+    let (full, partial) = (eighths / 8, eighths % 8);
+    let mut bar = "█".repeat(full);
+    if partial > 0 {
+        bar.push(EIGHTHS[partial - 1]);
+    }
+    let drawn = full + usize::from(partial > 0);
+    bar + &"─".repeat(width - drawn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_blocks_show_eighths_of_a_cell() {
+        assert_eq!(border_bar(0.0, 4, false), "────");
+        // One eighth of one cell of four
+        assert_eq!(border_bar(1.0 / 32.0, 4, false), "▏───");
+        assert_eq!(border_bar(0.5, 4, false), "██──");
+        assert_eq!(border_bar(0.5 + 3.0 / 32.0, 4, false), "██▍─");
+        assert_eq!(border_bar(31.0 / 32.0, 4, false), "███▉");
+        assert_eq!(border_bar(1.0, 4, false), "████");
+    }
+
+    #[test]
+    fn the_ascii_bar_fills_whole_cells() {
+        assert_eq!(border_bar(0.0, 4, true), "----");
+        assert_eq!(border_bar(0.3, 4, true), "#---");
+        assert_eq!(border_bar(0.5, 4, true), "##--");
+        assert_eq!(border_bar(1.0, 4, true), "####");
+    }
+
+    #[test]
+    fn the_bar_always_fills_its_width() {
+        for step in 0..=64 {
+            let elapsed = step as f64 / 64.0;
+            assert_eq!(border_bar(elapsed, 7, false).chars().count(), 7);
+            assert_eq!(border_bar(elapsed, 7, true).len(), 7);
+        }
+        // Out-of-range fractions are clamped rather than overflowing the border
+        assert_eq!(border_bar(1.5, 3, false), "███");
+        assert_eq!(border_bar(-0.5, 3, true), "---");
+    }
+}
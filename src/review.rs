@@ -0,0 +1,292 @@
+use crate::{
+    input::Keys,
+    session_log::{self, LogEntry, Record},
+    Transition,
+};
+use chrono::{Datelike, Days, Local, NaiveDate, NaiveTime};
+use crossterm::{
+    event::{KeyCode, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Alignment, Constraint, Layout},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame, Terminal,
+};
+use std::{collections::BTreeMap, io, time::Duration};
+
+/// Rows taken by everything but the timeline: summary, controls and the
+/// timeline's own border.
+const CHROME_HEIGHT: u16 = 4 + 3 + 2;
+/// A timeline bar cell per this many seconds, so 25 minutes is 10 cells.
+const BAR_CELL_SECS: u64 = 150;
+const BAR_MAX: usize = 36;
+
+/// The log split into days when the screen opens; paging only looks up
+/// the day it shows, so even a large log is read once.
+struct History {
+    days: BTreeMap<NaiveDate, Vec<Record>>,
+}
+
+#[derive(Default)]
+struct Totals {
+    pomodoros: u32,
+    focus_secs: u64,
+    break_secs: u64,
+    interruptions: u32,
+}
+
+impl History {
+    /// With a `tag`, only the phases tagged with it; interruptions and
+    /// adjustments carry no tags and are left out.
+    fn new(records: Vec<Record>, day_start: NaiveTime, tag: Option<&str>) -> Self {
+        let mut days: BTreeMap<NaiveDate, Vec<Record>> = BTreeMap::new();
+        let tagged = |record: &Record| match (tag, record) {
+            (None, _) => true,
+            (Some(tag), Record::Phase(entry)) => entry.tags.iter().any(|t| t == tag),
+            (Some(_), _) => false,
+        };
+        for record in records.into_iter().filter(tagged) {
+            days.entry(session_log::day_of(record.at(), day_start)).or_default().push(record);
+        }
+        Self { days }
+    }
+
+    fn day(&self, day: NaiveDate) -> &[Record] {
+        self.days.get(&day).map_or(&[], Vec::as_slice)
+    }
+
+    fn totals(&self, days: impl Iterator<Item = NaiveDate>) -> Totals {
+        let mut totals = Totals::default();
+        for record in days.flat_map(|day| self.day(day)) {
+            match record {
+                Record::Phase(entry) if entry.event == Transition::FocusEnd => {
+                    totals.pomodoros += 1;
+                    totals.focus_secs += entry.duration_secs;
+                }
+                Record::Phase(entry) => totals.break_secs += entry.duration_secs,
+                Record::Interruption(_) => totals.interruptions += 1,
+                Record::Adjustment(_) | Record::Rating(_) => {}
+            }
+        }
+        totals
+    }
+}
+
+/// Print one day's phases in order, with their start, length and task,
+/// followed by the day's totals; `pomo replay`.
+pub fn print_day(records: Vec<Record>, day_start: NaiveTime, day: NaiveDate) {
+    let history = History::new(records, day_start, None);
+    let phases: Vec<&LogEntry> = history
+        .day(day)
+        .iter()
+        .filter_map(|record| match record {
+            Record::Phase(entry) => Some(entry),
+            _ => None,
+        })
+        .collect();
+    if phases.is_empty() {
+        println!("Nothing logged on {}", day.format("%a %Y-%m-%d"));
+        return;
+    }
+    println!("── {} ──", day.format("%a %Y-%m-%d"));
+    for entry in phases {
+        let start = entry.at - chrono::Duration::seconds(entry.duration_secs as i64);
+        let phase = match entry.event {
+            Transition::FocusEnd => "focus",
+            Transition::BreakEnd => "break",
+        };
+        let mut line = format!(
+            "{}–{}  {}  {:>5}",
+            start.format("%H:%M"),
+            entry.at.format("%H:%M"),
+            phase,
+            format_duration(entry.duration_secs)
+        );
+        if let Some(task) = &entry.task {
+            line.push_str("  ");
+            line.push_str(task);
+        }
+        println!("{}", line);
+    }
+    println!();
+    println!("{}", describe_totals(&history.totals([day].into_iter())));
+}
+
+/// One day's totals, e.g. `4 pomodoros · 1h40m focus · 20m break · 2 interruptions`.
+pub fn day_totals(records: Vec<Record>, day_start: NaiveTime, day: NaiveDate) -> String {
+    describe_totals(&History::new(records, day_start, None).totals([day].into_iter()))
+}
+
+/// Page through the event log a day at a time until `q`. Read-only.
+pub fn run(records: Vec<Record>, day_start: NaiveTime, tag: Option<&str>) -> io::Result<()> {
+    let history = History::new(records, day_start, tag);
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let today = session_log::day_of(Local::now(), day_start);
+    let result = run_loop(&mut terminal, &history, today, tag);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+fn run_loop(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    history: &History,
+    today: NaiveDate,
+    tag: Option<&str>,
+) -> io::Result<()> {
+    let mut keys = Keys::new();
+    let mut day = today;
+    let mut scroll = 0;
+    loop {
+        // Keep the last entries on screen rather than scrolling past them
+        let visible = terminal.size()?.height.saturating_sub(CHROME_HEIGHT) as usize;
+        scroll = scroll.min(history.day(day).len().saturating_sub(visible));
+        terminal.draw(|f| draw(f, history, day, scroll, tag))?;
+
+        let Some((key, modifiers)) = keys.next_with_modifiers(Duration::from_millis(250))? else {
+            continue;
+        };
+        let step = if modifiers.contains(KeyModifiers::SHIFT) { 7 } else { 1 };
+        let previous_day = day;
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Left => day = day - Days::new(step),
+            KeyCode::Right => day = day + Days::new(step),
+            KeyCode::Char('t') => day = today,
+            KeyCode::Up => scroll = scroll.saturating_sub(1),
+            KeyCode::Down => scroll += 1,
+            KeyCode::PageUp => scroll = scroll.saturating_sub(visible.max(1)),
+            KeyCode::PageDown => scroll += visible.max(1),
+            _ => {}
+        }
+        if day != previous_day {
+            scroll = 0;
+        }
+    }
+}
+
+fn draw(f: &mut Frame, history: &History, day: NaiveDate, scroll: usize, tag: Option<&str>) {
+    let [summary_area, timeline_area, controls_area] = Layout::vertical([
+        Constraint::Length(4),
+        Constraint::Min(0),
+        Constraint::Length(3),
+    ])
+    .areas(f.area());
+
+    let monday = day - Days::new(day.weekday().num_days_from_monday() as u64);
+    let week = history.totals(monday.iter_days().take(7));
+    let summary = Paragraph::new(vec![
+        Line::from(describe_totals(&history.totals([day].into_iter()))),
+        Line::from(format!("Week of {}: {}", monday.format("%a %Y-%m-%d"), describe_totals(&week))),
+    ])
+    .alignment(Alignment::Center)
+    .block(Block::default().title(title(day, tag)).borders(Borders::ALL));
+    f.render_widget(summary, summary_area);
+
+    let records = history.day(day);
+    let lines: Vec<Line> = if records.is_empty() {
+        vec![Line::from(Span::styled("Nothing logged", Style::default().fg(Color::DarkGray)))]
+    } else {
+        records.iter().map(timeline_line).collect()
+    };
+    let visible = timeline_area.height.saturating_sub(2) as usize;
+    let title = if records.len() > visible {
+        format!(
+            "Timeline ({}-{} of {})",
+            scroll + 1,
+            (scroll + visible).min(records.len()),
+            records.len()
+        )
+    } else {
+        "Timeline".to_string()
+    };
+    let timeline = Paragraph::new(lines)
+        .scroll((scroll.min(u16::MAX as usize) as u16, 0))
+        .block(Block::default().title(title).borders(Borders::ALL));
+    f.render_widget(timeline, timeline_area);
+
+    let controls = Paragraph::new("←/→: Day | SHIFT+←/→: Week | ↑/↓/PGUP/PGDN: Scroll | T: Today | Q: Quit")
+        .style(Style::default().fg(Color::Cyan))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(controls, controls_area);
+}
+
+/// e.g. `Monday 2024-06-03`, or `Monday 2024-06-03 · #client-a` when filtered
+fn title(day: NaiveDate, tag: Option<&str>) -> String {
+    let mut title = day.format("%A %Y-%m-%d").to_string();
+    if let Some(tag) = tag {
+        title.push_str(&format!(" · #{}", tag));
+    }
+    title
+}
+
+/// e.g. `4 pomodoros · 1h40m focus · 20m break · 2 interruptions`
+fn describe_totals(totals: &Totals) -> String {
+    format!(
+        "{} pomodoros · {} focus · {} break · {} interruptions",
+        totals.pomodoros,
+        format_duration(totals.focus_secs),
+        format_duration(totals.break_secs),
+        totals.interruptions
+    )
+}
+
+/// Completed phases as a bar from their start to their end; notes and
+/// adjustments as they read in `pomo log`.
+fn timeline_line(record: &Record) -> Line<'static> {
+    let Record::Phase(entry) = record else {
+        let color = match record {
+            Record::Interruption(_) => Color::Red,
+            _ => Color::DarkGray,
+        };
+        return Line::from(Span::styled(format!("{:13}{}", "", record.describe()), Style::default().fg(color)));
+    };
+    let (phase, color) = match entry.event {
+        Transition::FocusEnd => ("focus", Color::Green),
+        Transition::BreakEnd => ("break", Color::Yellow),
+    };
+    let start = entry.at - chrono::Duration::seconds(entry.duration_secs as i64);
+    let cells = (entry.duration_secs.div_ceil(BAR_CELL_SECS) as usize).clamp(1, BAR_MAX);
+    let mut label = format!(" {} {}", format_duration(entry.duration_secs), phase);
+    if let Some(task) = &entry.task {
+        label.push_str(" — ");
+        label.push_str(task);
+    }
+    if let Some(category) = &entry.category {
+        label.push_str(" [");
+        label.push_str(category);
+        label.push(']');
+    }
+    for tag in &entry.tags {
+        label.push_str(" #");
+        label.push_str(tag);
+    }
+    Line::from(vec![
+        Span::raw(format!("{}–{}  ", start.format("%H:%M"), entry.at.format("%H:%M"))),
+        Span::styled("█".repeat(cells), Style::default().fg(color)),
+        Span::raw(" ".repeat(BAR_MAX - cells)),
+        Span::raw(label),
+    ])
+}
+
+/// `1h40m`, `25m`, or `45s` for the short phases of `--focus-secs`.
+fn format_duration(secs: u64) -> String {
+    match secs {
+        1..60 => format!("{}s", secs),
+        0..3600 => format!("{}m", secs / 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs / 60 % 60),
+    }
+}
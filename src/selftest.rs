@@ -0,0 +1,76 @@
+use crate::{PomodoroTimer, Stage, Transition};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How often the loop wakes, like the TUI between key presses.
+const POLL: Duration = Duration::from_millis(100);
+/// The timer counts whole seconds, so it may lag the clock by up to one,
+/// plus a poll before the tick is noticed; anything more is drift.
+const MAX_LAG: Duration = Duration::from_millis(1000 + 2 * POLL.as_millis() as u64);
+
+#[derive(clap::Args, Debug)]
+pub struct SelftestArgs {
+    /// Length of the focus phase to time, in seconds
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..=3600))]
+    secs: u64,
+}
+
+/// Run one focus phase against the real clock, ticking it the way the TUI
+/// and the background timer do, and report how far the timer strayed from
+/// the clock; `pomo selftest`. Returns whether it kept time.
+pub fn run(args: &SelftestArgs) -> bool {
+    let expected = Duration::from_secs(args.secs);
+    let start = Instant::now();
+    let mut timer = PomodoroTimer::new(Stage::default_schedule(args.secs, 60), start);
+    let mut last_tick = start;
+    // Seconds the timer is behind the clock; negative when ahead
+    let mut worst_lag = 0.0f64;
+    let ended = loop {
+        thread::sleep(POLL);
+        let now = Instant::now();
+        if now.duration_since(last_tick) < Duration::from_secs(1) {
+            continue;
+        }
+        last_tick = now;
+        let ended = timer.update(now) == Some(Transition::FocusEnd);
+        let counted = if ended {
+            args.secs
+        } else {
+            args.secs - timer.focus_remaining
+        };
+        let lag = now.duration_since(start).as_secs_f64() - counted as f64;
+        if lag.abs() > worst_lag.abs() {
+            worst_lag = lag;
+        }
+        if ended {
+            break now;
+        }
+    };
+
+    let measured = ended.duration_since(start);
+    let error = measured.as_secs_f64() - expected.as_secs_f64();
+    println!("Timed a {}s focus phase against the real clock", args.secs);
+    println!(
+        "expected {:.3}s, ended after {:.3}s ({:+.3}s)",
+        expected.as_secs_f64(),
+        measured.as_secs_f64(),
+        error
+    );
+    println!(
+        "drift: {:+.3}s per minute, timer at most {:.3}s behind the clock",
+        error / expected.as_secs_f64() * 60.0,
+        worst_lag
+    );
+    let passed = worst_lag >= 0.0 && worst_lag <= MAX_LAG.as_secs_f64() && error <= MAX_LAG.as_secs_f64();
+    if passed {
+        println!("PASS");
+    } else {
+        println!(
+            "FAIL: the timer should stay within {:.1}s of the clock",
+            MAX_LAG.as_secs_f64()
+        );
+    }
+    passed
+}
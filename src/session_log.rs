@@ -0,0 +1,592 @@
+use crate::{Phase, Transition};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone};
+use clap::ValueEnum;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// One line of the JSON-lines event log, written when a phase completes.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub at: DateTime<Local>,
+    pub event: Transition,
+    pub duration_secs: u64,
+    pub cycles: u32,
+    #[serde(default)]
+    pub task: Option<String>,
+    /// The preset the lengths came from, built in or from the config
+    #[serde(default)]
+    pub preset: Option<String>,
+    /// One of the `categories` from the config; `None` is uncategorized
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Normalized with `tags::normalize`, in the order given
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Pomodoros the task was expected to take, as set when this was written
+    #[serde(default)]
+    pub estimate: Option<u32>,
+    /// The app the session was brought in from by `pomo import`; `None`
+    /// for sessions pomo ran itself
+    #[serde(default)]
+    pub source: Option<String>,
+    /// How the focus session felt, rated with `+`/`-` while it ran, or
+    /// by a `LateRating` once read back
+    #[serde(default)]
+    pub rating: Option<Rating>,
+    /// Micro-breaks taken during the focus session, with `microbreak_interval`
+    #[serde(default)]
+    pub microbreaks: u32,
+    /// Checklist items ticked off during the break; `None` without a checklist
+    #[serde(default)]
+    pub checklist: Option<ChecklistTally>,
+}
+
+/// How much of the break checklist got done.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ChecklistTally {
+    pub done: u32,
+    pub of: u32,
+}
+
+/// A subjective verdict on a focus session, next to the objective numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rating {
+    Win,
+    Fail,
+}
+
+impl Rating {
+    pub fn label(self) -> &'static str {
+        match self {
+            Rating::Win => "win",
+            Rating::Fail => "fail",
+        }
+    }
+}
+
+impl LogEntry {
+    /// e.g. `10:25 ✔ completed 25m focus — write report #client-a`
+    pub fn describe(&self) -> String {
+        let phase = match self.event {
+            Transition::FocusEnd => "focus",
+            Transition::BreakEnd => "break",
+        };
+        let mut line = format!(
+            "{} ✔ completed {} {}",
+            self.at.format("%H:%M"),
+            format_secs(self.duration_secs),
+            phase
+        );
+        if let Some(task) = &self.task {
+            line.push_str(" — ");
+            line.push_str(task);
+        }
+        for tag in &self.tags {
+            line.push_str(" #");
+            line.push_str(tag);
+        }
+        if let Some(rating) = self.rating {
+            line.push_str(" (");
+            line.push_str(rating.label());
+            line.push(')');
+        }
+        match self.microbreaks {
+            0 => {}
+            1 => line.push_str(" · 1 micro-break"),
+            count => line.push_str(&format!(" · {} micro-breaks", count)),
+        }
+        if let Some(tally) = self.checklist {
+            line.push_str(&format!(" · checklist {}/{}", tally.done, tally.of));
+        }
+        line
+    }
+}
+
+/// How the length of a completed phase is rounded before it is logged, for
+/// time trackers that only take whole minutes. The timer itself keeps
+/// exact time, so logged totals can differ slightly from it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rounding {
+    /// Exact seconds
+    #[default]
+    None,
+    /// To the nearest whole minute; half a minute rounds up
+    NearestMinute,
+    /// Up to a whole minute
+    Up,
+    /// Down to a whole minute
+    Down,
+}
+
+impl Rounding {
+    pub fn apply(self, secs: u64) -> u64 {
+        match self {
+            Rounding::None => secs,
+            Rounding::NearestMinute => (secs + 30) / 60 * 60,
+            Rounding::Up => secs.div_ceil(60) * 60,
+            Rounding::Down => secs / 60 * 60,
+        }
+    }
+}
+
+/// A manual change to a phase length, e.g. with `f`/`F`, kept so oddly
+/// sized sessions can be explained later.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Adjustment {
+    pub at: DateTime<Local>,
+    pub adjusted: Phase,
+    pub from_secs: u64,
+    pub to_secs: u64,
+}
+
+/// A note on what interrupted a phase, typed in after pressing the note key.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Interruption {
+    pub at: DateTime<Local>,
+    pub during: Phase,
+    pub note: String,
+}
+
+/// A rating given to a focus session after it was logged, with `+`/`-`
+/// during the break that follows it. The latest one for a session counts.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LateRating {
+    pub at: DateTime<Local>,
+    /// When the rated session ended, the `at` of its entry
+    pub session: DateTime<Local>,
+    pub rating: Rating,
+}
+
+/// Any line of the log. Readers that only want completed phases use
+/// `SessionLog::entries`, which skips adjustments and interruptions.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Record {
+    Phase(LogEntry),
+    Adjustment(Adjustment),
+    Interruption(Interruption),
+    Rating(LateRating),
+}
+
+impl Record {
+    pub fn at(&self) -> DateTime<Local> {
+        match self {
+            Record::Phase(entry) => entry.at,
+            Record::Adjustment(adjustment) => adjustment.at,
+            Record::Interruption(interruption) => interruption.at,
+            Record::Rating(rating) => rating.at,
+        }
+    }
+
+    /// e.g. `10:12 ✎ focus 25m → 30m`, `10:40 ✖ focus: phone call`, or
+    /// `10:28 ★ focus ending 10:25: win`
+    pub fn describe(&self) -> String {
+        match self {
+            Record::Phase(entry) => entry.describe(),
+            Record::Adjustment(adjustment) => format!(
+                "{} ✎ {} {} → {}",
+                adjustment.at.format("%H:%M"),
+                match adjustment.adjusted {
+                    Phase::Focus => "focus",
+                    Phase::Break => "break",
+                },
+                format_secs(adjustment.from_secs),
+                format_secs(adjustment.to_secs)
+            ),
+            Record::Interruption(interruption) => format!(
+                "{} ✖ {}: {}",
+                interruption.at.format("%H:%M"),
+                match interruption.during {
+                    Phase::Focus => "focus",
+                    Phase::Break => "break",
+                },
+                interruption.note
+            ),
+            Record::Rating(rating) => format!(
+                "{} ★ focus ending {}: {}",
+                rating.at.format("%H:%M"),
+                rating.session.format("%H:%M"),
+                rating.rating.label()
+            ),
+        }
+    }
+}
+
+/// Whole minutes as `25m`; lengths under a minute (from --focus-secs and
+/// --break-secs) as `45s`.
+fn format_secs(secs: u64) -> String {
+    if secs < 60 {
+        format!("{}s", secs)
+    } else {
+        format!("{}m", secs / 60)
+    }
+}
+
+pub struct SessionLog {
+    path: PathBuf,
+}
+
+impl SessionLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Default location, e.g. `~/.local/share/pomo/sessions.jsonl` on Linux.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("pomo").join("sessions.jsonl"))
+    }
+
+    pub fn append(&self, entry: &impl Serialize) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        // A single write per entry keeps lines whole for readers tailing the file
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())
+    }
+
+    /// All completed phases, each focus session with the latest late rating
+    /// given for it; other records and lines that fail to parse are skipped.
+    pub fn entries(&self) -> io::Result<Vec<LogEntry>> {
+        Ok(self
+            .records()?
+            .into_iter()
+            .filter_map(|record| match record {
+                Record::Phase(entry) => Some(entry),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Every record: completed phases, adjustments, interruptions and late
+    /// ratings, in the order they were written. Each late rating is also
+    /// applied to the focus session it rates, the latest one counting.
+    pub fn records(&self) -> io::Result<Vec<Record>> {
+        let mut records: Vec<Record> = self.read()?;
+        for i in 0..records.len() {
+            let Record::Rating(late) = &records[i] else {
+                continue;
+            };
+            let (session, rating) = (late.session, late.rating);
+            let rated = records[..i].iter_mut().rev().find_map(|record| match record {
+                Record::Phase(entry) if entry.event == Transition::FocusEnd && entry.at == session => Some(entry),
+                _ => None,
+            });
+            if let Some(entry) = rated {
+                entry.rating = Some(rating);
+            }
+        }
+        Ok(records)
+    }
+
+    fn read<T: DeserializeOwned>(&self) -> io::Result<Vec<T>> {
+        match File::open(&self.path) {
+            Ok(file) => Ok(BufReader::new(file)
+                .lines()
+                .map_while(Result::ok)
+                .filter_map(|line| serde_json::from_str(&line).ok())
+                .collect()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Delete the whole log. A missing log is already clear.
+    pub fn clear(&self) -> io::Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Drop records from before `cutoff` and return how many were dropped.
+    /// The kept lines go to a temporary file that then replaces the log, so
+    /// an interrupted rotation leaves the old log whole. Lines that do not
+    /// parse are kept as they are.
+    pub fn rotate(&self, cutoff: DateTime<Local>) -> io::Result<usize> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        let mut kept = String::new();
+        let mut dropped = 0;
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            match serde_json::from_str::<Record>(&line) {
+                Ok(record) if record.at() < cutoff => dropped += 1,
+                _ => {
+                    kept.push_str(&line);
+                    kept.push('\n');
+                }
+            }
+        }
+        if dropped > 0 {
+            let temp = self.path.with_extension("jsonl.tmp");
+            fs::write(&temp, kept)?;
+            fs::rename(&temp, &self.path)?;
+        }
+        Ok(dropped)
+    }
+
+    /// Add `entries` among the records already in the log, in time order,
+    /// for sessions from before its end such as those of `pomo import`.
+    /// Rewritten through a temporary file like `rotate`; lines that do not
+    /// parse stay where they are.
+    pub fn insert(&self, mut entries: Vec<LogEntry>) -> io::Result<()> {
+        entries.sort_by_key(|entry| entry.at);
+        let existing = match fs::read_to_string(&self.path) {
+            Ok(existing) => existing,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        let mut pending = entries.into_iter().peekable();
+        let mut merged = String::new();
+        for line in existing.lines() {
+            if let Ok(record) = serde_json::from_str::<Record>(line) {
+                while let Some(entry) = pending.next_if(|entry| entry.at < record.at()) {
+                    merged.push_str(&serde_json::to_string(&entry)?);
+                    merged.push('\n');
+                }
+            }
+            merged.push_str(line);
+            merged.push('\n');
+        }
+        for entry in pending {
+            merged.push_str(&serde_json::to_string(&entry)?);
+            merged.push('\n');
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp = self.path.with_extension("jsonl.tmp");
+        fs::write(&temp, merged)?;
+        fs::rename(&temp, &self.path)
+    }
+
+    /// Print entries appended by any running instance until interrupted.
+    /// Copes with the file not existing yet, being truncated or replaced,
+    /// and with a writer caught halfway through a line.
+    pub fn follow(&self, mut on_entry: impl FnMut(Record)) -> io::Result<()> {
+        let mut reader = Follower::open(&self.path, true)?;
+        let mut partial = Vec::new();
+
+        loop {
+            if let Some(follower) = &mut reader {
+                if follower.replaced(&self.path)? {
+                    reader = Follower::open(&self.path, false)?;
+                    partial.clear();
+                    continue;
+                }
+                follower.file.read_to_end(&mut partial)?;
+                follower.position = follower.file.stream_position()?;
+                // Anything after the last newline is a line still being written
+                while let Some(newline) = partial.iter().position(|&byte| byte == b'\n') {
+                    let line: Vec<u8> = partial.drain(..=newline).collect();
+                    if let Ok(entry) = serde_json::from_slice(&line) {
+                        on_entry(entry);
+                    }
+                }
+            } else {
+                reader = Follower::open(&self.path, false)?;
+            }
+            thread::sleep(FOLLOW_POLL_INTERVAL);
+        }
+    }
+}
+
+struct Follower {
+    file: File,
+    position: u64,
+}
+
+impl Follower {
+    fn open(path: &Path, at_end: bool) -> io::Result<Option<Self>> {
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let position = if at_end { file.seek(SeekFrom::End(0))? } else { 0 };
+        Ok(Some(Self { file, position }))
+    }
+
+    /// True when the file was truncated, deleted or swapped for a new one.
+    fn replaced(&self, path: &Path) -> io::Result<bool> {
+        let current = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(true),
+            Err(e) => return Err(e),
+        };
+        if current.len() < self.position {
+            return Ok(true);
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            if current.ino() != self.file.metadata()?.ino() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+pub fn start_of_today() -> DateTime<Local> {
+    let midnight = Local::now().date_naive().and_time(NaiveTime::MIN);
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .unwrap_or_else(Local::now)
+}
+
+/// The day `at` counts towards when days start at `day_start`; with a 04:00
+/// start, 02:00 still belongs to the previous date.
+pub fn day_of(at: DateTime<Local>, day_start: NaiveTime) -> NaiveDate {
+    (at.naive_local() - day_start.signed_duration_since(NaiveTime::MIN)).date()
+}
+
+/// Parse `--day-start`: a time of day (`04:00`).
+pub fn parse_day_start(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| format!("expected HH:MM, got '{}'", value))
+}
+
+/// Parse a day: `today`, `yesterday` or a date (`2024-06-01`). Today is
+/// the day `day_start` puts now in.
+pub fn parse_day(value: &str, day_start: NaiveTime) -> Result<NaiveDate, String> {
+    let today = day_of(Local::now(), day_start);
+    match value.to_lowercase().as_str() {
+        "today" => Ok(today),
+        "yesterday" => Ok(today.pred_opt().unwrap_or(today)),
+        _ => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map_err(|_| format!("expected today, yesterday or YYYY-MM-DD, got '{}'", value)),
+    }
+}
+
+/// Parse `--since`: a date (`2024-06-01`) or a time today (`09:30`).
+pub fn parse_since(value: &str) -> Result<DateTime<Local>, String> {
+    let naive = if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        date.and_time(NaiveTime::MIN)
+    } else if let Ok(time) = NaiveTime::parse_from_str(value, "%H:%M") {
+        Local::now().date_naive().and_time(time)
+    } else {
+        return Err(format!("expected YYYY-MM-DD or HH:MM, got '{}'", value));
+    };
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .ok_or_else(|| format!("'{}' does not exist in the local time zone", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 6, day, hour, minute, 0).earliest().unwrap()
+    }
+
+    #[test]
+    fn day_of_counts_before_the_day_start_towards_the_previous_date() {
+        let four = NaiveTime::from_hms_opt(4, 0, 0).unwrap();
+        assert_eq!(day_of(at(2, 3, 59), four), NaiveDate::from_ymd_opt(2024, 6, 1).unwrap());
+        assert_eq!(day_of(at(2, 4, 0), four), NaiveDate::from_ymd_opt(2024, 6, 2).unwrap());
+    }
+
+    #[test]
+    fn day_of_rolls_over_at_midnight_by_default() {
+        assert_eq!(day_of(at(1, 23, 59), NaiveTime::MIN), NaiveDate::from_ymd_opt(2024, 6, 1).unwrap());
+        assert_eq!(day_of(at(2, 0, 0), NaiveTime::MIN), NaiveDate::from_ymd_opt(2024, 6, 2).unwrap());
+    }
+
+    fn focus_end(at: DateTime<Local>, rating: Option<Rating>) -> LogEntry {
+        LogEntry {
+            at,
+            event: Transition::FocusEnd,
+            duration_secs: 25 * 60,
+            cycles: 1,
+            task: None,
+            preset: None,
+            category: None,
+            tags: Vec::new(),
+            estimate: None,
+            source: None,
+            rating,
+            microbreaks: 0,
+            checklist: None,
+        }
+    }
+
+    #[test]
+    fn a_late_rating_reaches_the_session_it_rates_and_the_latest_counts() {
+        let path = std::env::temp_dir().join(format!("pomo-late-rating-{}.jsonl", std::process::id()));
+        let log = SessionLog::new(path.clone());
+        log.clear().unwrap();
+        log.append(&focus_end(at(1, 9, 25), Some(Rating::Fail))).unwrap();
+        log.append(&focus_end(at(1, 10, 0), None)).unwrap();
+        for (minute, rating) in [(27, Rating::Win), (28, Rating::Fail), (29, Rating::Win)] {
+            let late = LateRating {
+                at: at(1, 9, minute),
+                session: at(1, 9, 25),
+                rating,
+            };
+            log.append(&late).unwrap();
+        }
+        // A session not in the log is rated by nothing
+        let late = LateRating {
+            at: at(1, 10, 5),
+            session: at(1, 9, 59),
+            rating: Rating::Fail,
+        };
+        log.append(&late).unwrap();
+
+        let ratings: Vec<_> = log.entries().unwrap().iter().map(|entry| entry.rating).collect();
+        log.clear().unwrap();
+        assert_eq!(ratings, [Some(Rating::Win), None]);
+    }
+
+    #[test]
+    fn rounding_none_keeps_the_exact_seconds() {
+        assert_eq!(Rounding::None.apply(89), 89);
+        assert_eq!(Rounding::None.apply(0), 0);
+    }
+
+    #[test]
+    fn rounding_to_the_nearest_minute_goes_up_from_half_a_minute() {
+        assert_eq!(Rounding::NearestMinute.apply(89), 60);
+        assert_eq!(Rounding::NearestMinute.apply(90), 120);
+        assert_eq!(Rounding::NearestMinute.apply(29), 0);
+        assert_eq!(Rounding::NearestMinute.apply(120), 120);
+    }
+
+    #[test]
+    fn rounding_up_and_down_keep_whole_minutes_as_they_are() {
+        assert_eq!(Rounding::Up.apply(89), 120);
+        assert_eq!(Rounding::Up.apply(61), 120);
+        assert_eq!(Rounding::Up.apply(60), 60);
+        assert_eq!(Rounding::Up.apply(0), 0);
+        assert_eq!(Rounding::Down.apply(89), 60);
+        assert_eq!(Rounding::Down.apply(119), 60);
+        assert_eq!(Rounding::Down.apply(60), 60);
+        assert_eq!(Rounding::Down.apply(59), 0);
+    }
+}
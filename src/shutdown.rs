@@ -0,0 +1,52 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+/// Set when the terminal closes or the process is asked to end (SIGHUP,
+/// SIGINT or SIGTERM), so the loop can stop the usual way: the terminal,
+/// do-not-disturb and the Slack status are all restored on the way out,
+/// which the default handling would skip.
+#[derive(Debug, Clone, Default)]
+pub struct Shutdown(Arc<AtomicBool>);
+
+impl Shutdown {
+    /// Catch the signals from now on; needs the Tokio runtime. A signal
+    /// that cannot be caught keeps its default handling.
+    pub fn listen() -> Self {
+        let shutdown = Self::default();
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{SignalKind, signal};
+            for kind in [SignalKind::hangup(), SignalKind::interrupt(), SignalKind::terminate()] {
+                let Ok(mut signals) = signal(kind) else {
+                    continue;
+                };
+                let shutdown = shutdown.clone();
+                tokio::spawn(async move {
+                    if signals.recv().await.is_some() {
+                        shutdown.request();
+                    }
+                });
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    shutdown.request();
+                }
+            });
+        }
+        shutdown
+    }
+
+    pub fn request(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn requested(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,146 @@
+use crate::config::SlackConfig;
+use chrono::{DateTime, Local};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+const API_URL: &str = "https://slack.com/api";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Mirrors focus sessions into the user's Slack status and do-not-disturb.
+pub struct Slack {
+    token: String,
+    agent: ureq::Agent,
+    focusing: bool,
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+impl Slack {
+    /// Returns `None` when the integration is disabled in the config.
+    pub fn new(config: &SlackConfig) -> Option<Self> {
+        config.enabled.then(|| Self {
+            token: config.token.clone(),
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+            focusing: false,
+            last_error: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Call every frame with the end of the running focus phase, if any. The
+    /// status is only touched when focus starts or stops.
+    pub fn sync(&mut self, focus_until: Option<DateTime<Local>>) {
+        match focus_until {
+            Some(until) if !self.focusing => self.start_focus(until),
+            None if self.focusing => self.end_focus(),
+            _ => {}
+        }
+    }
+
+    /// Set "focusing until HH:MM" and snooze notifications until `until`.
+    fn start_focus(&mut self, until: DateTime<Local>) {
+        self.focusing = true;
+        let minutes = (until - Local::now()).num_minutes().max(1);
+        let profile = serde_json::json!({
+            "profile": {
+                "status_text": format!("focusing until {}", until.format("%H:%M")),
+                "status_emoji": ":tomato:",
+                // Slack expires the status itself if pomo never gets to clear it
+                "status_expiration": until.timestamp(),
+            }
+        });
+        self.spawn(move |slack| {
+            slack.set_profile(&profile)?;
+            slack.call_form("dnd.setSnooze", &[("num_minutes", &minutes.to_string())])
+        });
+    }
+
+    /// Clear the status and end the snooze in the background.
+    fn end_focus(&mut self) {
+        self.focusing = false;
+        self.spawn(|slack| slack.clear());
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().unwrap().clone()
+    }
+
+    fn spawn<F>(&self, request: F)
+    where
+        F: FnOnce(&Client) -> Result<(), String> + Send + 'static,
+    {
+        let client = self.client();
+        let last_error = Arc::clone(&self.last_error);
+        tokio::task::spawn_blocking(move || {
+            *last_error.lock().unwrap() = request(&client).err().map(|e| format!("slack: {}", e));
+        });
+    }
+
+    fn client(&self) -> Client {
+        Client {
+            token: self.token.clone(),
+            agent: self.agent.clone(),
+        }
+    }
+}
+
+impl Drop for Slack {
+    // Runs on quit, on early returns and while unwinding from a panic, so a
+    // crash never leaves the user stuck in do-not-disturb.
+    fn drop(&mut self) {
+        if self.focusing {
+            let _ = self.client().clear();
+        }
+    }
+}
+
+struct Client {
+    token: String,
+    agent: ureq::Agent,
+}
+
+impl Client {
+    fn clear(&self) -> Result<(), String> {
+        let profile = serde_json::json!({
+            "profile": { "status_text": "", "status_emoji": "", "status_expiration": 0 }
+        });
+        self.set_profile(&profile)?;
+        self.call_form("dnd.endSnooze", &[])
+    }
+
+    fn set_profile(&self, profile: &serde_json::Value) -> Result<(), String> {
+        let method = "users.profile.set";
+        let response = self
+            .request(method)
+            .set("Content-Type", "application/json; charset=utf-8")
+            .send_string(&profile.to_string());
+        Self::check(method, response)
+    }
+
+    // The dnd.* methods only accept form-encoded arguments
+    fn call_form(&self, method: &str, form: &[(&str, &str)]) -> Result<(), String> {
+        Self::check(method, self.request(method).send_form(form))
+    }
+
+    fn request(&self, method: &str) -> ureq::Request {
+        self.agent
+            .post(&format!("{}/{}", API_URL, method))
+            .set("Authorization", &format!("Bearer {}", self.token))
+    }
+
+    fn check(method: &str, response: Result<ureq::Response, ureq::Error>) -> Result<(), String> {
+        let response: serde_json::Value = response
+            .map_err(|e| e.to_string())?
+            .into_string()
+            .map_err(|e| e.to_string())
+            .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()))?;
+
+        // Slack reports API errors with HTTP 200 and `"ok": false`
+        if response["ok"].as_bool() == Some(true) {
+            Ok(())
+        } else {
+            let error = response["error"].as_str().unwrap_or("unknown error");
+            Err(format!("{} failed: {}", method, error))
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use crate::{
+    audio::{Overlap, Volume},
+    Transition,
+};
+use clap::ValueEnum;
+use rodio::{
+    buffer::SamplesBuffer,
+    source::{SineWave, UniformSourceIterator},
+    Decoder, Source,
+};
+use serde::Deserialize;
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Notification sounds synthesized at runtime, so no files need to exist on disk.
+#[derive(Debug, Default, Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SoundTheme {
+    /// The classic triple beep
+    #[default]
+    Beep,
+    /// A soft marimba arpeggio
+    Marimba,
+    /// A ringing bell
+    Bell,
+}
+
+impl SoundTheme {
+    /// The theme's sound for a transition: a falling figure as focus ends
+    /// and a rising one as the break ends. The beep theme has its own
+    /// `beeps`, which a custom melody replaces.
+    fn render(self, transition: Transition) -> Option<SamplesBuffer<f32>> {
+        let focus_end = transition == Transition::FocusEnd;
+        match self {
+            SoundTheme::Beep => None,
+            SoundTheme::Marimba => {
+                let notes: &[f32] = if focus_end {
+                    &[783.99, 659.25, 523.25]
+                } else {
+                    &[523.25, 659.25, 783.99, 1046.5]
+                };
+                let strikes: Vec<_> = notes.iter().enumerate().map(|(i, &note)| (i as f32 * 0.14, note)).collect();
+                Some(struck(&strikes, MARIMBA, 1.2))
+            }
+            SoundTheme::Bell => {
+                let strikes: &[(f32, f32)] = if focus_end {
+                    &[(0.0, 329.63)]
+                } else {
+                    &[(0.0, 523.25), (0.35, 659.25)]
+                };
+                Some(struck(strikes, BELL, 2.5))
+            }
+        }
+    }
+}
+
+/// The partials of a struck bar or bell: frequency ratio to the note,
+/// amplitude, and how fast it dies away (per second).
+type Partials = &'static [(f32, f32, f32)];
+
+/// A marimba bar: the fundamental and the fourth harmonic its bars are tuned to.
+const MARIMBA: Partials = &[(1.0, 1.0, 6.0), (3.93, 0.25, 14.0), (9.2, 0.05, 30.0)];
+
+/// A bell: the hum, prime, minor third, fifth and nominal, ringing long.
+const BELL: Partials = &[(0.5, 0.35, 1.2), (1.0, 1.0, 1.6), (1.2, 0.45, 2.0), (1.5, 0.3, 2.4), (2.0, 0.5, 3.0)];
+
+/// Strikes at `(seconds, frequency)`, each left ringing over the next,
+/// mixed into one buffer `seconds` past the last strike.
+fn struck(strikes: &[(f32, f32)], partials: Partials, seconds: f32) -> SamplesBuffer<f32> {
+    const SAMPLE_RATE: u32 = 44_100;
+    const ATTACK: f32 = 0.004;
+    let last = strikes.iter().map(|&(at, _)| at).fold(0.0, f32::max);
+    let mut samples = vec![0.0; ((last + seconds) * SAMPLE_RATE as f32) as usize];
+    // Louder partials add up, so scale the sum back to the beeps' volume
+    let scale = 0.20 / partials.iter().map(|&(_, amplitude, _)| amplitude).sum::<f32>();
+    for &(at, frequency) in strikes {
+        let offset = (at * SAMPLE_RATE as f32) as usize;
+        for (i, sample) in samples[offset..].iter_mut().enumerate() {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            // A few milliseconds of attack, so the strike does not click
+            let attack = (t / ATTACK).min(1.0);
+            let ring: f32 = partials
+                .iter()
+                .map(|&(ratio, amplitude, decay)| {
+                    amplitude * (-decay * t).exp() * (std::f32::consts::TAU * frequency * ratio * t).sin()
+                })
+                .sum();
+            *sample += ring * attack * scale;
+        }
+    }
+    SamplesBuffer::new(1, SAMPLE_RATE, samples)
+}
+
+/// Which sound to play, resolved from the command line and config.
+#[derive(Debug, Clone)]
+pub struct Sound {
+    /// Off means the timer plays no notifications at all
+    pub enabled: bool,
+    /// Repeat the alarm while `--manual` waits for a key
+    pub escalate: bool,
+    /// Play the sound again with each nudge after a break, instead of the alarm
+    pub nudge: bool,
+    pub theme: SoundTheme,
+    /// Played instead of the beep theme's triple beep when not empty
+    pub melody: Vec<Note>,
+    /// Handed to `Audio::start`, which applies it
+    pub volume: Volume,
+    /// What a notification does when another is still playing
+    pub overlap: Overlap,
+    /// A custom sound file, played instead of the theme when it can be decoded
+    pub file: Option<PathBuf>,
+    /// Files for one transition only, played instead of `file`
+    pub focus_end: Option<PathBuf>,
+    pub break_end: Option<PathBuf>,
+}
+
+impl Sound {
+    /// The custom file for a transition, if any.
+    fn file_for(&self, transition: Transition) -> Option<&Path> {
+        let specific = match transition {
+            Transition::FocusEnd => &self.focus_end,
+            Transition::BreakEnd => &self.break_end,
+        };
+        specific.as_ref().or(self.file.as_ref()).map(PathBuf::as_path)
+    }
+
+    /// Custom files that do not exist; they fall back to the theme when played.
+    pub fn missing_files(&self) -> Vec<&Path> {
+        [&self.file, &self.focus_end, &self.break_end]
+            .into_iter()
+            .flatten()
+            .map(PathBuf::as_path)
+            .filter(|path| !path.exists())
+            .collect()
+    }
+}
+
+impl Default for Sound {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            escalate: true,
+            nudge: false,
+            theme: SoundTheme::default(),
+            melody: Vec::new(),
+            volume: Volume::default(),
+            overlap: Overlap::default(),
+            file: None,
+            focus_end: None,
+            break_end: None,
+        }
+    }
+}
+
+/// The source for a notification: the transition's custom file if it
+/// decodes, otherwise the theme's sound, otherwise the melody or the beeps.
+pub fn notification_source(sound: &Sound, transition: Transition) -> Box<dyn Source<Item = f32> + Send> {
+    // An unreadable custom file falls back to the theme rather than silence
+    let custom = sound
+        .file_for(transition)
+        .and_then(|path| File::open(path).ok())
+        .and_then(|file| Decoder::new(BufReader::new(file)).ok());
+    if let Some(source) = custom {
+        return Box::new(source.convert_samples());
+    }
+
+    match sound.theme.render(transition) {
+        Some(source) => Box::new(source),
+        None if !sound.melody.is_empty() => Box::new(melody(&sound.melody)),
+        None => Box::new(beeps()),
+    }
+}
+
+/// A single soft tone, quieter than the notification, for mid-phase cues.
+pub fn soft_tone() -> Box<dyn Source<Item = f32> + Send> {
+    Box::new(
+        SineWave::new(660.0)
+            .take_duration(Duration::from_millis(300))
+            .amplify(0.08),
+    )
+}
+
+/// A short beep for each number of the get-ready count, and a longer,
+/// higher one as the first phase starts.
+pub fn countdown_tone(go: bool) -> Box<dyn Source<Item = f32> + Send> {
+    let (frequency, millis) = if go { (1320.0, 400) } else { (880.0, 150) };
+    Box::new(
+        SineWave::new(frequency)
+            .take_duration(Duration::from_millis(millis))
+            .amplify(0.15),
+    )
+}
+
+/// A rising arpeggio for lifetime milestones, unlike any transition sound.
+pub fn fanfare() -> Box<dyn Source<Item = f32> + Send> {
+    let notes = [(523.25, 120), (659.25, 120), (783.99, 120), (1046.5, 400)].map(|(frequency, millis)| Note {
+        frequency,
+        length: Duration::from_millis(millis),
+    });
+    Box::new(melody(&notes))
+}
+
+/// One note of a custom melody.
+#[derive(Debug, Clone, Copy)]
+pub struct Note {
+    frequency: f32,
+    length: Duration,
+}
+
+/// The longest a melody note may be; the melody is rendered up front.
+const MAX_NOTE: Duration = Duration::from_secs(10);
+/// The longest a whole melody may be, gaps included.
+const MAX_MELODY: Duration = Duration::from_secs(30);
+
+/// Check the `melody` from the config: `[frequency_hz, duration_ms]` pairs,
+/// all positive, no note longer than `MAX_NOTE` and all of them no longer
+/// than `MAX_MELODY`. An empty list is no melody rather than an error.
+pub fn parse_melody(notes: &[(f64, f64)]) -> Result<Vec<Note>, String> {
+    let notes = notes
+        .iter()
+        .enumerate()
+        .map(|(i, &(frequency, millis))| {
+            if !(frequency > 0.0 && millis > 0.0 && frequency.is_finite() && millis.is_finite()) {
+                Err(format!("note {} needs a positive frequency and length, got [{}, {}]", i + 1, frequency, millis))
+            } else if millis > MAX_NOTE.as_millis() as f64 {
+                let max = MAX_NOTE.as_millis();
+                Err(format!("note {} is {} ms long, more than the {} ms a note may be", i + 1, millis, max))
+            } else {
+                Ok(Note {
+                    frequency: frequency as f32,
+                    length: Duration::from_secs_f64(millis / 1000.0),
+                })
+            }
+        })
+        .collect::<Result<Vec<Note>, String>>()?;
+    let gaps = NOTE_GAP * notes.len().saturating_sub(1) as u32;
+    let total = notes.iter().map(|note| note.length).sum::<Duration>() + gaps;
+    if total > MAX_MELODY {
+        return Err(format!("the melody is {} ms long, more than {} ms", total.as_millis(), MAX_MELODY.as_millis()));
+    }
+    Ok(notes)
+}
+
+/// The silence between two notes of a melody.
+const NOTE_GAP: Duration = Duration::from_millis(30);
+
+/// The notes one after another at the beeps' volume, with a short gap
+/// between them so repeated notes stay apart. One buffer, like the beeps.
+fn melody(notes: &[Note]) -> SamplesBuffer<f32> {
+    const SAMPLE_RATE: u32 = 44_100;
+    let gap = (SAMPLE_RATE as f64 * NOTE_GAP.as_secs_f64()) as usize;
+    let mut samples = Vec::new();
+    for (i, note) in notes.iter().enumerate() {
+        if i > 0 {
+            samples.extend(std::iter::repeat_n(0.0, gap));
+        }
+        let tone = SineWave::new(note.frequency).take_duration(note.length).amplify(0.20);
+        samples.extend(UniformSourceIterator::<_, f32>::new(tone, 1, SAMPLE_RATE));
+    }
+    SamplesBuffer::new(1, SAMPLE_RATE, samples)
+}
+
+/// Three beeps with pauses between them, rendered into one buffer so the
+/// whole pattern can be faded as a unit.
+fn beeps() -> SamplesBuffer<f32> {
+    const SAMPLE_RATE: u32 = 44_100;
+    let mut samples = Vec::new();
+    for i in 0..3 {
+        // Generate a sine wave beep
+        let beep = SineWave::new(800.0) // 800 Hz frequency
+            .take_duration(Duration::from_millis(200)) // 0.2 seconds
+            .amplify(0.20); // 20% volume
+        samples.extend(UniformSourceIterator::<_, f32>::new(beep, 1, SAMPLE_RATE));
+
+        // Add a pause between beeps (except after the last one)
+        if i < 2 {
+            let pause = SAMPLE_RATE as usize * 150 / 1000; // 0.15 seconds
+            samples.extend(std::iter::repeat_n(0.0, pause));
+        }
+    }
+    SamplesBuffer::new(1, SAMPLE_RATE, samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_theme_is_audible_and_tells_the_transitions_apart() {
+        for theme in [SoundTheme::Marimba, SoundTheme::Bell] {
+            let [focus_end, break_end] = [Transition::FocusEnd, Transition::BreakEnd]
+                .map(|transition| theme.render(transition).unwrap().collect::<Vec<f32>>());
+            for samples in [&focus_end, &break_end] {
+                let peak = samples.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+                assert!((0.05..=1.0).contains(&peak), "{:?} peaks at {}", theme, peak);
+            }
+            assert_ne!(focus_end, break_end, "{:?}", theme);
+        }
+    }
+
+    #[test]
+    fn a_melody_with_a_bad_note_is_rejected() {
+        assert!(parse_melody(&[]).unwrap().is_empty());
+        assert_eq!(parse_melody(&[(440.0, 150.0), (660.0, 300.0)]).unwrap().len(), 2);
+        for note in [(0.0, 150.0), (440.0, -1.0), (f64::NAN, 150.0), (440.0, f64::INFINITY)] {
+            assert!(parse_melody(&[note]).unwrap_err().starts_with("note 1 needs"), "{:?}", note);
+        }
+    }
+
+    #[test]
+    fn a_note_longer_than_ten_seconds_is_rejected() {
+        assert!(parse_melody(&[(440.0, 10_000.0)]).is_ok());
+        let error = parse_melody(&[(440.0, 150.0), (440.0, 1e12)]).unwrap_err();
+        assert_eq!(error, "note 2 is 1000000000000 ms long, more than the 10000 ms a note may be");
+    }
+
+    #[test]
+    fn a_melody_longer_than_thirty_seconds_is_rejected() {
+        // The gaps between notes count too
+        assert!(parse_melody(&[(440.0, 9_950.0); 3]).is_ok());
+        let mut notes = vec![(440.0, 9_950.0); 3];
+        notes.push((440.0, 100.0));
+        assert_eq!(parse_melody(&notes).unwrap_err(), "the melody is 30040 ms long, more than 30000 ms");
+    }
+}
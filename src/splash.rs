@@ -0,0 +1,101 @@
+use crate::input::Keys;
+use ratatui::{
+    Frame, Terminal,
+    backend::Backend,
+    layout::{Alignment, Constraint, Flex, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph},
+};
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+/// How long the splash stays up unless a key dismisses it first.
+const SHOWN_FOR: Duration = Duration::from_secs(1);
+const WIDTH: u16 = 40;
+
+const LOGO: [&str; 5] = [
+    r" _ __   ___  _ __ ___   ___  ",
+    r"| '_ \ / _ \| '_ ` _ \ / _ \ ",
+    r"| |_) | (_) | | | | | | (_) |",
+    r"| .__/ \___/|_| |_| |_|\___/ ",
+    r"|_|                          ",
+];
+
+/// The settings the run resolved from the flags, the config file and the
+/// defaults, as label and value, e.g. `("Focus", "25min")`.
+pub type Settings = Vec<(&'static str, String)>;
+
+/// Show the logo, version and settings until a key is pressed or
+/// `SHOWN_FOR` is up. The key only dismisses the splash.
+pub fn show<B: Backend>(
+    terminal: &mut Terminal<B>,
+    keys: &mut Keys,
+    settings: &Settings,
+    icon: &str,
+) -> io::Result<()> {
+    let until = Instant::now() + SHOWN_FOR;
+    loop {
+        terminal.draw(|f| render(f, settings, icon))?;
+        let left = until.saturating_duration_since(Instant::now());
+        // Nothing back before the time is up means the terminal was resized
+        if left.is_zero() || keys.next(left)?.is_some() {
+            return Ok(());
+        }
+    }
+}
+
+fn render(f: &mut Frame, settings: &Settings, icon: &str) {
+    let mut lines: Vec<Line> = LOGO
+        .iter()
+        .map(|row| Line::styled(*row, Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)))
+        .collect();
+    lines.push(Line::styled(
+        format!("v{}", env!("CARGO_PKG_VERSION")),
+        Style::default().fg(Color::Gray),
+    ));
+    lines.push(Line::from(""));
+    // Padded to the same width, so centering keeps the columns lined up
+    let label_width = settings
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let value_width = settings
+        .iter()
+        .map(|(_, value)| value.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (label, value) in settings {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{:<1$}  ", label, label_width),
+                Style::default().fg(Color::Gray),
+            ),
+            Span::styled(
+                format!("{:<1$}", value, value_width),
+                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+            ),
+        ]));
+    }
+
+    let height = lines.len() as u16 + 4;
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(f.area());
+    let [area] = Layout::horizontal([Constraint::Length(WIDTH)])
+        .flex(Flex::Center)
+        .areas(area);
+    let block = Block::default()
+        .title(format!(" {} pomo ", icon))
+        .title_bottom(Line::from(" any key to continue ").centered())
+        .borders(Borders::ALL)
+        .style(Style::default().fg(Color::Red));
+    let paragraph = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .block(block.padding(Padding::uniform(1)));
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Settings changed from the keyboard that outlive the run, kept next to
+/// the event log. Unlike the config file, pomo writes this one itself.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct State {
+    /// Last set with `m`; until then the config's `muted` applies
+    pub muted: Option<bool>,
+    /// Last set with `v`/`V`; until then the config's `master` applies
+    pub master: Option<f32>,
+}
+
+impl State {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("pomo").join("state.json"))
+    }
+
+    /// Read the state; a missing file means nothing has been changed yet.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(io::Error::from),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Written through a temporary file, like the lifetime stats.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&temp, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_master_volume_and_reads_older_files() {
+        let path = std::env::temp_dir().join(format!("pomo-state-{}.json", std::process::id()));
+        fs::write(&path, r#"{"muted": true}"#).unwrap();
+        let mut state = State::load(&path).unwrap();
+        assert_eq!(state.master, None);
+
+        state.master = Some(0.4);
+        state.save(&path).unwrap();
+        let state = State::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!((state.muted, state.master), (Some(true), Some(0.4)));
+    }
+}
@@ -0,0 +1,434 @@
+use crate::{
+    session_log::{self, LogEntry},
+    tags, Transition,
+};
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveTime};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const UNTAGGED: &str = "untagged";
+/// How far back `pomo stats` looks for days that met the daily goal.
+const GOAL_DAYS: u32 = 30;
+const UNCATEGORIZED: &str = "uncategorized";
+
+#[derive(clap::Args, Debug)]
+pub struct StatsArgs {
+    /// Only count sessions from this date (YYYY-MM-DD) or time today (HH:MM)
+    #[arg(long, value_parser = session_log::parse_since)]
+    since: Option<DateTime<Local>>,
+
+    /// Only count sessions before this date (YYYY-MM-DD) or time today (HH:MM)
+    #[arg(long, value_parser = session_log::parse_since)]
+    until: Option<DateTime<Local>>,
+
+    /// Only count sessions whose task contains this text (case-insensitive)
+    #[arg(long)]
+    task: Option<String>,
+
+    /// Match --task exactly instead of as a substring
+    #[arg(long, requires = "task")]
+    exact: bool,
+
+    /// Only count sessions run with this preset, e.g. 52-17
+    #[arg(long)]
+    preset: Option<String>,
+
+    /// Only count sessions tagged with this, e.g. client-a
+    #[arg(long, value_parser = tags::parse)]
+    tag: Option<String>,
+
+    /// Leave out sessions brought in with `pomo import`
+    #[arg(long)]
+    no_imported: bool,
+
+    /// Break the totals down per task or category
+    #[arg(long, value_enum)]
+    group_by: Option<GroupBy>,
+
+    /// Shorthand for --group-by category
+    #[arg(long, conflicts_with = "group_by")]
+    by_category: bool,
+
+    /// Compare each task's pomodoro estimate with the sessions it took
+    #[arg(long, conflicts_with_all = ["group_by", "by_category"])]
+    estimates: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+
+    /// Shorthand for --format json
+    #[arg(long, conflicts_with = "format")]
+    json: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GroupBy {
+    Task,
+    Category,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+    /// Aligned columns for reading
+    Table,
+    /// Machine-readable, for scripts and dashboards
+    Json,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Totals {
+    pub pomodoros: u32,
+    pub focus_minutes: u64,
+}
+
+/// The overall numbers: today, this week (from Monday) and everything
+/// matching the filters. Dates are `YYYY-MM-DD`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatsSummary {
+    pub date: NaiveDate,
+    pub week_start: NaiveDate,
+    pub today: Totals,
+    pub week: Totals,
+    pub all_time: Totals,
+    /// Only with a `daily_goal` in the config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<GoalRate>,
+}
+
+/// How many of the last `days` days, today included, reached the daily goal.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GoalRate {
+    pub daily_goal: u32,
+    pub days: u32,
+    pub days_reached: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaskTotals {
+    pub task: String,
+    #[serde(flatten)]
+    pub totals: Totals,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CategoryTotals {
+    pub category: String,
+    #[serde(flatten)]
+    pub totals: Totals,
+}
+
+/// A task's last estimate against the focus sessions logged for it.
+#[derive(Debug, Serialize)]
+pub struct EstimateRow {
+    pub task: String,
+    pub estimate: u32,
+    pub actual: u32,
+}
+
+impl EstimateRow {
+    /// How far off the estimate was, as a share of it: 0.5 took half again as long.
+    fn error(&self) -> f64 {
+        (self.actual as f64 - self.estimate as f64) / self.estimate as f64
+    }
+}
+
+impl Totals {
+    fn add(&mut self, entry: &LogEntry) {
+        self.pomodoros += 1;
+        self.focus_minutes += entry.duration_secs / 60;
+    }
+}
+
+impl StatsArgs {
+    fn json(&self) -> bool {
+        self.json || self.format == Format::Json
+    }
+
+    fn group_by(&self) -> Option<GroupBy> {
+        if self.by_category { Some(GroupBy::Category) } else { self.group_by }
+    }
+
+    fn matches(&self, entry: &LogEntry) -> bool {
+        if entry.event != Transition::FocusEnd
+            || self.since.is_some_and(|since| entry.at < since)
+            || self.until.is_some_and(|until| entry.at >= until)
+            || self.preset.as_ref().is_some_and(|preset| entry.preset.as_ref() != Some(preset))
+            || self.tag.as_ref().is_some_and(|tag| !entry.tags.contains(tag))
+            || (self.no_imported && entry.source.is_some())
+        {
+            return false;
+        }
+        match (&self.task, &entry.task) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(wanted), Some(task)) if self.exact => task == wanted,
+            (Some(wanted), Some(task)) => task.to_lowercase().contains(&wanted.to_lowercase()),
+        }
+    }
+}
+
+impl StatsSummary {
+    /// Entries count toward the day they fall on by `day_start`, so a
+    /// session past midnight still belongs to the evening before.
+    pub fn new<'a>(
+        entries: impl IntoIterator<Item = &'a LogEntry>,
+        date: NaiveDate,
+        day_start: NaiveTime,
+        daily_goal: Option<u32>,
+    ) -> Self {
+        let week_start = date - Days::new(date.weekday().num_days_from_monday().into());
+        let goal_start = date - Days::new((GOAL_DAYS - 1).into());
+        let mut summary = Self {
+            date,
+            week_start,
+            today: Totals::default(),
+            week: Totals::default(),
+            all_time: Totals::default(),
+            goal: None,
+        };
+        let mut per_day: HashMap<NaiveDate, u32> = HashMap::new();
+        for entry in entries {
+            let day = session_log::day_of(entry.at, day_start);
+            if day >= goal_start && day <= date {
+                *per_day.entry(day).or_default() += 1;
+            }
+            summary.all_time.add(entry);
+            if day >= week_start && day <= date {
+                summary.week.add(entry);
+            }
+            if day == date {
+                summary.today.add(entry);
+            }
+        }
+        summary.goal = daily_goal.map(|daily_goal| GoalRate {
+            daily_goal,
+            days: GOAL_DAYS,
+            days_reached: per_day.values().filter(|&&pomodoros| pomodoros >= daily_goal).count() as u32,
+        });
+        summary
+    }
+}
+
+/// Focus totals per task, largest first, with unlabeled sessions under "untagged".
+pub fn group_by_task<'a>(entries: impl IntoIterator<Item = &'a LogEntry>) -> Vec<TaskTotals> {
+    group(entries, |entry| entry.task.as_deref().unwrap_or(UNTAGGED))
+        .into_iter()
+        .map(|(task, totals)| TaskTotals { task, totals })
+        .collect()
+}
+
+/// Focus totals per category, largest first, with untagged sessions under
+/// "uncategorized".
+pub fn group_by_category<'a>(entries: impl IntoIterator<Item = &'a LogEntry>) -> Vec<CategoryTotals> {
+    group(entries, |entry| entry.category.as_deref().unwrap_or(UNCATEGORIZED))
+        .into_iter()
+        .map(|(category, totals)| CategoryTotals { category, totals })
+        .collect()
+}
+
+/// Tasks that were given an estimate, by name. A task estimated again in a
+/// later run is judged by the newest estimate, against all its sessions.
+pub fn estimates<'a>(entries: impl IntoIterator<Item = &'a LogEntry>) -> Vec<EstimateRow> {
+    let mut tasks: HashMap<&str, (Option<u32>, u32)> = HashMap::new();
+    for entry in entries {
+        let Some(task) = &entry.task else {
+            continue;
+        };
+        let (estimate, actual) = tasks.entry(task).or_default();
+        *estimate = entry.estimate.or(*estimate);
+        *actual += 1;
+    }
+    let mut rows: Vec<EstimateRow> = tasks
+        .into_iter()
+        .filter_map(|(task, (estimate, actual))| {
+            Some(EstimateRow {
+                task: task.to_string(),
+                estimate: estimate?,
+                actual,
+            })
+        })
+        .collect();
+    rows.sort_by(|a, b| a.task.cmp(&b.task));
+    rows
+}
+
+fn group<'a>(
+    entries: impl IntoIterator<Item = &'a LogEntry>,
+    key: impl Fn(&'a LogEntry) -> &'a str,
+) -> Vec<(String, Totals)> {
+    let mut groups: HashMap<&str, Totals> = HashMap::new();
+    for entry in entries {
+        groups.entry(key(entry)).or_default().add(entry);
+    }
+
+    let mut rows: Vec<(String, Totals)> = groups
+        .into_iter()
+        .map(|(name, totals)| (name.to_string(), totals))
+        .collect();
+    rows.sort_by(|(a_name, a), (b_name, b)| {
+        b.focus_minutes
+            .cmp(&a.focus_minutes)
+            .then(b.pomodoros.cmp(&a.pomodoros))
+            .then_with(|| a_name.cmp(b_name))
+    });
+    rows
+}
+
+pub fn run(
+    entries: &[LogEntry],
+    args: &StatsArgs,
+    day_start: NaiveTime,
+    daily_goal: Option<u32>,
+) -> Result<(), serde_json::Error> {
+    let selected: Vec<&LogEntry> = entries.iter().filter(|entry| args.matches(entry)).collect();
+
+    if args.estimates {
+        let rows = estimates(selected);
+        if args.json() {
+            println!("{}", serde_json::to_string_pretty(&rows)?);
+        } else {
+            print_estimates(&rows);
+        }
+        return Ok(());
+    }
+
+    match args.group_by() {
+        Some(GroupBy::Task) => {
+            let rows = group_by_task(selected);
+            if args.json() {
+                println!("{}", serde_json::to_string_pretty(&rows)?);
+            } else {
+                print_table("TASK", rows.iter().map(|row| (row.task.as_str(), &row.totals)));
+            }
+        }
+        Some(GroupBy::Category) => {
+            let rows = group_by_category(selected);
+            if args.json() {
+                println!("{}", serde_json::to_string_pretty(&rows)?);
+            } else {
+                print_table("CATEGORY", rows.iter().map(|row| (row.category.as_str(), &row.totals)));
+            }
+        }
+        None => {
+            let today = session_log::day_of(Local::now(), day_start);
+            let summary = StatsSummary::new(selected, today, day_start, daily_goal);
+            if args.json() {
+                println!("{}", serde_json::to_string_pretty(&summary)?);
+            } else {
+                println!("{:<9}  {:>9}  {:>13}", "", "POMODOROS", "FOCUS MINUTES");
+                for (label, totals) in [
+                    ("Today", &summary.today),
+                    ("This week", &summary.week),
+                    ("All time", &summary.all_time),
+                ] {
+                    println!("{:<9}  {:>9}  {:>13}", label, totals.pomodoros, totals.focus_minutes);
+                }
+                if let Some(goal) = &summary.goal {
+                    println!();
+                    println!(
+                        "Daily goal of {} reached on {} of the last {} days ({:.0}%)",
+                        goal.daily_goal,
+                        goal.days_reached,
+                        goal.days,
+                        goal.days_reached as f64 / goal.days as f64 * 100.0
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_estimates(rows: &[EstimateRow]) {
+    let width = rows.iter().map(|row| row.task.chars().count()).chain(["TASK".len()]).max().unwrap_or_default();
+    println!("{:<width$}  {:>8}  {:>6}  {:>6}", "TASK", "ESTIMATE", "ACTUAL", "OFF BY");
+    for row in rows {
+        println!(
+            "{:<width$}  {:>8}  {:>6}  {:>+5.0}%",
+            row.task,
+            row.estimate,
+            row.actual,
+            row.error() * 100.0
+        );
+    }
+    if !rows.is_empty() {
+        let within = rows.iter().filter(|row| row.actual <= row.estimate).count();
+        let average = rows.iter().map(|row| row.error().abs()).sum::<f64>() / rows.len() as f64;
+        println!();
+        println!(
+            "{} of {} tasks within the estimate; estimates were off by {:.0}% on average",
+            within,
+            rows.len(),
+            average * 100.0
+        );
+    }
+}
+
+fn print_table<'a>(heading: &str, rows: impl Iterator<Item = (&'a str, &'a Totals)> + Clone) {
+    let width = rows
+        .clone()
+        .map(|(name, _)| name.chars().count())
+        .chain([heading.len()])
+        .max()
+        .unwrap_or_default();
+
+    println!("{:<width$}  {:>9}  {:>13}", heading, "POMODOROS", "FOCUS MINUTES");
+    for (name, totals) in rows {
+        println!("{:<width$}  {:>9}  {:>13}", name, totals.pomodoros, totals.focus_minutes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn focus_on(date: &str, minutes: u64) -> LogEntry {
+        let line = format!(
+            r#"{{"at":"{}T10:00:00+00:00","event":"focus_end","duration_secs":{},"cycles":1}}"#,
+            date,
+            minutes * 60
+        );
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[test]
+    fn the_json_summary_parses_back_into_the_struct() {
+        // Wednesday, so Monday's session counts towards the week
+        let entries = [focus_on("2024-06-05", 25), focus_on("2024-06-03", 50), focus_on("2024-05-01", 25)];
+        let summary = StatsSummary::new(&entries, NaiveDate::from_ymd_opt(2024, 6, 5).unwrap(), NaiveTime::MIN, None);
+
+        let json = serde_json::to_string(&summary).unwrap();
+        let parsed: StatsSummary = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.date, summary.date);
+        assert_eq!(parsed.week_start, NaiveDate::from_ymd_opt(2024, 6, 3).unwrap());
+        assert_eq!((parsed.today.pomodoros, parsed.today.focus_minutes), (1, 25));
+        assert_eq!((parsed.week.pomodoros, parsed.week.focus_minutes), (2, 75));
+        assert_eq!((parsed.all_time.pomodoros, parsed.all_time.focus_minutes), (3, 100));
+        assert!(parsed.goal.is_none());
+    }
+
+    #[test]
+    fn the_goal_rate_counts_late_sessions_toward_the_day_before_day_start() {
+        let at = |day, hour| {
+            let mut entry = focus_on("2024-06-01", 25);
+            entry.at = Local.with_ymd_and_hms(2024, 6, day, hour, 30, 0).unwrap();
+            entry
+        };
+        let entries = [at(4, 23), at(5, 1)];
+        let date = NaiveDate::from_ymd_opt(2024, 6, 5).unwrap();
+        let reached = |day_start| StatsSummary::new(&entries, date, day_start, Some(2)).goal.unwrap().days_reached;
+        assert_eq!(reached(NaiveTime::MIN), 0);
+        assert_eq!(reached(NaiveTime::from_hms_opt(4, 0, 0).unwrap()), 1);
+    }
+
+    #[test]
+    fn json_dates_are_plain_days() {
+        let summary = StatsSummary::new([], NaiveDate::from_ymd_opt(2024, 6, 5).unwrap(), NaiveTime::MIN, None);
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["date"], "2024-06-05");
+        assert_eq!(json["week_start"], "2024-06-03");
+    }
+}
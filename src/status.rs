@@ -0,0 +1,328 @@
+use crate::{template::Values, Phase, PomodoroTimer, TimerState};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// What a client asks the running timer, as one line such as `skip`. Every
+/// request is answered with the status after it was carried out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Request {
+    Status,
+    TogglePause,
+    Skip,
+    Reset,
+    /// End a background timer; the TUI ignores it like the other controls
+    Stop,
+}
+
+impl Request {
+    fn name(self) -> &'static str {
+        match self {
+            Request::Status => "status",
+            Request::TogglePause => "toggle",
+            Request::Skip => "skip",
+            Request::Reset => "reset",
+            Request::Stop => "stop",
+        }
+    }
+
+    /// Anything unrecognized only asks for the status, and so does a
+    /// client that sends nothing.
+    fn parse(line: &str) -> Self {
+        match line.trim() {
+            "toggle" => Request::TogglePause,
+            "skip" => Request::Skip,
+            "reset" => Request::Reset,
+            "stop" => Request::Stop,
+            _ => Request::Status,
+        }
+    }
+}
+
+/// What a running timer reports to `pomo status`, one JSON line per
+/// connection. Field names are part of the `--json` output, so they stay
+/// stable.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Status {
+    pub phase: Phase,
+    pub paused: bool,
+    pub remaining_secs: u64,
+    /// Both timers, whichever phase is running
+    #[serde(default)]
+    pub focus_remaining_secs: u64,
+    #[serde(default)]
+    pub break_remaining_secs: u64,
+    /// The cycle the phase belongs to; a break belongs to the focus before it
+    pub cycle: u32,
+    /// Focus sessions completed so far
+    #[serde(default)]
+    pub completed_cycles: u32,
+    #[serde(default)]
+    pub task: Option<String>,
+    /// Focus completed by the running instance so far
+    #[serde(default)]
+    pub focus_minutes: u64,
+    /// Started with `pomo start`, so there is no TUI of its own
+    #[serde(default)]
+    pub daemon: bool,
+    /// Sound is off, with `--mute`, `m` or the config's `muted`
+    #[serde(default)]
+    pub muted: bool,
+}
+
+impl Status {
+    pub fn of(timer: &PomodoroTimer, task: Option<&str>, focus_minutes: u64) -> Self {
+        let phase = timer.current_phase();
+        Self {
+            phase,
+            paused: timer.state == TimerState::Paused,
+            remaining_secs: match phase {
+                Phase::Focus => timer.focus_remaining,
+                Phase::Break => timer.break_remaining,
+            },
+            focus_remaining_secs: timer.focus_remaining,
+            break_remaining_secs: timer.break_remaining,
+            cycle: match phase {
+                Phase::Focus => timer.total_cycles + 1,
+                Phase::Break => timer.total_cycles.max(1),
+            },
+            completed_cycles: timer.total_cycles,
+            task: task.map(str::to_string),
+            focus_minutes,
+            daemon: false,
+            muted: false,
+        }
+    }
+
+    /// e.g. `Focus`, or `Paused (break)`.
+    pub fn state(&self) -> &'static str {
+        match (self.paused, self.phase) {
+            (false, Phase::Focus) => "Focus",
+            (false, Phase::Break) => "Break",
+            (true, Phase::Focus) => "Paused (focus)",
+            (true, Phase::Break) => "Paused (break)",
+        }
+    }
+
+    /// The status for a `--status-format` or `[format] status` template.
+    pub fn values(&self, today_minutes: u64) -> Values<'_> {
+        Values {
+            state: self.state(),
+            phase: match self.phase {
+                Phase::Focus => "Focus",
+                Phase::Break => "Break",
+            },
+            remaining_secs: self.remaining_secs,
+            focus_remaining_secs: self.focus_remaining_secs,
+            break_remaining_secs: self.break_remaining_secs,
+            cycle: self.cycle,
+            cycles: self.completed_cycles,
+            task: self.task.as_deref(),
+            ends_at: (!self.paused).then(|| Local::now() + chrono::Duration::seconds(self.remaining_secs as i64)),
+            today_minutes,
+            focus_minutes: self.focus_minutes,
+            muted: self.muted,
+        }
+    }
+}
+
+/// Default location, e.g. `$XDG_RUNTIME_DIR/pomo/status.sock` on Linux.
+pub fn socket_path() -> Option<PathBuf> {
+    dirs::runtime_dir()
+        .or_else(dirs::data_dir)
+        .map(|dir| dir.join("pomo").join("status.sock"))
+}
+
+/// A request read from a client, and where its answer goes.
+#[cfg(unix)]
+type Pending = (Request, Sender<Status>);
+
+/// The running timer's end of the socket. It never blocks the main loop:
+/// each client is read on a thread of its own, and the requests read so
+/// far are answered once per frame.
+#[cfg(unix)]
+pub struct StatusServer {
+    listener: UnixListener,
+    path: PathBuf,
+    requests: Receiver<Pending>,
+    sender: Sender<Pending>,
+}
+
+#[cfg(unix)]
+impl StatusServer {
+    /// Listen at `path`, unless another instance already answers there. A
+    /// socket left behind by a crashed instance is replaced.
+    pub fn bind(path: PathBuf) -> io::Result<Option<Self>> {
+        if UnixStream::connect(&path).is_ok() {
+            return Ok(None);
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        let listener = UnixListener::bind(&path)?;
+        listener.set_nonblocking(true)?;
+        let (sender, requests) = mpsc::channel();
+        Ok(Some(Self {
+            listener,
+            path,
+            requests,
+            sender,
+        }))
+    }
+
+    /// Answer every request read so far: `handle` carries it out and
+    /// returns the status to send back. New clients are handed to their
+    /// own thread and answered on a later frame.
+    pub fn serve(&self, mut handle: impl FnMut(Request) -> Status) {
+        while let Ok((stream, _)) = self.listener.accept() {
+            let sender = self.sender.clone();
+            thread::spawn(move || answer(stream, sender));
+        }
+        while let Ok((request, reply)) = self.requests.try_recv() {
+            // A client that hangs up early only loses its own answer
+            let _ = reply.send(handle(request));
+        }
+    }
+}
+
+/// Read one client's request, wait for the timer to carry it out, and
+/// send back the status. A client that hangs up or goes quiet before a
+/// whole line asks for the status, as connecting alone does.
+#[cfg(unix)]
+fn answer(mut stream: UnixStream, requests: Sender<Pending>) {
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_read_timeout(Some(CLIENT_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(CLIENT_TIMEOUT));
+    let mut request = String::new();
+    if BufReader::new(&stream).read_line(&mut request).is_err() {
+        request.clear();
+    }
+    let (reply, status) = mpsc::channel();
+    if requests.send((Request::parse(&request), reply)).is_err() {
+        return;
+    }
+    // Fails once the timer has exited without getting to the request
+    if let Ok(status) = status.recv()
+        && let Ok(line) = serde_json::to_string(&status)
+    {
+        let _ = writeln!(stream, "{}", line);
+    }
+}
+
+#[cfg(unix)]
+impl Drop for StatusServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Ask a running instance for its status; `None` when nothing answers.
+pub fn query(path: &Path) -> io::Result<Option<Status>> {
+    send(path, Request::Status)
+}
+
+/// Send a running instance a request and return its status afterwards;
+/// `None` when nothing answers.
+#[cfg(unix)]
+pub fn send(path: &Path, request: Request) -> io::Result<Option<Status>> {
+    let Ok(mut stream) = UnixStream::connect(path) else {
+        return Ok(None);
+    };
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    writeln!(stream, "{}", request.name())?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    serde_json::from_str(&line)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Without Unix sockets there is never an instance to ask.
+#[cfg(not(unix))]
+pub fn send(_path: &Path, _request: Request) -> io::Result<Option<Status>> {
+    Ok(None)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::{Stage, template::Template};
+    use std::{net::Shutdown, time::Instant};
+
+    fn listen(name: &str) -> (StatusServer, PathBuf) {
+        let path = std::env::temp_dir().join(format!("pomo-status-{}-{}.sock", std::process::id(), name));
+        let server = StatusServer::bind(path.clone()).unwrap().expect("nothing else listens there");
+        (server, path)
+    }
+
+    /// Serve frames until a request comes in, each frame checked to be quick.
+    fn next_request(server: &StatusServer) -> Request {
+        let timer = PomodoroTimer::new(Stage::default_schedule(60, 60), Instant::now());
+        let mut received = None;
+        while received.is_none() {
+            let frame = Instant::now();
+            server.serve(|request| {
+                received = Some(request);
+                Status::of(&timer, None, 0)
+            });
+            assert!(frame.elapsed() < Duration::from_millis(100));
+            thread::sleep(Duration::from_millis(10));
+        }
+        received.unwrap()
+    }
+
+    #[test]
+    fn cycles_counts_only_finished_focus() {
+        let start = Instant::now();
+        let mut timer = PomodoroTimer::new(Stage::default_schedule(60, 60), start);
+        let template = Template::parse("{cycle}/{cycles}").unwrap();
+        let render = |timer: &PomodoroTimer| template.render(&Status::of(timer, None, 0).values(0));
+        assert_eq!(render(&timer), "1/0");
+        timer.skip(start);
+        assert_eq!(render(&timer), "1/1");
+        timer.skip(start);
+        assert_eq!(render(&timer), "2/1");
+    }
+
+    #[test]
+    fn a_client_that_sends_nothing_gets_the_status() {
+        let (server, path) = listen("bare");
+        let client = thread::spawn(move || {
+            let stream = UnixStream::connect(path).unwrap();
+            stream.shutdown(Shutdown::Write).unwrap();
+            let mut line = String::new();
+            BufReader::new(stream).read_line(&mut line).unwrap();
+            serde_json::from_str::<Status>(&line).unwrap()
+        });
+        assert_eq!(next_request(&server), Request::Status);
+        assert_eq!(client.join().unwrap().cycle, 1);
+    }
+
+    #[test]
+    fn a_silent_client_does_not_hold_up_the_frame_or_the_others() {
+        let (server, path) = listen("silent");
+        let silent = UnixStream::connect(&path).unwrap();
+        let client = thread::spawn(move || send(&path, Request::Skip).unwrap());
+        assert_eq!(next_request(&server), Request::Skip);
+        assert!(client.join().unwrap().is_some());
+        drop(silent);
+    }
+}
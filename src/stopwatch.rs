@@ -0,0 +1,153 @@
+use crate::{icons::Icons, input::Keys, PomodoroTimer};
+use crossterm::{
+    event::KeyCode,
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Alignment, Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame, Terminal,
+};
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+/// A plain count-up timer: no phases, cycles or sounds. Like the pomodoro
+/// timer it never reads the clock itself.
+struct Stopwatch {
+    /// Time counted before the current run, i.e. up to the last pause
+    accumulated: Duration,
+    /// When the current run started; `None` while paused
+    running_since: Option<Instant>,
+    /// Total elapsed time at each lap, oldest first
+    laps: Vec<Duration>,
+}
+
+impl Stopwatch {
+    fn new(now: Instant) -> Self {
+        Self {
+            accumulated: Duration::ZERO,
+            running_since: Some(now),
+            laps: Vec::new(),
+        }
+    }
+
+    fn elapsed(&self, now: Instant) -> Duration {
+        self.accumulated + self.running_since.map_or(Duration::ZERO, |since| now.duration_since(since))
+    }
+
+    fn toggle_pause(&mut self, now: Instant) {
+        match self.running_since.take() {
+            Some(since) => self.accumulated += now.duration_since(since),
+            None => self.running_since = Some(now),
+        }
+    }
+
+    /// Back to zero, keeping it running or paused as it was.
+    fn reset(&mut self, now: Instant) {
+        self.accumulated = Duration::ZERO;
+        self.running_since = self.running_since.map(|_| now);
+        self.laps.clear();
+    }
+
+    fn lap(&mut self, now: Instant) {
+        self.laps.push(self.elapsed(now));
+    }
+}
+
+/// Run the stopwatch in its own screen until `q`, and return the total.
+pub fn run(icons: &Icons) -> io::Result<Duration> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let result = run_loop(&mut terminal, icons);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+fn run_loop(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, icons: &Icons) -> io::Result<Duration> {
+    let mut keys = Keys::new();
+    let mut stopwatch = Stopwatch::new(Instant::now());
+    loop {
+        let now = Instant::now();
+        terminal.draw(|f| draw(f, &stopwatch, now, icons))?;
+
+        if let Some(key) = keys.next(Duration::from_millis(100))? {
+            let now = Instant::now();
+            match key {
+                KeyCode::Char('q') => return Ok(stopwatch.elapsed(now)),
+                KeyCode::Char(' ') => stopwatch.toggle_pause(now),
+                KeyCode::Char('r') => stopwatch.reset(now),
+                KeyCode::Char('l') => stopwatch.lap(now),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn draw(f: &mut Frame, stopwatch: &Stopwatch, now: Instant, icons: &Icons) {
+    let [digits_area, laps_area, controls_area] = Layout::vertical([
+        Constraint::Length(7),
+        Constraint::Min(0),
+        Constraint::Length(3),
+    ])
+    .areas(f.area());
+
+    let running = stopwatch.running_since.is_some();
+    let style = if running {
+        Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::DarkGray)
+    };
+    let time = PomodoroTimer::format_time(stopwatch.elapsed(now).as_secs());
+    let lines: Vec<Line> = PomodoroTimer::get_ascii_digits(&time)
+        .into_iter()
+        .map(|line| Line::from(Span::styled(line, style)))
+        .collect();
+    let title = if running {
+        format!("Stopwatch {}", icons.focus)
+    } else {
+        "Stopwatch (paused)".to_string()
+    };
+    let digits = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .block(Block::default().title(title).borders(Borders::ALL).style(style));
+    f.render_widget(digits, digits_area);
+
+    // Newest lap first, with the time since the lap before it
+    let laps: Vec<Line> = stopwatch
+        .laps
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, &total)| {
+            let previous = i.checked_sub(1).map_or(Duration::ZERO, |previous| stopwatch.laps[previous]);
+            Line::from(format!(
+                "Lap {:>2}  {}  (+{})",
+                i + 1,
+                PomodoroTimer::format_time(total.as_secs()),
+                PomodoroTimer::format_time((total - previous).as_secs())
+            ))
+        })
+        .collect();
+    let laps = Paragraph::new(laps)
+        .alignment(Alignment::Center)
+        .block(Block::default().title("Laps").borders(Borders::ALL));
+    f.render_widget(laps, laps_area);
+
+    let controls = Paragraph::new("SPACE: Pause/Resume | L: Lap | R: Reset | Q: Quit")
+        .style(Style::default().fg(Color::Cyan))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(controls, controls_area);
+}
@@ -0,0 +1,83 @@
+use serde::Deserialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Shown when the config lists no suggestions of its own.
+pub const DEFAULTS: [&str; 6] = [
+    "Stand up and stretch",
+    "Refill your water",
+    "Look at something 20 feet away for 20 seconds",
+    "Roll your shoulders and neck",
+    "Take a few slow, deep breaths",
+    "Walk around the room",
+];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Order {
+    /// Every suggestion once in a random order, then again in a new one
+    #[default]
+    Shuffle,
+    /// As listed, starting over at the end
+    InOrder,
+}
+
+/// One suggestion at a time for the break block, picked anew for every
+/// break and with `g`.
+pub struct Suggestions {
+    list: Vec<String>,
+    order: Order,
+    current: Option<usize>,
+    /// Shuffled positions not shown yet in this round
+    left: Vec<usize>,
+    /// Xorshift state, like the break jitter's; an even spread is all it needs
+    state: u64,
+}
+
+impl Suggestions {
+    /// `None` for an empty list, which turns suggestions off.
+    pub fn new(list: Vec<String>, order: Order) -> Option<Self> {
+        if list.is_empty() {
+            return None;
+        }
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_nanos());
+        Some(Self {
+            list,
+            order,
+            current: None,
+            left: Vec::new(),
+            // Xorshift never leaves zero
+            state: (nanos as u64).max(1),
+        })
+    }
+
+    /// Nothing until the first break.
+    pub fn current(&self) -> Option<&str> {
+        self.current.map(|index| self.list[index].as_str())
+    }
+
+    /// Move on to another suggestion. A shuffled round never repeats one,
+    /// and the next round does not start with the one just shown.
+    pub fn next(&mut self) {
+        let len = self.list.len();
+        self.current = Some(match self.order {
+            Order::InOrder => self.current.map_or(0, |index| (index + 1) % len),
+            Order::Shuffle => {
+                if self.left.is_empty() {
+                    self.left = (0..len).collect();
+                }
+                let mut pick = (self.random() % self.left.len() as u64) as usize;
+                if self.left.len() > 1 && Some(self.left[pick]) == self.current {
+                    pick = (pick + 1) % self.left.len();
+                }
+                self.left.swap_remove(pick)
+            }
+        });
+    }
+
+    fn random(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+}
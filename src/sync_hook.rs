@@ -0,0 +1,201 @@
+use std::{
+    process::{Command, Output, Stdio},
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// How long the log has to stay quiet before the command runs, so a focus
+/// end followed by a skipped break makes one push rather than two.
+const DEBOUNCE: Duration = Duration::from_secs(30);
+/// How often `finish` checks whether a command is done.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Default)]
+struct Failures {
+    count: u32,
+    last: Option<String>,
+}
+
+/// Runs the config's `sync_command` in the background once sessions have
+/// been logged, e.g. to commit and push the log to a git repository.
+pub struct SyncHook {
+    command: String,
+    /// When the log was last written without a sync started since
+    pending: Option<Instant>,
+    running: Option<JoinHandle<()>>,
+    failures: Arc<Mutex<Failures>>,
+}
+
+impl SyncHook {
+    pub fn new(command: String) -> Self {
+        Self {
+            command,
+            pending: None,
+            running: None,
+            failures: Arc::new(Mutex::new(Failures::default())),
+        }
+    }
+
+    /// Takes effect from the next sync; one already waiting still runs.
+    pub fn set_command(&mut self, command: String) {
+        self.command = command;
+    }
+
+    /// Note that the log changed; the sync waits until it has been quiet a while.
+    pub fn logged(&mut self, now: Instant) {
+        self.pending = Some(now);
+    }
+
+    /// Start the command once the log has settled and no earlier run is
+    /// still going. Call every frame.
+    pub fn tick(&mut self, now: Instant) {
+        if self.running.as_ref().is_some_and(|running| !running.is_finished()) {
+            return;
+        }
+        if self.pending.is_some_and(|at| now.duration_since(at) >= DEBOUNCE) {
+            self.pending = None;
+            let command = self.command.clone();
+            let failures = Arc::clone(&self.failures);
+            self.running = Some(thread::spawn(move || record(&failures, run(&command))));
+        }
+    }
+
+    /// Wait for a run in progress, then sync anything logged since it
+    /// started, so the last session is never left behind on exit. Gives
+    /// up after `limit`, so a command stuck on e.g. a password prompt
+    /// cannot hold up quitting: a run in progress is left to itself and
+    /// the last one is stopped.
+    pub fn finish(mut self, limit: Duration) -> Option<String> {
+        let deadline = Instant::now() + limit;
+        while self.running.as_ref().is_some_and(|running| !running.is_finished()) {
+            if Instant::now() >= deadline {
+                return Some("sync still running on exit; left it to finish on its own".to_string());
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        if self.pending.take().is_some() {
+            record(&self.failures, run_until(&self.command, deadline));
+        }
+        self.last_error()
+    }
+
+    /// Failures since the last success as one message, so a sync that
+    /// keeps failing shows a single warning rather than one per session.
+    pub fn last_error(&self) -> Option<String> {
+        let failures = self.failures.lock().unwrap();
+        let last = failures.last.as_ref()?;
+        Some(match failures.count {
+            1 => format!("sync failed: {}", last),
+            count => format!("sync failed {} times: {}", count, last),
+        })
+    }
+}
+
+/// The command through the shell, with no input.
+fn shell(command: &str) -> Command {
+    let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+    let mut shell = Command::new(shell);
+    shell.args([flag, command]).stdin(Stdio::null());
+    shell
+}
+
+/// Run `command` through the shell, failing with its last line of stderr.
+pub fn run(command: &str) -> Result<(), String> {
+    let output = shell(command).output().map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(describe_failure(&output))
+    }
+}
+
+/// `run`, but stopped if it is not done by `deadline`. It runs in a
+/// process group of its own, so whatever the shell started stops with it.
+fn run_until(command: &str, deadline: Instant) -> Result<(), String> {
+    let mut command = shell(command);
+    command.stdout(Stdio::null()).stderr(Stdio::piped());
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let mut child = command.spawn().map_err(|e| e.to_string())?;
+    while child.try_wait().map_err(|e| e.to_string())?.is_none() {
+        if Instant::now() >= deadline {
+            // SAFETY: kill only sends a signal, here to the group the child leads
+            #[cfg(unix)]
+            unsafe {
+                libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+            }
+            #[cfg(not(unix))]
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err("stopped, it was taking too long".to_string());
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(describe_failure(&output))
+    }
+}
+
+/// The last line the command wrote to stderr, or its exit status.
+fn describe_failure(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map_or_else(|| output.status.to_string(), str::to_string)
+}
+
+fn record(failures: &Mutex<Failures>, result: Result<(), String>) {
+    let mut failures = failures.lock().unwrap();
+    match result {
+        Ok(()) => *failures = Failures::default(),
+        Err(e) => {
+            failures.count += 1;
+            failures.last = Some(e);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    /// A hook with a sync waiting to run, as after a session was logged.
+    fn logged(command: &str) -> SyncHook {
+        let mut hook = SyncHook::new(command.to_string());
+        hook.logged(Instant::now());
+        hook
+    }
+
+    #[test]
+    fn finish_runs_the_waiting_sync() {
+        assert_eq!(logged("true").finish(Duration::from_secs(5)), None);
+        assert_eq!(
+            logged("echo rejected >&2; exit 1").finish(Duration::from_secs(5)).as_deref(),
+            Some("sync failed: rejected")
+        );
+    }
+
+    #[test]
+    fn finish_stops_a_sync_that_takes_too_long() {
+        let started = Instant::now();
+        let error = logged("sleep 30").finish(Duration::from_millis(300));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(error.as_deref(), Some("sync failed: stopped, it was taking too long"));
+    }
+
+    #[test]
+    fn finish_leaves_a_run_in_progress_that_takes_too_long() {
+        let mut hook = logged("sleep 30");
+        hook.tick(Instant::now() + DEBOUNCE);
+        let started = Instant::now();
+        let error = hook.finish(Duration::from_millis(300));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(error.as_deref(), Some("sync still running on exit; left it to finish on its own"));
+    }
+}
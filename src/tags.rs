@@ -0,0 +1,141 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::Span,
+};
+
+/// Chip colors, picked per tag so the same tag always looks the same.
+const COLORS: [Color; 6] = [
+    Color::Magenta,
+    Color::Blue,
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::LightRed,
+];
+
+/// The canonical form of a tag: lowercase, without the leading `#`, and
+/// with inner whitespace turned into `-`. `None` if nothing is left.
+pub fn normalize(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_start_matches('#');
+    let tag: Vec<String> = tag.split_whitespace().map(str::to_lowercase).collect();
+    (!tag.is_empty()).then(|| tag.join("-"))
+}
+
+/// Parse a `--tag` filter.
+pub fn parse(value: &str) -> Result<String, String> {
+    normalize(value).ok_or_else(|| "a tag cannot be empty".to_string())
+}
+
+/// Split `#tags` out of a task label: `write report #client-a #Writing`
+/// becomes the task `write report` and the tags `client-a`, `writing`. A
+/// label made only of tags leaves no task.
+pub fn split_label(label: &str) -> (Option<String>, Vec<String>) {
+    let (tags, words): (Vec<&str>, Vec<&str>) = label
+        .split_whitespace()
+        .partition(|word| word.len() > 1 && word.starts_with('#'));
+    let task = words.join(" ");
+    ((!task.is_empty()).then_some(task), tags.into_iter().filter_map(normalize).collect())
+}
+
+/// Normalized tags in first-seen order, each once.
+pub fn dedup(tags: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::new();
+    for tag in tags.into_iter().filter_map(|tag| normalize(&tag)) {
+        if !unique.contains(&tag) {
+            unique.push(tag);
+        }
+    }
+    unique
+}
+
+/// The tags as colored chips within `width` columns; the ones that do not
+/// fit are counted in a trailing `+N`.
+pub fn chips(tags: &[String], width: usize) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut used = 0;
+    for (i, tag) in tags.iter().enumerate() {
+        let chip = format!(" {} ", tag);
+        let rest = tags.len() - i - 1;
+        // Leave room for the `+N` that would follow when this is not the last
+        let reserve = if rest > 0 { format!(" +{}", rest).len() } else { 0 };
+        if used + 1 + chip.chars().count() + reserve > width {
+            let more = format!(" +{}", tags.len() - i);
+            if used + more.len() <= width {
+                spans.push(Span::styled(more, Style::default().fg(Color::DarkGray)));
+            }
+            break;
+        }
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            chip.clone(),
+            Style::default().fg(Color::Black).bg(color(tag)).add_modifier(Modifier::BOLD),
+        ));
+        used += 1 + chip.chars().count();
+    }
+    spans
+}
+
+fn color(tag: &str) -> Color {
+    let hash = tag.bytes().fold(0usize, |hash, byte| hash.wrapping_mul(31).wrapping_add(byte as usize));
+    COLORS[hash % COLORS.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(spans: &[Span]) -> String {
+        spans.iter().map(|span| span.content.as_ref()).collect()
+    }
+
+    fn owned(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    #[test]
+    fn normalize_lowercases_drops_the_hash_and_joins_words() {
+        assert_eq!(normalize("#Client-A").as_deref(), Some("client-a"));
+        assert_eq!(normalize("  Deep   Work ").as_deref(), Some("deep-work"));
+        assert_eq!(normalize("#"), None);
+        assert_eq!(normalize("   "), None);
+    }
+
+    #[test]
+    fn parse_rejects_an_empty_filter() {
+        assert_eq!(parse("#Writing"), Ok("writing".to_string()));
+        assert_eq!(parse(" # "), Err("a tag cannot be empty".to_string()));
+    }
+
+    #[test]
+    fn split_label_takes_the_tags_out_of_the_task() {
+        assert_eq!(
+            split_label("write report #client-a #Writing"),
+            (Some("write report".to_string()), owned(&["client-a", "writing"]))
+        );
+        // A lone `#` is part of the task, not an empty tag
+        assert_eq!(split_label("fix # in title"), (Some("fix # in title".to_string()), Vec::new()));
+        assert_eq!(split_label("#admin #mail"), (None, owned(&["admin", "mail"])));
+    }
+
+    #[test]
+    fn dedup_keeps_the_first_of_each_normalized_tag() {
+        let tags = owned(&["Writing", "#client", "writing", "", "CLIENT", "deep work"]);
+        assert_eq!(dedup(tags), owned(&["writing", "client", "deep-work"]));
+    }
+
+    #[test]
+    fn chips_count_the_tags_that_do_not_fit() {
+        let tags = owned(&["alpha", "beta", "gamma"]);
+        assert_eq!(text(&chips(&tags, 100)), "  alpha   beta   gamma ");
+        assert_eq!(text(&chips(&tags, 12)), "  alpha  +2");
+        assert_eq!(text(&chips(&tags, 5)), " +3");
+        assert_eq!(text(&chips(&tags, 2)), "");
+    }
+
+    #[test]
+    fn the_last_chip_needs_no_room_for_a_count() {
+        let tags = owned(&["ab", "cd"]);
+        assert_eq!(text(&chips(&tags, 10)), "  ab   cd ");
+        assert_eq!(text(&chips(&tags, 9)), "  ab  +1");
+    }
+}
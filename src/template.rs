@@ -0,0 +1,245 @@
+use crate::PomodoroTimer;
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+
+/// A user-defined output line such as `{state} {remaining} ({task})`, set per
+/// output under `[format]`. Placeholders take an optional width, e.g.
+/// `{cycles:>3}`, `{remaining_secs:05}` or `{task:<20}`; `{{` and `}}` are
+/// literal braces. Unknown placeholders are rejected when the config is read.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Field(Field, Spec),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    State,
+    Phase,
+    Remaining,
+    FocusRemaining,
+    BreakRemaining,
+    RemainingSecs,
+    Cycle,
+    Cycles,
+    Task,
+    EndsAt,
+    TodayMinutes,
+    FocusMinutes,
+    Now,
+    Muted,
+}
+
+const FIELDS: &[(&str, Field)] = &[
+    ("state", Field::State),
+    ("phase", Field::Phase),
+    ("remaining", Field::Remaining),
+    ("focus_remaining", Field::FocusRemaining),
+    ("break_remaining", Field::BreakRemaining),
+    ("remaining_secs", Field::RemainingSecs),
+    ("cycle", Field::Cycle),
+    ("cycles", Field::Cycles),
+    ("task", Field::Task),
+    ("ends_at", Field::EndsAt),
+    ("today_minutes", Field::TodayMinutes),
+    ("focus_minutes", Field::FocusMinutes),
+    ("now", Field::Now),
+    ("muted", Field::Muted),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Spec {
+    align: Option<Align>,
+    zero: bool,
+    width: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// Everything a template can show.
+pub struct Values<'a> {
+    /// e.g. `Focus` or `Paused (break)`
+    pub state: &'a str,
+    /// `Focus` or `Break`, paused or not
+    pub phase: &'a str,
+    pub remaining_secs: u64,
+    pub focus_remaining_secs: u64,
+    pub break_remaining_secs: u64,
+    /// The cycle the phase belongs to
+    pub cycle: u32,
+    /// Focus sessions completed in this run
+    pub cycles: u32,
+    pub task: Option<&'a str>,
+    /// When the running phase ends; `None` while paused
+    pub ends_at: Option<DateTime<Local>>,
+    /// Focus logged today, by any instance
+    pub today_minutes: u64,
+    /// Focus completed in this run
+    pub focus_minutes: u64,
+    pub muted: bool,
+}
+
+impl TryFrom<String> for Template {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut text_part = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text_part.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text_part.push('}');
+                }
+                '}' => return Err("unmatched `}`; write `}}` for a literal brace".to_string()),
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest.find('}').ok_or("unclosed `{`")?;
+                    let (name, spec) = rest[..end].split_once(':').unwrap_or((&rest[..end], ""));
+                    let &(_, field) = FIELDS
+                        .iter()
+                        .find(|(known, _)| *known == name)
+                        .ok_or_else(|| unknown_placeholder(name))?;
+                    if !text_part.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text_part)));
+                    }
+                    parts.push(Part::Field(field, parse_spec(spec)?));
+                    chars = rest[end + 1..].chars();
+                }
+                c => text_part.push(c),
+            }
+        }
+        if !text_part.is_empty() {
+            parts.push(Part::Text(text_part));
+        }
+        Ok(Self { parts })
+    }
+}
+
+impl Template {
+    /// What `pomo status` prints without `--status-format` or `[format] status`,
+    /// e.g. `Focus 12:34 cycle 3`.
+    pub fn default_status() -> Self {
+        Self::try_from("{state} {remaining} cycle {cycle}".to_string()).expect("the default status template is valid")
+    }
+
+    /// For `--status-format`: unknown placeholders are rejected with the
+    /// other arguments, before anything runs.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        Self::try_from(text.to_string())
+    }
+
+    pub fn render(&self, values: &Values) -> String {
+        let mut line = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => line.push_str(text),
+                Part::Field(field, spec) => line.push_str(&spec.pad(value(*field, values), is_number(*field))),
+            }
+        }
+        line
+    }
+}
+
+fn value(field: Field, values: &Values) -> String {
+    match field {
+        Field::State => values.state.to_string(),
+        Field::Phase => values.phase.to_string(),
+        Field::Remaining => PomodoroTimer::format_time(values.remaining_secs),
+        Field::FocusRemaining => PomodoroTimer::format_time(values.focus_remaining_secs),
+        Field::BreakRemaining => PomodoroTimer::format_time(values.break_remaining_secs),
+        Field::RemainingSecs => values.remaining_secs.to_string(),
+        Field::Cycle => values.cycle.to_string(),
+        Field::Cycles => values.cycles.to_string(),
+        Field::Task => values.task.unwrap_or_default().to_string(),
+        Field::EndsAt => values.ends_at.map(|at| at.format("%H:%M").to_string()).unwrap_or_default(),
+        Field::TodayMinutes => values.today_minutes.to_string(),
+        Field::FocusMinutes => values.focus_minutes.to_string(),
+        Field::Now => Local::now().format("%H:%M").to_string(),
+        Field::Muted => if values.muted { "🔇" } else { "" }.to_string(),
+    }
+}
+
+/// Numbers line up on the right by default, like Rust's `format!`.
+fn is_number(field: Field) -> bool {
+    matches!(
+        field,
+        Field::RemainingSecs | Field::Cycle | Field::Cycles | Field::TodayMinutes | Field::FocusMinutes
+    )
+}
+
+impl Spec {
+    fn pad(self, value: String, number: bool) -> String {
+        let fill = self.width.saturating_sub(value.chars().count());
+        if fill == 0 {
+            return value;
+        }
+        if self.zero {
+            return "0".repeat(fill) + &value;
+        }
+        let default = if number { Align::Right } else { Align::Left };
+        let (before, after) = match self.align.unwrap_or(default) {
+            Align::Left => (0, fill),
+            Align::Right => (fill, 0),
+            Align::Center => (fill / 2, fill - fill / 2),
+        };
+        format!("{}{}{}", " ".repeat(before), value, " ".repeat(after))
+    }
+}
+
+/// Wider than any status line, and small enough that a typo in the
+/// config cannot make every render allocate a huge string.
+const MAX_WIDTH: usize = 256;
+
+/// `[<|>|^][0][width]`, e.g. `>8` or `03`.
+fn parse_spec(spec: &str) -> Result<Spec, String> {
+    let invalid = || format!("bad format `:{}`, expected e.g. `:>8` or `:03`", spec);
+    let (align, rest) = match spec.chars().next() {
+        Some('<') => (Some(Align::Left), &spec[1..]),
+        Some('>') => (Some(Align::Right), &spec[1..]),
+        Some('^') => (Some(Align::Center), &spec[1..]),
+        _ => (None, spec),
+    };
+    let (zero, width) = match rest.strip_prefix('0') {
+        Some(width) if !width.is_empty() => (true, width),
+        _ => (false, rest),
+    };
+    let width = if width.is_empty() { 0 } else { width.parse().map_err(|_| invalid())? };
+    if width > MAX_WIDTH {
+        return Err(format!("width {} in `:{}` is too wide, the most is {}", width, spec, MAX_WIDTH));
+    }
+    Ok(Spec { align, zero, width })
+}
+
+fn unknown_placeholder(name: &str) -> String {
+    let known: Vec<String> = FIELDS.iter().map(|(name, _)| format!("{{{}}}", name)).collect();
+    format!("unknown placeholder `{{{}}}`, expected one of {}", name, known.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_a_width_past_the_limit() {
+        assert!(Template::parse("{task:<256}").is_ok());
+        let error = Template::parse("{task:<999999999}").unwrap_err();
+        assert!(error.contains("too wide"), "{}", error);
+    }
+}
@@ -0,0 +1,148 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::{
+    layout::{Position, Rect},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+/// What a key did to a `TextArea`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Editing,
+    /// Ctrl+S or Ctrl+D
+    Save,
+    /// Esc
+    Cancel,
+}
+
+/// Multi-line text input: Enter starts a new line, the arrow keys move
+/// the cursor, and long lines wrap when drawn. The single-line inputs
+/// elsewhere are a plain `String`; this is for answers longer than a note.
+#[derive(Debug)]
+pub struct TextArea {
+    lines: Vec<String>,
+    /// Cursor line, and position in it in characters
+    row: usize,
+    column: usize,
+}
+
+impl Default for TextArea {
+    fn default() -> Self {
+        Self {
+            lines: vec![String::new()],
+            row: 0,
+            column: 0,
+        }
+    }
+}
+
+impl TextArea {
+    pub fn handle(&mut self, key: KeyCode, modifiers: KeyModifiers) -> Outcome {
+        if modifiers.contains(KeyModifiers::CONTROL) {
+            return match key {
+                KeyCode::Char('s' | 'd') => Outcome::Save,
+                _ => Outcome::Editing,
+            };
+        }
+        match key {
+            KeyCode::Esc => return Outcome::Cancel,
+            KeyCode::Char(c) => {
+                let at = self.byte_index();
+                self.lines[self.row].insert(at, c);
+                self.column += 1;
+            }
+            KeyCode::Enter => {
+                let at = self.byte_index();
+                let rest = self.lines[self.row].split_off(at);
+                self.row += 1;
+                self.lines.insert(self.row, rest);
+                self.column = 0;
+            }
+            KeyCode::Backspace if self.column > 0 => {
+                self.column -= 1;
+                let at = self.byte_index();
+                self.lines[self.row].remove(at);
+            }
+            KeyCode::Backspace if self.row > 0 => {
+                let line = self.lines.remove(self.row);
+                self.row -= 1;
+                self.column = self.line_len();
+                self.lines[self.row].push_str(&line);
+            }
+            KeyCode::Left if self.column > 0 => self.column -= 1,
+            KeyCode::Left if self.row > 0 => {
+                self.row -= 1;
+                self.column = self.line_len();
+            }
+            KeyCode::Right if self.column < self.line_len() => self.column += 1,
+            KeyCode::Right if self.row + 1 < self.lines.len() => {
+                self.row += 1;
+                self.column = 0;
+            }
+            KeyCode::Up if self.row > 0 => {
+                self.row -= 1;
+                self.column = self.column.min(self.line_len());
+            }
+            KeyCode::Down if self.row + 1 < self.lines.len() => {
+                self.row += 1;
+                self.column = self.column.min(self.line_len());
+            }
+            KeyCode::Home => self.column = 0,
+            KeyCode::End => self.column = self.line_len(),
+            _ => {}
+        }
+        Outcome::Editing
+    }
+
+    /// The text with surrounding blank lines and trailing spaces removed.
+    pub fn text(&self) -> String {
+        let lines: Vec<&str> = self.lines.iter().map(|line| line.trim_end()).collect();
+        lines.join("\n").trim_matches('\n').to_string()
+    }
+
+    /// A bordered box over whatever is in `area`, with the cursor placed
+    /// in it. Scrolls to keep the cursor in view.
+    pub fn render(&self, f: &mut Frame, area: Rect, title: &str, hint: &str) {
+        let block = Block::default()
+            .title(title.to_string())
+            .title_bottom(Line::from(hint.to_string()).style(Style::default().fg(Color::DarkGray)))
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(area);
+        let width = inner.width.max(1) as usize;
+
+        // Wrap every line at the box's width, noting where the cursor lands
+        let mut rows: Vec<String> = Vec::new();
+        let mut cursor = (0, 0);
+        for (index, line) in self.lines.iter().enumerate() {
+            let chars: Vec<char> = line.chars().collect();
+            if index == self.row {
+                cursor = (rows.len() + self.column / width, self.column % width);
+            }
+            if chars.is_empty() {
+                rows.push(String::new());
+            }
+            rows.extend(chars.chunks(width).map(|chunk| chunk.iter().collect::<String>()));
+        }
+        let top = cursor.0.saturating_sub((inner.height as usize).saturating_sub(1));
+        let visible: Vec<Line> = rows.into_iter().skip(top).take(inner.height as usize).map(Line::from).collect();
+
+        f.render_widget(Clear, area);
+        f.render_widget(Paragraph::new(visible).style(Style::default().fg(Color::White)).block(block), area);
+        f.set_cursor_position(Position::new(
+            inner.x + cursor.1 as u16,
+            inner.y + (cursor.0 - top).min(inner.height.saturating_sub(1) as usize) as u16,
+        ));
+    }
+
+    fn line_len(&self) -> usize {
+        self.lines[self.row].chars().count()
+    }
+
+    fn byte_index(&self) -> usize {
+        let line = &self.lines[self.row];
+        line.char_indices().nth(self.column).map_or(line.len(), |(index, _)| index)
+    }
+}
@@ -0,0 +1,79 @@
+use crate::{config::WebhookConfig, Transition};
+use serde::Serialize;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    event: &'a str,
+    cycles: u32,
+    task: Option<&'a str>,
+    at: String,
+}
+
+/// Posts a JSON event to the configured URL on every phase transition.
+pub struct Webhook {
+    url: String,
+    headers: Vec<(String, String)>,
+    agent: ureq::Agent,
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+impl Webhook {
+    pub fn new(config: &WebhookConfig) -> Self {
+        Self {
+            url: config.url.clone(),
+            headers: config
+                .headers
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+            last_error: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Send the event in the background, retrying once before giving up.
+    pub fn notify(&self, transition: Transition, cycles: u32, task: Option<&str>) {
+        let body = serde_json::to_string(&Payload {
+            event: transition.event_name(),
+            cycles,
+            task,
+            at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        })
+        .expect("webhook payload is always serializable");
+
+        let url = self.url.clone();
+        let headers = self.headers.clone();
+        let agent = self.agent.clone();
+        let last_error = Arc::clone(&self.last_error);
+
+        tokio::task::spawn_blocking(move || {
+            let send = || {
+                let mut request = agent.post(&url).set("Content-Type", "application/json");
+                for (name, value) in &headers {
+                    request = request.set(name, value);
+                }
+                request.send_string(&body).map_err(|e| e.to_string())
+            };
+
+            let result = send().or_else(|_| {
+                std::thread::sleep(RETRY_DELAY);
+                send()
+            });
+
+            // Only the latest outcome is kept so a dead endpoint shows one warning
+            *last_error.lock().unwrap() = result.err().map(|e| format!("webhook failed: {}", e));
+        });
+    }
+
+    /// The most recent delivery failure, cleared again by the next success.
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().unwrap().clone()
+    }
+}
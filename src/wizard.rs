@@ -0,0 +1,307 @@
+use crate::{
+    audio::{Audio, Overlap, Volume},
+    icons::IconSet,
+    sound::{Sound, SoundTheme},
+    Transition,
+};
+use clap::ValueEnum;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Alignment, Constraint, Flex, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph},
+    Frame, Terminal,
+};
+use std::{fs, io, path::Path};
+use toml_edit::DocumentMut;
+
+/// Name, focus and break minutes.
+const PRESETS: [(&str, u64, u64); 2] = [("Classic", 25, 5), ("Deep", 50, 10)];
+const MAX_MINUTES: u64 = 180;
+
+/// What the setup wizard collected.
+pub struct Choices {
+    pub focus: u64,
+    pub break_time: u64,
+    pub sound: bool,
+    pub theme: SoundTheme,
+}
+
+impl Choices {
+    /// Write the choices into the config file, keeping any other settings
+    /// already in it, and its comments and layout.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut config: DocumentMut = match fs::read_to_string(path) {
+            Ok(contents) => contents.parse().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => DocumentMut::new(),
+            Err(e) => return Err(e),
+        };
+
+        let theme = self.theme.to_possible_value().expect("no hidden themes");
+        set(&mut config, "timer", "focus", (self.focus as i64).into())?;
+        set(&mut config, "timer", "break", (self.break_time as i64).into())?;
+        set(&mut config, "sound", "enabled", self.sound.into())?;
+        set(&mut config, "sound", "theme", theme.get_name().into())?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, config.to_string())
+    }
+}
+
+/// Replaces only the value, so a comment after it on the line stays.
+fn set(config: &mut DocumentMut, section: &str, key: &str, value: toml_edit::Value) -> io::Result<()> {
+    let table = config
+        .entry(section)
+        .or_insert_with(toml_edit::table)
+        .as_table_like_mut()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("`{}` is not a table", section)))?;
+    match table.get_mut(key).and_then(|item| item.as_value_mut()) {
+        Some(old) => {
+            let decor = old.decor().clone();
+            *old = value;
+            *old.decor_mut() = decor;
+        }
+        None => {
+            table.insert(key, toml_edit::value(value));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step {
+    Preset,
+    Focus,
+    Break,
+    Sound,
+    Theme,
+}
+
+struct Wizard {
+    step: Step,
+    cursor: usize,
+    choices: Choices,
+}
+
+impl Wizard {
+    fn new() -> Self {
+        let (_, focus, break_time) = PRESETS[0];
+        Self {
+            step: Step::Preset,
+            cursor: 0,
+            choices: Choices {
+                focus,
+                break_time,
+                sound: true,
+                theme: SoundTheme::default(),
+            },
+        }
+    }
+
+    fn question(&self) -> String {
+        match self.step {
+            Step::Preset => "How long should your sessions be?".to_string(),
+            Step::Focus => format!("Focus minutes: ◀ {} ▶", self.choices.focus),
+            Step::Break => format!("Break minutes: ◀ {} ▶", self.choices.break_time),
+            Step::Sound => "Play a sound when a phase ends?".to_string(),
+            Step::Theme => "Pick a sound (each one plays as you select it)".to_string(),
+        }
+    }
+
+    fn options(&self) -> Vec<String> {
+        match self.step {
+            Step::Preset => PRESETS
+                .iter()
+                .map(|(name, focus, break_time)| format!("{:<8} {} min focus, {} min break", name, focus, break_time))
+                .chain(["Custom".to_string()])
+                .collect(),
+            Step::Focus | Step::Break => Vec::new(),
+            Step::Sound => vec!["On".to_string(), "Off".to_string()],
+            Step::Theme => SoundTheme::value_variants()
+                .iter()
+                .map(|theme| {
+                    let name = theme.to_possible_value().expect("no hidden themes");
+                    format!("{:<8} {}", name.get_name(), name.get_help().unwrap_or_default())
+                })
+                .collect(),
+        }
+    }
+
+    fn move_cursor(&mut self, down: bool) {
+        let count = self.options().len();
+        if count == 0 {
+            return;
+        }
+        self.cursor = if down {
+            (self.cursor + 1) % count
+        } else {
+            (self.cursor + count - 1) % count
+        };
+    }
+
+    /// Change the number on the custom duration steps.
+    fn adjust(&mut self, increase: bool) {
+        let minutes = match self.step {
+            Step::Focus => &mut self.choices.focus,
+            Step::Break => &mut self.choices.break_time,
+            _ => return,
+        };
+        *minutes = if increase { *minutes + 1 } else { minutes.saturating_sub(1) }.clamp(1, MAX_MINUTES);
+    }
+
+    /// Take the current answer and move on; returns true after the last step.
+    fn confirm(&mut self) -> bool {
+        let next = match self.step {
+            Step::Preset => match PRESETS.get(self.cursor) {
+                Some(&(_, focus, break_time)) => {
+                    self.choices.focus = focus;
+                    self.choices.break_time = break_time;
+                    Step::Sound
+                }
+                None => Step::Focus,
+            },
+            Step::Focus => Step::Break,
+            Step::Break => Step::Sound,
+            Step::Sound => {
+                self.choices.sound = self.cursor == 0;
+                if !self.choices.sound {
+                    return true;
+                }
+                Step::Theme
+            }
+            Step::Theme => {
+                self.choices.theme = SoundTheme::value_variants()[self.cursor];
+                return true;
+            }
+        };
+        self.step = next;
+        self.cursor = 0;
+        false
+    }
+
+    fn selected_theme(&self) -> Option<SoundTheme> {
+        (self.step == Step::Theme).then(|| SoundTheme::value_variants()[self.cursor])
+    }
+}
+
+/// Run the wizard in its own screen. `None` means it was skipped with Esc.
+pub fn run() -> io::Result<Option<Choices>> {
+    let audio = Audio::start(None, Volume::default());
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let result = run_steps(&mut terminal, &audio);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+fn run_steps(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, audio: &Audio) -> io::Result<Option<Choices>> {
+    let mut wizard = Wizard::new();
+    loop {
+        terminal.draw(|f| draw(f, &wizard))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let previous_theme = wizard.selected_theme();
+        match key.code {
+            KeyCode::Esc => return Ok(None),
+            KeyCode::Enter if wizard.confirm() => return Ok(Some(wizard.choices)),
+            KeyCode::Up | KeyCode::Char('k') => wizard.move_cursor(false),
+            KeyCode::Down | KeyCode::Char('j') => wizard.move_cursor(true),
+            KeyCode::Left | KeyCode::Char('-') => wizard.adjust(false),
+            KeyCode::Right | KeyCode::Char('+') => wizard.adjust(true),
+            _ => {}
+        }
+
+        // Live preview whenever a different theme is highlighted
+        if let Some(theme) = wizard.selected_theme()
+            && previous_theme != Some(theme)
+        {
+            // Moving on quickly cuts the previous preview short
+            let preview = Sound {
+                theme,
+                overlap: Overlap::Cancel,
+                ..Sound::default()
+            };
+            audio.play_notification(&preview, Transition::FocusEnd);
+        }
+    }
+}
+
+fn draw(f: &mut Frame, wizard: &Wizard) {
+    let options = wizard.options();
+    let height = options.len() as u16 + 7;
+    let [area] = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center).areas(f.area());
+    let [area] = Layout::horizontal([Constraint::Length(60)]).flex(Flex::Center).areas(area);
+
+    let mut lines = vec![Line::from(wizard.question()), Line::from("")];
+    for (i, option) in options.iter().enumerate() {
+        lines.push(if i == wizard.cursor {
+            Line::from(Span::styled(
+                format!("▶ {}", option),
+                Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+            ))
+        } else {
+            Line::from(format!("  {}", option))
+        });
+    }
+    let hint = match wizard.step {
+        Step::Focus | Step::Break => " ◀/▶ change · Enter next · Esc skip setup ",
+        _ => " ↑/↓ choose · Enter next · Esc skip setup ",
+    };
+
+    let block = Block::default()
+        .title(format!(" {} Welcome to pomo ", IconSet::detect().icons().tomato))
+        .title_bottom(Line::from(hint).centered())
+        .borders(Borders::ALL)
+        .style(Style::default().fg(Color::Cyan));
+    let paragraph = Paragraph::new(lines)
+        .alignment(Alignment::Left)
+        .block(block.padding(Padding::uniform(1)));
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saving_keeps_comments_and_other_settings() {
+        let path = std::env::temp_dir().join(format!("pomo-wizard-{}.toml", std::process::id()));
+        let before = "# my pomo setup\n[timer]\nfocus = 25  # the classic\ndaily_goal = 8\n\n[ui]\n# clock = true\n";
+        fs::write(&path, before).unwrap();
+        let choices = Choices {
+            focus: 50,
+            break_time: 10,
+            sound: true,
+            theme: SoundTheme::Bell,
+        };
+        choices.save(&path).unwrap();
+        let after = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        for kept in ["# my pomo setup", "focus = 50  # the classic", "daily_goal = 8", "# clock = true"] {
+            assert!(after.contains(kept), "`{}` missing from:\n{}", kept, after);
+        }
+        let config: crate::config::Config = toml::from_str(&after).unwrap();
+        assert_eq!((config.sound.enabled, config.sound.theme), (true, Some(SoundTheme::Bell)));
+    }
+}